@@ -0,0 +1,411 @@
+//! Command line control of LightDM, covering the `dm-tool` commands plus a dump of what a
+//! greeter on this host would see.
+
+use gio::DBusConnection;
+use lightdm::{
+    dbus::{self, DisplayManager, Seat, Session},
+    os_release::OsRelease,
+    power::{self, PowerManager},
+    snapshot,
+};
+use serde_json::{Value, json};
+
+use std::{
+    error::Error,
+    io::{BufRead, BufReader},
+    process::{Command, ExitCode, Stdio},
+};
+
+const USAGE: &str = "\
+Usage:
+  lightdm-ctl [OPTION...] COMMAND [ARGS...]
+
+Options:
+  -h, --help                     Show help options
+  --session-bus                  Use session D-Bus
+  --json                         Print results as JSON
+
+Commands:
+  switch-to-greeter                       Switch to the greeter
+  switch-to-user USERNAME [SESSION]       Switch to a user session
+  switch-to-guest [SESSION]               Switch to a guest session
+  lock                                    Lock the current seat
+  list-seats                              List the active seats
+  add-nested-seat [--fullscreen|--screen DIMENSIONS]
+                                          Start a nested display
+  add-local-x-seat DISPLAY_NUMBER         Add a local X seat
+  add-seat TYPE [NAME=VALUE...]           Add a dynamic seat
+
+Greeter view:
+  sessions                                List the available sessions
+  remote-sessions                         List the available remote sessions
+  languages                               List the available languages
+  layouts                                 List the available keyboard layouts
+  power                                   Show the power actions allowed
+  os-release                              Show the operating system
+  greeter-info                            Show all of the above";
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// A command line which cannot be run; printed together with the usage.
+#[derive(Debug)]
+struct UsageError(String);
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for UsageError {}
+
+fn usage_error<T>(message: impl Into<String>) -> Result<T> {
+    Err(Box::new(UsageError(message.into())))
+}
+
+struct Options {
+    session_bus: bool,
+    json: bool,
+}
+
+impl Options {
+    fn connection(&self) -> Result<DBusConnection> {
+        let bus_type = if self.session_bus {
+            gio::BusType::Session
+        } else {
+            gio::BusType::System
+        };
+        Ok(gio::bus_get_sync(bus_type, gio::Cancellable::NONE)?)
+    }
+
+    fn current_seat(&self) -> Result<Seat> {
+        let Some(path) = dbus::current_seat_path() else {
+            return Err("Not running inside a display manager, XDG_SEAT_PATH not defined".into());
+        };
+        Ok(Seat::new_sync(&self.connection()?, &path)?)
+    }
+
+    fn print(&self, value: Value, text: impl FnOnce() -> String) -> Result<()> {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        } else {
+            let text = text();
+            if !text.is_empty() {
+                println!("{text}");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn main() -> ExitCode {
+    let mut options = Options {
+        session_bus: false,
+        json: false,
+    };
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next_if(|arg| arg.starts_with('-')) {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            "--session-bus" => options.session_bus = true,
+            "--json" => options.json = true,
+            _ => {
+                eprintln!("Unknown option {arg}\n\n{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    let Some(command) = args.next() else {
+        eprintln!("Missing command\n\n{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    let args = args.collect::<Vec<_>>();
+
+    match run(&options, &command, &args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) if err.is::<UsageError>() => {
+            eprintln!("{err}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options, command: &str, args: &[String]) -> Result<()> {
+    match (command, args) {
+        ("switch-to-greeter", []) => Ok(options.current_seat()?.switch_to_greeter_sync()?),
+        ("switch-to-user", [username]) => Ok(options
+            .current_seat()?
+            .switch_to_user_sync(username, None)?),
+        ("switch-to-user", [username, session]) => Ok(options
+            .current_seat()?
+            .switch_to_user_sync(username, Some(session))?),
+        ("switch-to-guest", []) => Ok(options.current_seat()?.switch_to_guest_sync(None)?),
+        ("switch-to-guest", [session]) => Ok(options
+            .current_seat()?
+            .switch_to_guest_sync(Some(session))?),
+        ("lock", []) => Ok(options.current_seat()?.lock_sync()?),
+        ("list-seats", []) => list_seats(options),
+        ("add-nested-seat", args) => add_nested_seat(options, args),
+        ("add-local-x-seat", [display_number]) => {
+            let Ok(display_number) = display_number.parse() else {
+                return usage_error(format!("Invalid display number {display_number}"));
+            };
+            let display_manager = DisplayManager::new_sync(&options.connection()?)?;
+            print_seat(
+                options,
+                &display_manager.add_local_x_seat_sync(display_number)?,
+            )
+        }
+        ("add-seat", [seat_type, properties @ ..]) => {
+            let properties = properties
+                .iter()
+                .map(|property| {
+                    property
+                        .split_once('=')
+                        .ok_or_else(|| UsageError(format!("Invalid property {property}")))
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let display_manager = DisplayManager::new_sync(&options.connection()?)?;
+            print_seat(
+                options,
+                &display_manager.add_seat_sync(seat_type, &properties)?,
+            )
+        }
+        ("sessions", []) => print_sessions(options, snapshot::sessions()),
+        ("remote-sessions", []) => print_sessions(options, snapshot::remote_sessions()),
+        ("languages", []) => print_languages(options),
+        ("layouts", []) => print_layouts(options),
+        ("power", []) => {
+            let power = power();
+            options.print(power.clone(), || format_fields(&power))
+        }
+        ("os-release", []) => {
+            let os_release = os_release();
+            options.print(os_release.clone(), || format_fields(&os_release))
+        }
+        ("greeter-info", []) => greeter_info(options),
+        (
+            "switch-to-greeter" | "switch-to-user" | "switch-to-guest" | "lock" | "list-seats"
+            | "add-local-x-seat" | "add-seat" | "sessions" | "remote-sessions" | "languages"
+            | "layouts" | "power" | "os-release" | "greeter-info",
+            _,
+        ) => usage_error(format!("Wrong arguments for {command}")),
+        _ => usage_error(format!("Unknown command {command}")),
+    }
+}
+
+/// Object paths relative to the display manager, as `dm-tool` prints them.
+fn short_path(path: &str) -> &str {
+    path.strip_prefix(dbus::OBJECT_PATH)
+        .and_then(|path| path.strip_prefix('/'))
+        .unwrap_or(path)
+}
+
+fn print_seat(options: &Options, path: &str) -> Result<()> {
+    options.print(json!({ "seat": path }), || short_path(path).to_owned())
+}
+
+fn list_seats(options: &Options) -> Result<()> {
+    let connection = options.connection()?;
+    let display_manager = DisplayManager::new_sync(&connection)?;
+    let mut seats = Vec::new();
+    for path in display_manager.seats() {
+        let seat = Seat::new_sync(&connection, &path)?;
+        let mut sessions = Vec::new();
+        for path in seat.sessions() {
+            let session = Session::new_sync(&connection, &path)?;
+            sessions.push(json!({ "path": path, "user_name": session.user_name() }));
+        }
+        seats.push(json!({
+            "path": path,
+            "can_switch": seat.can_switch(),
+            "has_guest_account": seat.has_guest_account(),
+            "sessions": sessions,
+        }));
+    }
+
+    options.print(Value::Array(seats.clone()), || {
+        let mut lines = Vec::new();
+        for seat in &seats {
+            lines.push(short_path(seat["path"].as_str().unwrap_or_default()).to_owned());
+            lines.push(format!("  CanSwitch={}", seat["can_switch"]));
+            lines.push(format!("  HasGuestAccount={}", seat["has_guest_account"]));
+            for session in seat["sessions"].as_array().into_iter().flatten() {
+                lines.push(format!(
+                    "  {}",
+                    short_path(session["path"].as_str().unwrap_or_default())
+                ));
+                lines.push(format!(
+                    "    UserName='{}'",
+                    session["user_name"].as_str().unwrap_or_default()
+                ));
+            }
+        }
+        lines.join("\n")
+    })
+}
+
+/// Starts Xephyr on a free display and adds a seat for it.
+fn add_nested_seat(options: &Options, args: &[String]) -> Result<()> {
+    let mut xephyr = Command::new("Xephyr");
+    xephyr.args(["-displayfd", "1", "-ac", "-br", "-noreset"]);
+    match args {
+        [] => {}
+        [flag] if flag == "--fullscreen" => {
+            xephyr.arg("-fullscreen");
+        }
+        [flag, dimensions] if flag == "--screen" => {
+            xephyr.args(["-screen", dimensions]);
+        }
+        _ => return usage_error("Wrong arguments for add-nested-seat"),
+    }
+
+    let mut child = xephyr
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("Unable to start Xephyr: {err}"))?;
+    let mut display_number = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut display_number)?;
+    let Ok(display_number) = display_number.trim().parse() else {
+        let _ = child.kill();
+        return Err("Xephyr did not report a display number".into());
+    };
+
+    let display_manager = DisplayManager::new_sync(&options.connection()?)?;
+    match display_manager.add_local_x_seat_sync(display_number) {
+        Ok(path) => print_seat(options, &path),
+        Err(err) => {
+            let _ = child.kill();
+            Err(err.into())
+        }
+    }
+}
+
+fn print_sessions(options: &Options, sessions: Vec<snapshot::SessionInfo>) -> Result<()> {
+    options.print(serde_json::to_value(&sessions)?, || {
+        sessions
+            .iter()
+            .map(|session| {
+                format!(
+                    "{}\t{}\t{}",
+                    session.key.as_deref().unwrap_or_default(),
+                    session.session_type.as_ref().map_or("", |ty| ty.as_str()),
+                    session.name.as_deref().unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+fn print_languages(options: &Options) -> Result<()> {
+    let languages = snapshot::languages();
+    options.print(serde_json::to_value(&languages)?, || {
+        languages
+            .iter()
+            .map(|language| {
+                format!(
+                    "{}\t{}",
+                    language.code.as_deref().unwrap_or_default(),
+                    language.name.as_deref().unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+fn print_layouts(options: &Options) -> Result<()> {
+    let layouts = snapshot::layouts();
+    options.print(serde_json::to_value(&layouts)?, || {
+        layouts
+            .iter()
+            .map(|layout| {
+                format!(
+                    "{}\t{}",
+                    layout.name.as_deref().unwrap_or_default(),
+                    layout.description.as_deref().unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+fn power() -> Value {
+    let manager: &dyn PowerManager = &power::LibLightDm;
+    json!({
+        "can_suspend": manager.can_suspend(),
+        "can_hibernate": manager.can_hibernate(),
+        "can_restart": manager.can_restart(),
+        "can_shutdown": manager.can_shutdown(),
+    })
+}
+
+fn os_release() -> Value {
+    let os = OsRelease::load().unwrap_or_default();
+    json!({
+        "id": os.id(),
+        "name": os.name(),
+        "pretty_name": os.pretty_name(),
+        "version": os.version(),
+        "version_id": os.version_id(),
+        "variant": os.variant(),
+        "build_id": os.build_id(),
+        "image_version": os.image_version(),
+        "logo": os.logo(),
+        "ansi_color": os.ansi_color(),
+        "home_url": os.home_url(),
+        "support_url": os.support_url(),
+    })
+}
+
+/// `key=value` lines for a flat JSON object, leaving out null values.
+fn format_fields(value: &Value) -> String {
+    value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| match value {
+            Value::String(value) => format!("{key}={value}"),
+            value => format!("{key}={value}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn greeter_info(options: &Options) -> Result<()> {
+    if options.json {
+        return options.print(
+            json!({
+                "sessions": snapshot::sessions(),
+                "remote_sessions": snapshot::remote_sessions(),
+                "languages": snapshot::languages(),
+                "layouts": snapshot::layouts(),
+                "power": power(),
+                "os_release": os_release(),
+            }),
+            String::new,
+        );
+    }
+
+    println!("[sessions]");
+    print_sessions(options, snapshot::sessions())?;
+    println!("\n[remote-sessions]");
+    print_sessions(options, snapshot::remote_sessions())?;
+    println!("\n[languages]");
+    print_languages(options)?;
+    println!("\n[layouts]");
+    print_layouts(options)?;
+    println!("\n[power]\n{}", format_fields(&power()));
+    println!("\n[os-release]\n{}", format_fields(&os_release()));
+    Ok(())
+}
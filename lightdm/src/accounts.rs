@@ -0,0 +1,356 @@
+//! Client for the `org.freedesktop.Accounts` service of AccountsService, for the user details
+//! `User` does not have: account type, last login, password mode, email and lock state.
+//!
+//! Like the proxies of [`dbus`](crate::dbus), [`AccountsUser`] reads properties from the proxy
+//! cache. AccountsService announces changes with a `Changed` signal rather than
+//! `PropertiesChanged`, so [`AccountsUser::connect_changed`] reloads the properties before
+//! calling back. Both need the thread-default main context of the creating thread to run.
+
+use gio::{DBusCallFlags, DBusConnection, DBusProxy, DBusProxyFlags, prelude::*};
+use glib::{SignalHandlerId, Variant, VariantTy};
+
+use std::{
+    boxed::Box as Box_,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "liblightdm")]
+use glib::object::IsA;
+
+use crate::{
+    Error,
+    dbus::{CallFuture, call_future, call_sync, object_path_reply, unexpected_reply},
+};
+#[cfg(feature = "liblightdm")]
+use crate::{User, prelude::UserExt};
+
+/// Well-known bus name of AccountsService.
+pub const ACCOUNTS_BUS_NAME: &str = "org.freedesktop.Accounts";
+/// Object path of the [`Accounts`] object.
+pub const ACCOUNTS_OBJECT_PATH: &str = "/org/freedesktop/Accounts";
+
+const ACCOUNTS_INTERFACE: &str = "org.freedesktop.Accounts";
+const USER_INTERFACE: &str = "org.freedesktop.Accounts.User";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountType {
+    Standard,
+    Administrator,
+    Unknown(i32),
+}
+
+impl From<i32> for AccountType {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::Standard,
+            1 => Self::Administrator,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+/// How the user logs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PasswordMode {
+    Regular,
+    /// The password has to be chosen at the next login.
+    SetAtLogin,
+    /// No password is needed.
+    None,
+    Unknown(i32),
+}
+
+impl From<i32> for PasswordMode {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::Regular,
+            1 => Self::SetAtLogin,
+            2 => Self::None,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+/// Why the user cannot log in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LockedReason {
+    /// An administrator locked the account.
+    Locked,
+    /// The account expiry date has passed.
+    AccountExpired,
+    /// The password expired and was not changed within the grace period.
+    PasswordExpired,
+}
+
+/// The `GetPasswordExpirationPolicy` reply, in days since the epoch or days, `-1` or `0` when
+/// unset, as in `shadow(5)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordExpirationPolicy {
+    pub expiration_time: i64,
+    pub last_change_time: i64,
+    pub min_days_between_changes: i64,
+    pub max_days_between_changes: i64,
+    pub days_to_warn: i64,
+    pub days_after_expiration_until_lock: i64,
+}
+
+impl PasswordExpirationPolicy {
+    /// Why the account is unusable on day `today` (days since the epoch), if it is.
+    pub fn locked_reason(&self, today: i64) -> Option<LockedReason> {
+        if self.expiration_time > 0 && today >= self.expiration_time {
+            return Some(LockedReason::AccountExpired);
+        }
+        let expires = self.last_change_time > 0 && self.max_days_between_changes >= 0;
+        let lock_day = self.last_change_time
+            + self.max_days_between_changes
+            + self.days_after_expiration_until_lock;
+        (expires && self.days_after_expiration_until_lock >= 0 && today >= lock_day)
+            .then_some(LockedReason::PasswordExpired)
+    }
+}
+
+fn new_proxy_sync(
+    connection: &DBusConnection,
+    path: &str,
+    interface: &str,
+) -> Result<DBusProxy, Error> {
+    Ok(DBusProxy::new_sync(
+        connection,
+        DBusProxyFlags::NONE,
+        None,
+        Some(ACCOUNTS_BUS_NAME),
+        path,
+        interface,
+        gio::Cancellable::NONE,
+    )?)
+}
+
+/// The `org.freedesktop.Accounts` object at [`ACCOUNTS_OBJECT_PATH`].
+#[doc(alias = "org.freedesktop.Accounts")]
+#[derive(Debug, Clone)]
+pub struct Accounts {
+    proxy: DBusProxy,
+}
+
+impl Accounts {
+    pub fn new_sync(connection: &DBusConnection) -> Result<Self, Error> {
+        let proxy = new_proxy_sync(connection, ACCOUNTS_OBJECT_PATH, ACCOUNTS_INTERFACE)?;
+        Ok(Self { proxy })
+    }
+
+    /// Connects to AccountsService on the system bus.
+    pub fn for_system_bus_sync() -> Result<Self, Error> {
+        let connection = gio::bus_get_sync(gio::BusType::System, gio::Cancellable::NONE)?;
+        Self::new_sync(&connection)
+    }
+
+    /// The underlying proxy, for calls this type does not wrap.
+    pub fn proxy(&self) -> &DBusProxy {
+        &self.proxy
+    }
+
+    #[doc(alias = "FindUserById")]
+    pub fn find_user_by_id_sync(&self, uid: u32) -> Result<AccountsUser, Error> {
+        let reply = call_sync(
+            &self.proxy,
+            "FindUserById",
+            Some((i64::from(uid),).to_variant()),
+        )?;
+        AccountsUser::new_sync(
+            &self.proxy.connection(),
+            &object_path_reply("FindUserById", reply)?,
+        )
+    }
+
+    #[doc(alias = "FindUserById")]
+    pub fn find_user_by_id_future(&self, uid: u32) -> CallFuture<AccountsUser> {
+        let connection = self.proxy.connection();
+        let call = call_future(
+            &self.proxy,
+            "FindUserById",
+            Some((i64::from(uid),).to_variant()),
+        );
+        Box_::pin(async move {
+            let path = object_path_reply("FindUserById", call.await?)?;
+            let proxy = DBusProxy::new_future(
+                &connection,
+                DBusProxyFlags::NONE,
+                None,
+                Some(ACCOUNTS_BUS_NAME),
+                &path,
+                USER_INTERFACE,
+            )
+            .await?;
+            Ok(AccountsUser { proxy })
+        })
+    }
+
+    #[doc(alias = "FindUserByName")]
+    pub fn find_user_by_name_sync(&self, name: &str) -> Result<AccountsUser, Error> {
+        let reply = call_sync(&self.proxy, "FindUserByName", Some((name,).to_variant()))?;
+        AccountsUser::new_sync(
+            &self.proxy.connection(),
+            &object_path_reply("FindUserByName", reply)?,
+        )
+    }
+
+    /// The AccountsService user of a LightDM user, looked up by its `uid()`.
+    #[cfg(feature = "liblightdm")]
+    pub fn user_sync(&self, user: &impl IsA<User>) -> Result<AccountsUser, Error> {
+        self.find_user_by_id_sync(user.as_ref().uid())
+    }
+}
+
+/// An `org.freedesktop.Accounts.User` object.
+#[doc(alias = "org.freedesktop.Accounts.User")]
+#[derive(Debug, Clone)]
+pub struct AccountsUser {
+    proxy: DBusProxy,
+}
+
+impl AccountsUser {
+    pub fn new_sync(connection: &DBusConnection, path: &str) -> Result<Self, Error> {
+        let proxy = new_proxy_sync(connection, path, USER_INTERFACE)?;
+        Ok(Self { proxy })
+    }
+
+    fn from_proxy(proxy: &DBusProxy) -> Self {
+        Self {
+            proxy: proxy.clone(),
+        }
+    }
+
+    /// The underlying proxy, for calls this type does not wrap.
+    pub fn proxy(&self) -> &DBusProxy {
+        &self.proxy
+    }
+
+    pub fn object_path(&self) -> glib::GString {
+        self.proxy.object_path()
+    }
+
+    fn property<T: glib::variant::FromVariant>(&self, name: &str) -> Option<T> {
+        self.proxy
+            .cached_property(name)
+            .and_then(|value| value.get())
+    }
+
+    pub fn uid(&self) -> Option<u32> {
+        self.property::<u64>("Uid")
+            .and_then(|uid| u32::try_from(uid).ok())
+    }
+
+    pub fn user_name(&self) -> Option<String> {
+        self.property("UserName")
+    }
+
+    pub fn real_name(&self) -> Option<String> {
+        self.property("RealName")
+    }
+
+    pub fn account_type(&self) -> Option<AccountType> {
+        self.property::<i32>("AccountType").map(AccountType::from)
+    }
+
+    /// The last login, if the user ever logged in.
+    pub fn last_login(&self) -> Option<glib::DateTime> {
+        let time = self.property::<i64>("LoginTime").filter(|time| *time > 0)?;
+        glib::DateTime::from_unix_local(time).ok()
+    }
+
+    pub fn password_mode(&self) -> Option<PasswordMode> {
+        self.property::<i32>("PasswordMode").map(PasswordMode::from)
+    }
+
+    /// The email address, if one is set.
+    pub fn email(&self) -> Option<String> {
+        self.property::<String>("Email")
+            .filter(|email| !email.is_empty())
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.property("Locked").unwrap_or_default()
+    }
+
+    #[doc(alias = "GetPasswordExpirationPolicy")]
+    pub fn password_expiration_policy_sync(&self) -> Result<PasswordExpirationPolicy, Error> {
+        let reply = call_sync(&self.proxy, "GetPasswordExpirationPolicy", None)?;
+        let Some((expiration, last_change, min, max, warn, inactive)) =
+            reply.get::<(i64, i64, i64, i64, i64, i64)>()
+        else {
+            return Err(unexpected_reply("GetPasswordExpirationPolicy", &reply));
+        };
+        Ok(PasswordExpirationPolicy {
+            expiration_time: expiration,
+            last_change_time: last_change,
+            min_days_between_changes: min,
+            max_days_between_changes: max,
+            days_to_warn: warn,
+            days_after_expiration_until_lock: inactive,
+        })
+    }
+
+    /// Why the user cannot log in: locked by an administrator, or expired according to the
+    /// password expiration policy.
+    pub fn locked_reason_sync(&self) -> Result<Option<LockedReason>, Error> {
+        if self.is_locked() {
+            return Ok(Some(LockedReason::Locked));
+        }
+        let today = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs() / (24 * 60 * 60));
+        let today = i64::try_from(today).unwrap_or(i64::MAX);
+        Ok(self.password_expiration_policy_sync()?.locked_reason(today))
+    }
+
+    /// Calls `f` when AccountsService reports a change, once the properties were reloaded.
+    #[doc(alias = "Changed")]
+    pub fn connect_changed<F: Fn(&Self) + Send + Sync + 'static>(&self, f: F) -> SignalHandlerId {
+        let f = Arc::new(f);
+        self.proxy
+            .connect_g_signal(move |proxy, _, signal_name, _| {
+                if signal_name != "Changed" {
+                    return;
+                }
+                let f = f.clone();
+                let proxy = proxy.clone();
+                proxy.connection().call(
+                    proxy.name().as_deref(),
+                    &proxy.object_path(),
+                    "org.freedesktop.DBus.Properties",
+                    "GetAll",
+                    Some(&(USER_INTERFACE,).to_variant()),
+                    VariantTy::new("(a{sv})").ok(),
+                    DBusCallFlags::NONE,
+                    -1,
+                    gio::Cancellable::NONE,
+                    {
+                        let proxy = proxy.clone();
+                        move |reply| {
+                            if let Ok(reply) = reply {
+                                update_cache(&proxy, &reply.child_value(0));
+                            }
+                            f(&Self::from_proxy(&proxy))
+                        }
+                    },
+                );
+            })
+    }
+
+    pub fn disconnect(&self, id: SignalHandlerId) {
+        self.proxy.disconnect(id);
+    }
+}
+
+/// Stores the `a{sv}` of a `GetAll` reply in the proxy cache.
+fn update_cache(proxy: &DBusProxy, properties: &Variant) {
+    for entry in properties.iter() {
+        if let (Some(name), Some(value)) = (
+            entry.child_value(0).str(),
+            entry.child_value(1).as_variant(),
+        ) {
+            proxy.set_cached_property(name, Some(&value));
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Authentication state machine built on top of the [`Greeter`] signals.
+//!
+//! [`Authenticator`] wires `show-prompt`, `show-message`, `authentication-complete` and `reset`
+//! once and keeps track of where the PAM conversation is, so that calls which make no sense in
+//! the current state are refused with an [`Error`] instead of reaching the daemon.
+
+#[cfg(feature = "liblightdm")]
+use glib::{SignalHandlerId, object::ObjectExt};
+
+use std::{cell::RefCell, fmt, rc::Rc};
+
+#[cfg(not(feature = "liblightdm"))]
+use super::native::SignalHandlerId;
+use super::{
+    Greeter, MessageType, PromptType, Secret,
+    prelude::{GreeterExt, GreeterExtManual},
+};
+
+/// Where the conversation with the daemon currently stands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthState {
+    /// No authentication has been started, or the greeter was reset.
+    Idle,
+    /// The daemon asked a question which has not been answered yet.
+    AwaitingResponse(String, PromptType),
+    /// Authentication is in progress and no prompt is pending; holds the messages shown so far.
+    Messages(Vec<(String, MessageType)>),
+    /// Authentication succeeded for the given user.
+    Succeeded(Option<String>),
+    /// Authentication failed for the given user.
+    Failed(Option<String>),
+    /// Authentication was cancelled by the greeter.
+    Cancelled,
+}
+
+impl AuthState {
+    /// Whether a conversation with the daemon is in progress.
+    pub fn is_in_progress(&self) -> bool {
+        matches!(self, Self::AwaitingResponse(..) | Self::Messages(_))
+    }
+}
+
+/// Error returned by [`Authenticator`] methods.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Error {
+    /// `respond` was called while no prompt is pending.
+    NoPendingPrompt,
+    /// `respond` was called for a [`PromptType::Secret`] prompt; use `respond_secret` instead.
+    SecretRequired,
+    /// `cancel` was called while no authentication is in progress.
+    NotInProgress,
+    /// `start_session` was called before authentication succeeded.
+    NotAuthenticated,
+    /// The call was allowed but the greeter reported an error.
+    Greeter(crate::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoPendingPrompt => f.write_str("no prompt is waiting for a response"),
+            Self::SecretRequired => f.write_str("secret prompts must be answered with a Secret"),
+            Self::NotInProgress => f.write_str("no authentication is in progress"),
+            Self::NotAuthenticated => f.write_str("authentication has not succeeded"),
+            Self::Greeter(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Greeter(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<crate::Error> for Error {
+    fn from(err: crate::Error) -> Self {
+        Self::Greeter(err)
+    }
+}
+
+type StateCallback = Rc<dyn Fn(&AuthState)>;
+type SessionCallback = Rc<dyn Fn(Result<(), crate::Error>)>;
+
+struct Inner {
+    greeter: Greeter,
+    state: RefCell<AuthState>,
+    callbacks: RefCell<Vec<StateCallback>>,
+    autostart: RefCell<Option<(Option<String>, SessionCallback)>>,
+    handlers: RefCell<Vec<SignalHandlerId>>,
+}
+
+impl Inner {
+    fn set_state(&self, state: AuthState) {
+        self.state.replace(state.clone());
+        let callbacks = self.callbacks.borrow().clone();
+        for callback in callbacks {
+            callback(&state);
+        }
+        if matches!(state, AuthState::Succeeded(_)) {
+            let autostart = self.autostart.borrow().clone();
+            if let Some((session, callback)) = autostart {
+                self.greeter.start_session(
+                    session.as_deref(),
+                    gio::Cancellable::NONE,
+                    move |res| callback(res),
+                );
+            }
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        for id in self.handlers.take() {
+            self.greeter.disconnect(id);
+        }
+    }
+}
+
+/// Owns a [`Greeter`] and drives its authentication through explicit [`AuthState`]s.
+///
+/// Cloning an `Authenticator` yields another handle to the same state. The signal handlers are
+/// disconnected once the last handle is dropped.
+#[derive(Clone)]
+pub struct Authenticator {
+    inner: Rc<Inner>,
+}
+
+impl Authenticator {
+    pub fn new(greeter: Greeter) -> Self {
+        let inner = Rc::new(Inner {
+            greeter,
+            state: RefCell::new(AuthState::Idle),
+            callbacks: RefCell::default(),
+            autostart: RefCell::default(),
+            handlers: RefCell::default(),
+        });
+
+        let greeter = &inner.greeter;
+        let handlers = vec![
+            greeter.connect_show_prompt({
+                let inner = Rc::downgrade(&inner);
+                move |_, text, prompt_type| {
+                    let Some(inner) = inner.upgrade() else { return };
+                    inner.set_state(AuthState::AwaitingResponse(text.to_owned(), prompt_type));
+                }
+            }),
+            greeter.connect_show_message({
+                let inner = Rc::downgrade(&inner);
+                move |_, text, message_type| {
+                    let Some(inner) = inner.upgrade() else { return };
+                    // PAM only sends messages ahead of its prompts, so a pending prompt is kept.
+                    let state = match &*inner.state.borrow() {
+                        AuthState::Messages(messages) => {
+                            let mut messages = messages.clone();
+                            messages.push((text.to_owned(), message_type));
+                            AuthState::Messages(messages)
+                        }
+                        _ => return,
+                    };
+                    inner.set_state(state);
+                }
+            }),
+            greeter.connect_authentication_complete({
+                let inner = Rc::downgrade(&inner);
+                move |greeter| {
+                    let Some(inner) = inner.upgrade() else { return };
+                    if *inner.state.borrow() == AuthState::Cancelled {
+                        return;
+                    }
+                    let user = greeter.authentication_user().map(String::from);
+                    if greeter.is_authenticated() {
+                        inner.set_state(AuthState::Succeeded(user));
+                    } else {
+                        inner.set_state(AuthState::Failed(user));
+                    }
+                }
+            }),
+            greeter.connect_reset({
+                let inner = Rc::downgrade(&inner);
+                move |_| {
+                    let Some(inner) = inner.upgrade() else { return };
+                    inner.set_state(AuthState::Idle);
+                }
+            }),
+        ];
+        inner.handlers.replace(handlers);
+
+        Self { inner }
+    }
+
+    pub fn greeter(&self) -> &Greeter {
+        &self.inner.greeter
+    }
+
+    pub fn state(&self) -> AuthState {
+        self.inner.state.borrow().clone()
+    }
+
+    /// Calls `f` every time the state changes.
+    pub fn connect_state_changed<F: Fn(&AuthState) + 'static>(&self, f: F) {
+        self.inner.callbacks.borrow_mut().push(Rc::new(f));
+    }
+
+    /// Starts `session` as soon as authentication succeeds, reporting the outcome to `f`.
+    pub fn start_session_on_success<F: Fn(Result<(), crate::Error>) + 'static>(
+        &self,
+        session: Option<&str>,
+        f: F,
+    ) {
+        self.inner
+            .autostart
+            .replace(Some((session.map(ToOwned::to_owned), Rc::new(f))));
+    }
+
+    /// Starts authenticating `username`, or asks the daemon for one when `None`.
+    ///
+    /// Any conversation already in progress is abandoned, as liblightdm does.
+    pub fn authenticate(&self, username: Option<&str>) -> Result<(), Error> {
+        self.inner.greeter.authenticate(username)?;
+        self.inner.set_state(AuthState::Messages(Vec::new()));
+        Ok(())
+    }
+
+    pub fn authenticate_as_guest(&self) -> Result<(), Error> {
+        self.inner.greeter.authenticate_as_guest()?;
+        self.inner.set_state(AuthState::Messages(Vec::new()));
+        Ok(())
+    }
+
+    /// Answers the pending prompt, which must not be a [`PromptType::Secret`] prompt.
+    pub fn respond(&self, response: &str) -> Result<(), Error> {
+        match *self.inner.state.borrow() {
+            AuthState::AwaitingResponse(_, PromptType::Secret) => {
+                return Err(Error::SecretRequired);
+            }
+            AuthState::AwaitingResponse(..) => {}
+            _ => return Err(Error::NoPendingPrompt),
+        }
+        self.inner.greeter.respond_secret(&Secret::from(response))?;
+        self.inner.set_state(AuthState::Messages(Vec::new()));
+        Ok(())
+    }
+
+    /// Answers the pending prompt with a secret that is wiped from memory after use.
+    pub fn respond_secret(&self, response: &Secret) -> Result<(), Error> {
+        if !matches!(*self.inner.state.borrow(), AuthState::AwaitingResponse(..)) {
+            return Err(Error::NoPendingPrompt);
+        }
+        self.inner.greeter.respond_secret(response)?;
+        self.inner.set_state(AuthState::Messages(Vec::new()));
+        Ok(())
+    }
+
+    pub fn cancel(&self) -> Result<(), Error> {
+        if !self.inner.state.borrow().is_in_progress() {
+            return Err(Error::NotInProgress);
+        }
+        self.inner.greeter.cancel_authentication()?;
+        self.inner.set_state(AuthState::Cancelled);
+        Ok(())
+    }
+
+    /// Starts `session` for the authenticated user.
+    pub fn start_session(&self, session: Option<&str>) -> Result<(), Error> {
+        if !matches!(*self.inner.state.borrow(), AuthState::Succeeded(_)) {
+            return Err(Error::NotAuthenticated);
+        }
+        self.inner.greeter.start_session_sync(session)?;
+        Ok(())
+    }
+
+    pub async fn start_session_future(&self, session: Option<&str>) -> Result<(), Error> {
+        if !matches!(*self.inner.state.borrow(), AuthState::Succeeded(_)) {
+            return Err(Error::NotAuthenticated);
+        }
+        self.inner.greeter.start_session_future(session).await?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Authenticator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Authenticator")
+            .field("greeter", &self.inner.greeter)
+            .field("state", &*self.inner.state.borrow())
+            .finish()
+    }
+}
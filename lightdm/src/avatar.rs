@@ -0,0 +1,410 @@
+//! User pictures, resolved through the fallback chain every greeter implements: the
+//! AccountsService icon, `~/.face`, `~/.face.icon`, then a default image.
+//!
+//! [`AvatarLoader::check`] only accepts regular files in the user's home or among their own
+//! pictures, owned by the user or root, no larger than a size limit and in a recognized image
+//! format, so a user cannot make the greeter read another user's files through a `~/.face`
+//! symlink or a huge file.
+
+use std::{
+    error, fmt,
+    fs::{self, OpenOptions},
+    io::{self, Read},
+    os::unix::fs::{MetadataExt, OpenOptionsExt},
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "liblightdm")]
+use glib::{SignalHandlerId, object::IsA, prelude::ObjectExt};
+
+#[cfg(feature = "liblightdm")]
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+#[cfg(feature = "liblightdm")]
+use crate::{User, prelude::UserExt};
+
+/// Where AccountsService keeps the pictures set through it.
+pub const ACCOUNTS_SERVICE_ICONS: &str = "/var/lib/AccountsService/icons";
+
+/// The default [`AvatarLoader::max_size`], 1 MiB.
+pub const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Bmp,
+    Webp,
+    Svg,
+}
+
+impl ImageFormat {
+    /// Recognizes a format from the first bytes of a file.
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        match data {
+            [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', ..] => Some(Self::Png),
+            [0xff, 0xd8, 0xff, ..] => Some(Self::Jpeg),
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(Self::Gif),
+            [b'B', b'M', ..] => Some(Self::Bmp),
+            _ if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") => Some(Self::Webp),
+            _ => {
+                let head = String::from_utf8_lossy(&data[..data.len().min(1024)]);
+                let head = head.trim_start_matches('\u{feff}').trim_start();
+                (head.starts_with('<') && head.contains("<svg")).then_some(Self::Svg)
+            }
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+            Self::Bmp => "image/bmp",
+            Self::Webp => "image/webp",
+            Self::Svg => "image/svg+xml",
+        }
+    }
+}
+
+/// Why a candidate picture was refused.
+#[derive(Debug)]
+pub enum AvatarError {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    NotAFile(PathBuf),
+    /// The file, or the target of a symlink, is neither in the user's home nor one of the
+    /// pictures set for them.
+    Outside(PathBuf),
+    /// The file, or the target of a symlink, belongs to someone else.
+    ForeignOwner {
+        path: PathBuf,
+        owner: u32,
+    },
+    TooLarge {
+        path: PathBuf,
+        size: u64,
+    },
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for AvatarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::NotAFile(path) => write!(f, "{}: not a regular file", path.display()),
+            Self::Outside(path) => {
+                write!(
+                    f,
+                    "{}: outside the user's home and pictures",
+                    path.display()
+                )
+            }
+            Self::ForeignOwner { path, owner } => {
+                write!(f, "{}: owned by another user ({owner})", path.display())
+            }
+            Self::TooLarge { path, size } => {
+                write!(f, "{}: too large ({size} bytes)", path.display())
+            }
+            Self::UnknownFormat(path) => write!(f, "{}: not a known image format", path.display()),
+        }
+    }
+}
+
+impl error::Error for AvatarError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// A picture which passed [`AvatarLoader::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Avatar {
+    path: PathBuf,
+    format: ImageFormat,
+    bytes: glib::Bytes,
+}
+
+impl Avatar {
+    /// The path of the file read, with symlinks resolved.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// The contents, as read when the picture was checked.
+    pub fn bytes(&self) -> &glib::Bytes {
+        &self.bytes
+    }
+
+    pub fn file(&self) -> gio::File {
+        gio::File::for_path(&self.path)
+    }
+}
+
+/// Finds and reads the picture of a user.
+#[derive(Debug, Clone)]
+pub struct AvatarLoader {
+    accounts_service_icons: PathBuf,
+    default_image: Option<PathBuf>,
+    max_size: u64,
+}
+
+impl Default for AvatarLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AvatarLoader {
+    pub fn new() -> Self {
+        Self {
+            accounts_service_icons: PathBuf::from(ACCOUNTS_SERVICE_ICONS),
+            default_image: None,
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+
+    pub fn accounts_service_icons(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.accounts_service_icons = dir.into();
+        self
+    }
+
+    /// The picture of users without one, e.g. the `default-user-image` of the greeter
+    /// configuration.
+    pub fn default_image(mut self, path: impl Into<PathBuf>) -> Self {
+        self.default_image = Some(path.into());
+        self
+    }
+
+    /// The largest file accepted, in bytes.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// The paths tried for user `name`, in order. `image` is the picture liblightdm reports,
+    /// which AccountsService may have set to a file outside its icon directory.
+    pub fn candidates(
+        &self,
+        name: &str,
+        home: Option<&Path>,
+        image: Option<&Path>,
+    ) -> Vec<PathBuf> {
+        let mut candidates = Vec::new();
+        candidates.extend(image.map(Path::to_owned));
+        candidates.push(self.accounts_service_icons.join(name));
+        if let Some(home) = home {
+            candidates.push(home.join(".face"));
+            candidates.push(home.join(".face.icon"));
+        }
+        candidates.extend(self.default_image.clone());
+        candidates.dedup();
+        candidates
+    }
+
+    /// The first of the [`candidates`](Self::candidates) which passes
+    /// [`check`](Self::check).
+    pub fn load(
+        &self,
+        name: &str,
+        uid: u32,
+        home: Option<&Path>,
+        image: Option<&Path>,
+    ) -> Option<Avatar> {
+        self.candidates(name, home, image)
+            .iter()
+            .find_map(|path| self.check(path, name, uid, home, image).ok())
+    }
+
+    /// Reads `path` for user `name` if it resolves to a regular file owned by `uid` or root, no
+    /// larger than [`max_size`](Self::max_size) and in a known [`ImageFormat`].
+    ///
+    /// The file must also be below `home`, or be the AccountsService icon of `name`, the
+    /// default image or `image` itself, so that root-owned pictures of other users are refused.
+    pub fn check(
+        &self,
+        path: &Path,
+        name: &str,
+        uid: u32,
+        home: Option<&Path>,
+        image: Option<&Path>,
+    ) -> Result<Avatar, AvatarError> {
+        let io_error = |source| AvatarError::Io {
+            path: path.to_owned(),
+            source,
+        };
+        let resolved = fs::canonicalize(path).map_err(io_error)?;
+        if !self.is_allowed(&resolved, name, home, image) {
+            return Err(AvatarError::Outside(resolved));
+        }
+        // Non-blocking, so that opening a FIFO does not hang; the checks are made on the open
+        // file, so the path cannot be swapped in between.
+        let mut file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK | libc::O_NOCTTY)
+            .open(&resolved)
+            .map_err(io_error)?;
+        let metadata = file.metadata().map_err(io_error)?;
+        if !metadata.is_file() {
+            return Err(AvatarError::NotAFile(resolved));
+        }
+        if metadata.uid() != uid && metadata.uid() != 0 {
+            return Err(AvatarError::ForeignOwner {
+                path: resolved,
+                owner: metadata.uid(),
+            });
+        }
+        if metadata.len() > self.max_size {
+            return Err(AvatarError::TooLarge {
+                path: resolved,
+                size: metadata.len(),
+            });
+        }
+        let mut data = Vec::new();
+        (&mut file)
+            .take(self.max_size + 1)
+            .read_to_end(&mut data)
+            .map_err(io_error)?;
+        if data.len() as u64 > self.max_size {
+            return Err(AvatarError::TooLarge {
+                path: resolved,
+                size: data.len() as u64,
+            });
+        }
+        let Some(format) = ImageFormat::sniff(&data) else {
+            return Err(AvatarError::UnknownFormat(resolved));
+        };
+        Ok(Avatar {
+            path: resolved,
+            format,
+            bytes: glib::Bytes::from_owned(data),
+        })
+    }
+
+    /// Whether the resolved path `resolved` is a place pictures of `name` may come from. `image`
+    /// is compared as given, so that a `~/.face` symlink reported by liblightdm does not allow
+    /// its target.
+    fn is_allowed(
+        &self,
+        resolved: &Path,
+        name: &str,
+        home: Option<&Path>,
+        image: Option<&Path>,
+    ) -> bool {
+        let canonical = |path: &Path| fs::canonicalize(path).ok();
+        home.and_then(canonical)
+            .is_some_and(|home| resolved.starts_with(home))
+            || canonical(&self.accounts_service_icons)
+                .is_some_and(|icons| resolved == icons.join(name))
+            || self
+                .default_image
+                .as_deref()
+                .and_then(canonical)
+                .is_some_and(|default| resolved == default)
+            || image == Some(resolved)
+    }
+
+    /// The picture of `user`.
+    #[cfg(feature = "liblightdm")]
+    pub fn load_user(&self, user: &impl IsA<User>) -> Option<Avatar> {
+        let user = user.as_ref();
+        let home = user.home_directory();
+        let image = user.image();
+        self.load(
+            user.name().as_deref().unwrap_or_default(),
+            user.uid(),
+            home.as_deref().map(Path::new),
+            image.as_deref().map(Path::new),
+        )
+    }
+}
+
+/// Avatars by user name, each dropped when the `image` property of its user changes.
+///
+/// The users are only weakly referenced, so a cache may be kept on a user itself.
+#[cfg(feature = "liblightdm")]
+#[derive(Debug, Clone)]
+pub struct AvatarCache {
+    inner: Rc<CacheInner>,
+}
+
+#[cfg(feature = "liblightdm")]
+#[derive(Debug)]
+struct CacheInner {
+    loader: AvatarLoader,
+    avatars: RefCell<HashMap<String, Option<Avatar>>>,
+    handlers: RefCell<HashMap<String, (glib::WeakRef<User>, SignalHandlerId)>>,
+}
+
+#[cfg(feature = "liblightdm")]
+impl Drop for CacheInner {
+    fn drop(&mut self) {
+        for (_, (user, handler)) in self.handlers.take() {
+            if let Some(user) = user.upgrade() {
+                user.disconnect(handler);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "liblightdm")]
+impl AvatarCache {
+    pub fn new(loader: AvatarLoader) -> Self {
+        Self {
+            inner: Rc::new(CacheInner {
+                loader,
+                avatars: RefCell::default(),
+                handlers: RefCell::default(),
+            }),
+        }
+    }
+
+    pub fn get(&self, user: &impl IsA<User>) -> Option<Avatar> {
+        let user = user.as_ref();
+        let name = user.name().map(String::from).unwrap_or_default();
+        if let Some(avatar) = self.inner.avatars.borrow().get(&name) {
+            return avatar.clone();
+        }
+        if !self.inner.handlers.borrow().contains_key(&name) {
+            let inner = Rc::downgrade(&self.inner);
+            let handler = user.connect_image_notify({
+                let name = name.clone();
+                move |_| {
+                    if let Some(inner) = Weak::upgrade(&inner) {
+                        inner.avatars.borrow_mut().remove(&name);
+                    }
+                }
+            });
+            self.inner
+                .handlers
+                .borrow_mut()
+                .insert(name.clone(), (user.downgrade(), handler));
+        }
+        let avatar = self.inner.loader.load_user(user);
+        self.inner.avatars.borrow_mut().insert(name, avatar.clone());
+        avatar
+    }
+
+    /// Drops the cached avatar of user `name`, e.g. after its `~/.face` changed.
+    pub fn invalidate(&self, name: &str) {
+        self.inner.avatars.borrow_mut().remove(name);
+    }
+
+    pub fn clear(&self) {
+        self.inner.avatars.borrow_mut().clear();
+    }
+}
@@ -0,0 +1,321 @@
+//! Reader for the configuration of a greeter, e.g. `lightdm-gtk-greeter.conf`.
+//!
+//! [`GreeterConfigLoader`] merges the files in the same order as the daemon's own
+//! configuration, and [`GreeterConfigLoader::watch`] reloads them when they change.
+
+use gio::prelude::*;
+
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+};
+
+use super::{ConfigPaths, Error, Section};
+use crate::{Greeter, keyfile::KeyFile, prelude::*};
+
+/// Loads the configuration of a greeter, e.g. `lightdm-gtk-greeter`.
+///
+/// Files are read in the same order as the daemon's own configuration, each overriding the
+/// previous ones:
+///
+/// 1. `lightdm/<name>.conf.d/*.conf` in the XDG system data dirs
+/// 2. `lightdm/<name>.conf.d/*.conf` in the XDG system config dirs
+/// 3. `/etc/lightdm/<name>.conf.d/*.conf`
+/// 4. `/etc/lightdm/<name>.conf`
+///
+/// Keys no file sets take the defaults given to the loader, which may come from the daemon's
+/// hints.
+#[derive(Debug, Clone)]
+pub struct GreeterConfigLoader {
+    name: String,
+    paths: ConfigPaths,
+    defaults: Vec<(String, String, String)>,
+}
+
+impl GreeterConfigLoader {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            paths: ConfigPaths::default(),
+            defaults: Vec::new(),
+        }
+    }
+
+    pub fn paths(mut self, paths: ConfigPaths) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    pub fn default(mut self, section: &str, key: &str, value: &str) -> Self {
+        self.defaults
+            .push((section.to_owned(), key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Uses the value of `hint` as the default of `key`, if the daemon sent that hint.
+    ///
+    /// For example `hint_default(&greeter, "hide-users", "greeter", "hide-user-list")`.
+    pub fn hint_default(self, greeter: &Greeter, hint: &str, section: &str, key: &str) -> Self {
+        match greeter.hint(hint) {
+            Some(value) => self.default(section, key, &value),
+            None => self,
+        }
+    }
+
+    /// The files which exist, in the order they are merged.
+    pub fn files(&self) -> Result<Vec<PathBuf>, Error> {
+        self.paths.conf_files(&self.name)
+    }
+
+    pub fn load(&self) -> Result<GreeterConfig, Error> {
+        let mut keyfile = KeyFile::default();
+        for (section, key, value) in &self.defaults {
+            keyfile.set_default(section, key, value);
+        }
+        let sources = self.files()?;
+        for path in &sources {
+            keyfile.load_file(path, |name| name)?;
+        }
+        Ok(GreeterConfig { keyfile, sources })
+    }
+
+    /// Calls `f` with the reloaded configuration whenever one of its files or `.conf.d`
+    /// directories changes, until the returned watcher is dropped.
+    ///
+    /// The file monitors dispatch on the thread-default main context of the calling thread.
+    pub fn watch<F: Fn(Result<GreeterConfig, Error>) + 'static>(
+        &self,
+        f: F,
+    ) -> Result<ConfigWatcher, Error> {
+        let (dirs, main) = self.paths.conf_locations(&self.name);
+        let loader = self.clone();
+        let f = Rc::new(f);
+        let mut monitors = Vec::new();
+        for path in dirs.iter().map(PathBuf::as_path).chain([main.as_path()]) {
+            let file = gio::File::for_path(path);
+            let monitor = if path == main {
+                file.monitor_file(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+            } else {
+                file.monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+            }
+            .map_err(|err| Error::Io {
+                path: path.to_owned(),
+                source: std::io::Error::other(err),
+            })?;
+            monitor.connect_changed({
+                let loader = loader.clone();
+                let f = f.clone();
+                move |_, file, _, event| {
+                    if is_relevant(file.path().as_deref(), event) {
+                        f(loader.load())
+                    }
+                }
+            });
+            monitors.push(monitor);
+        }
+        Ok(ConfigWatcher { monitors })
+    }
+}
+
+fn is_relevant(path: Option<&Path>, event: gio::FileMonitorEvent) -> bool {
+    use gio::FileMonitorEvent::*;
+    let is_conf = path.is_some_and(|path| path.extension().is_some_and(|ext| ext == "conf"));
+    is_conf
+        && matches!(
+            event,
+            ChangesDoneHint | Deleted | Created | Renamed | MovedIn | MovedOut
+        )
+}
+
+/// Keeps the file monitors of [`GreeterConfigLoader::watch`] alive.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    monitors: Vec<gio::FileMonitor>,
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        for monitor in &self.monitors {
+            monitor.cancel();
+        }
+    }
+}
+
+/// The merged configuration of a greeter.
+#[derive(Debug, Clone)]
+pub struct GreeterConfig {
+    keyfile: KeyFile,
+    sources: Vec<PathBuf>,
+}
+
+impl GreeterConfig {
+    /// Loads `name` from the standard locations without defaults.
+    pub fn load(name: &str) -> Result<Self, Error> {
+        GreeterConfigLoader::new(name).load()
+    }
+
+    /// The files which were merged, in load order.
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    pub fn section_names(&self) -> impl Iterator<Item = &str> {
+        self.keyfile.groups().map(|group| group.name.as_str())
+    }
+
+    pub fn section(&self, name: &str) -> Section {
+        match self.keyfile.group(name) {
+            Some(group) => Section::new(name, group.entries.clone()),
+            None => Section::new(name, Vec::new()),
+        }
+    }
+
+    /// The `[greeter]` section, where the reference greeters keep their settings.
+    pub fn greeter(&self) -> Section {
+        self.section("greeter")
+    }
+
+    pub fn theme_name(&self) -> Option<String> {
+        self.greeter().string("theme-name")
+    }
+
+    pub fn icon_theme_name(&self) -> Option<String> {
+        self.greeter().string("icon-theme-name")
+    }
+
+    pub fn font_name(&self) -> Option<String> {
+        self.greeter().string("font-name")
+    }
+
+    /// `background`: an image path or a color such as `#772953`.
+    pub fn background(&self) -> Option<String> {
+        self.greeter().string("background")
+    }
+
+    /// `user-background`: whether to show the background of the selected user.
+    pub fn user_background(&self) -> Result<Option<bool>, Error> {
+        self.greeter().boolean("user-background")
+    }
+
+    pub fn default_user_image(&self) -> Option<String> {
+        self.greeter().string("default-user-image")
+    }
+
+    pub fn hide_user_image(&self) -> Result<Option<bool>, Error> {
+        self.greeter().boolean("hide-user-image")
+    }
+
+    /// `indicators`: the panel items, in order.
+    pub fn indicators(&self) -> Vec<String> {
+        self.greeter().list("indicators")
+    }
+
+    /// `position`: where the login window goes.
+    pub fn position(&self) -> Result<Option<Position>, Error> {
+        self.greeter()
+            .parse("position", "a position", |value| value.parse().ok())
+    }
+
+    pub fn clock_format(&self) -> Option<String> {
+        self.greeter().string("clock-format")
+    }
+
+    /// `screensaver-timeout`, in seconds.
+    pub fn screensaver_timeout(&self) -> Result<Option<i64>, Error> {
+        self.greeter().integer("screensaver-timeout")
+    }
+
+    pub fn a11y_states(&self) -> Vec<String> {
+        self.greeter().list("a11y-states")
+    }
+}
+
+/// Which edge of the window a [`Dimension`] places.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Anchor {
+    Start,
+    #[default]
+    Center,
+    End,
+}
+
+/// One coordinate of a [`Position`], e.g. `50%,center` or `-20,end`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Dimension {
+    /// Distance from the start of the screen, or from its end when negative.
+    pub value: i32,
+    /// Whether `value` is a percentage of the screen size.
+    pub percentage: bool,
+    pub anchor: Anchor,
+}
+
+impl Dimension {
+    /// The window offset on a screen of `screen` pixels, for a window of `window` pixels.
+    pub fn resolve(&self, screen: i32, window: i32) -> i32 {
+        let mut offset = if self.percentage {
+            screen * self.value / 100
+        } else {
+            self.value
+        };
+        if self.value < 0 {
+            offset += screen;
+        }
+        match self.anchor {
+            Anchor::Start => offset,
+            Anchor::Center => offset - window / 2,
+            Anchor::End => offset - window,
+        }
+    }
+}
+
+impl FromStr for Dimension {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let (value, anchor) = match s.split_once(',') {
+            Some((value, anchor)) => (value, Some(anchor)),
+            None => (s, None),
+        };
+        let (value, percentage) = match value.strip_suffix('%') {
+            Some(value) => (value, true),
+            None => (value, false),
+        };
+        let anchor = match anchor {
+            None => Anchor::default(),
+            Some("start") => Anchor::Start,
+            Some("center") => Anchor::Center,
+            Some("end") => Anchor::End,
+            Some(_) => return Err(()),
+        };
+        Ok(Self {
+            value: value.parse().map_err(|_| ())?,
+            percentage,
+            anchor,
+        })
+    }
+}
+
+/// The `x [y]` value of `position`; `y` defaults to `x`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub x: Dimension,
+    pub y: Dimension,
+}
+
+impl FromStr for Position {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let mut parts = s.split_whitespace();
+        let x = parts.next().ok_or(())?.parse()?;
+        let y = match parts.next() {
+            Some(y) => y.parse()?,
+            None => x,
+        };
+        if parts.next().is_some() {
+            return Err(());
+        }
+        Ok(Self { x, y })
+    }
+}
@@ -0,0 +1,579 @@
+//! Reader for the daemon configuration: `lightdm.conf`, `lightdm.conf.d`, `users.conf` and
+//! `keys.conf`.
+//!
+//! [`Config::load`] reads the files in the order LightDM does, each overriding the previous ones:
+//!
+//! 1. `lightdm/lightdm.conf.d/*.conf` in each of the XDG system data dirs, last dir first
+//!    (usually `/usr/share/lightdm/lightdm.conf.d`)
+//! 2. `lightdm/lightdm.conf.d/*.conf` in each of the XDG system config dirs, last dir first
+//!    (usually `/etc/xdg/lightdm/lightdm.conf.d`)
+//! 3. `/etc/lightdm/lightdm.conf.d/*.conf`
+//! 4. `/etc/lightdm/lightdm.conf`
+//!
+//! Keys not set by any file take LightDM's built-in defaults. The legacy `[SeatDefaults]`
+//! section is read as `[Seat:*]`, and [`Config::seat`] merges every `[Seat:pattern]` section
+//! whose glob matches the seat name, in the order they first appear.
+//!
+//! Greeters keep their own `<greeter>.conf` next to `lightdm.conf`; [`GreeterConfigLoader`] merges
+//! it with its `.conf.d` directories the same way.
+
+use std::{
+    collections::BTreeMap,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::keyfile::{self, Entry, KeyFile};
+
+mod greeter;
+pub use greeter::{Anchor, ConfigWatcher, Dimension, GreeterConfig, GreeterConfigLoader, Position};
+
+/// Error reading the configuration.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// A line which is not a comment, a section header or a `key=value` pair.
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    /// A value which cannot be read as the type the key needs.
+    InvalidValue {
+        /// The file and line the value was read from, `None` for built-in defaults.
+        location: Option<(PathBuf, usize)>,
+        section: String,
+        key: String,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.display()),
+            Self::InvalidValue {
+                location,
+                section,
+                key,
+                value,
+                expected,
+            } => {
+                if let Some((path, line)) = location {
+                    write!(f, "{}:{line}: ", path.display())?;
+                }
+                write!(f, "[{section}] {key}: expected {expected}, found {value:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Where the configuration files are looked up.
+#[derive(Debug, Clone)]
+pub struct ConfigPaths {
+    /// Directory of `lightdm.conf`, `users.conf` and `keys.conf`, `/etc/lightdm` by default.
+    pub config_dir: PathBuf,
+    /// XDG system data dirs, most important first.
+    pub data_dirs: Vec<PathBuf>,
+    /// XDG system config dirs, most important first.
+    pub config_dirs: Vec<PathBuf>,
+}
+
+impl Default for ConfigPaths {
+    fn default() -> Self {
+        Self {
+            config_dir: PathBuf::from("/etc/lightdm"),
+            data_dirs: glib::system_data_dirs(),
+            config_dirs: glib::system_config_dirs(),
+        }
+    }
+}
+
+impl ConfigPaths {
+    /// Only reads files below `config_dir`, ignoring the XDG dirs.
+    pub fn with_config_dir(config_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            config_dir: config_dir.into(),
+            data_dirs: Vec::new(),
+            config_dirs: Vec::new(),
+        }
+    }
+
+    /// The `lightdm.conf` files which exist, in the order they are loaded.
+    pub fn lightdm_conf_files(&self) -> Result<Vec<PathBuf>, Error> {
+        self.conf_files("lightdm")
+    }
+
+    /// The `<name>.conf.d` directories, in load order, followed by `<name>.conf`.
+    pub(crate) fn conf_locations(&self, name: &str) -> (Vec<PathBuf>, PathBuf) {
+        let xdg_dir = Path::new("lightdm").join(format!("{name}.conf.d"));
+        let dirs = self
+            .data_dirs
+            .iter()
+            .rev()
+            .chain(self.config_dirs.iter().rev())
+            .map(|dir| dir.join(&xdg_dir))
+            .chain([self.config_dir.join(format!("{name}.conf.d"))])
+            .collect();
+        (dirs, self.config_dir.join(format!("{name}.conf")))
+    }
+
+    pub(crate) fn conf_files(&self, name: &str) -> Result<Vec<PathBuf>, Error> {
+        let (dirs, main) = self.conf_locations(name);
+        let mut files = Vec::new();
+        for dir in dirs {
+            files.extend(keyfile::files(&dir, "conf")?);
+        }
+        if main.exists() {
+            files.push(main);
+        }
+        Ok(files)
+    }
+}
+
+const DEFAULTS: &[(&str, &str, &str)] = &[
+    ("LightDM", "start-default-seat", "true"),
+    ("LightDM", "minimum-vt", "7"),
+    ("LightDM", "guest-account-script", "guest-account"),
+    ("LightDM", "greeter-user", "lightdm"),
+    ("LightDM", "lock-memory", "true"),
+    ("LightDM", "user-authority-in-system-dir", "false"),
+    ("LightDM", "run-directory", "/var/run/lightdm"),
+    ("LightDM", "cache-directory", "/var/cache/lightdm"),
+    ("LightDM", "log-directory", "/var/log/lightdm"),
+    (
+        "LightDM",
+        "sessions-directory",
+        "/usr/share/lightdm/sessions:/usr/share/xsessions:/usr/share/wayland-sessions",
+    ),
+    (
+        "LightDM",
+        "remote-sessions-directory",
+        "/usr/share/lightdm/remote-sessions",
+    ),
+    (
+        "LightDM",
+        "greeters-directory",
+        "/usr/share/lightdm/greeters:/usr/share/xgreeters",
+    ),
+    ("LightDM", "backup-logs", "true"),
+    ("LightDM", "dbus-service", "true"),
+    ("Seat:*", "type", "local"),
+    ("Seat:*", "pam-service", "lightdm"),
+    ("Seat:*", "pam-autologin-service", "lightdm-autologin"),
+    ("Seat:*", "pam-greeter-service", "lightdm-greeter"),
+    ("Seat:*", "xserver-command", "X"),
+    ("Seat:*", "xserver-share", "true"),
+    ("Seat:*", "start-session", "true"),
+    ("Seat:*", "allow-user-switching", "true"),
+    ("Seat:*", "allow-guest", "true"),
+    ("Seat:*", "greeter-allow-guest", "true"),
+    ("Seat:*", "greeter-show-remote-login", "true"),
+    ("Seat:*", "greeter-session", "example-gtk-gnome"),
+    ("Seat:*", "user-session", "default"),
+    ("Seat:*", "session-wrapper", "lightdm-session"),
+    ("Seat:*", "autologin-user-timeout", "0"),
+    ("XDMCPServer", "enabled", "false"),
+    ("XDMCPServer", "port", "177"),
+    ("VNCServer", "enabled", "false"),
+    ("VNCServer", "port", "5900"),
+    ("VNCServer", "width", "1024"),
+    ("VNCServer", "height", "768"),
+    ("VNCServer", "depth", "8"),
+];
+
+fn rename_legacy(name: &str) -> &str {
+    match name {
+        "SeatDefaults" => "Seat:*",
+        name => name,
+    }
+}
+
+/// The merged keys of one section.
+#[derive(Debug, Clone, Default)]
+pub struct Section {
+    name: String,
+    entries: Vec<Entry>,
+}
+
+impl Section {
+    pub(crate) fn new(name: &str, entries: Vec<Entry>) -> Self {
+        Self {
+            name: name.to_owned(),
+            entries,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| entry.key.as_str())
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entry(key).is_some()
+    }
+
+    fn entry(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+
+    /// The file and line `key` was read from, `None` if it is unset or a built-in default.
+    pub fn location(&self, key: &str) -> Option<(&Path, usize)> {
+        let origin = self.entry(key)?.origin.as_ref()?;
+        Some((&origin.path, origin.line))
+    }
+
+    /// The unescaped value, `None` if the key is unset or empty.
+    pub fn string(&self, key: &str) -> Option<String> {
+        let value = keyfile::unescape(&self.entry(key)?.value);
+        (!value.is_empty()).then_some(value)
+    }
+
+    /// A `;`-separated list.
+    pub fn list(&self, key: &str) -> Vec<String> {
+        self.entry(key)
+            .map(|entry| keyfile::split_list(&entry.value))
+            .unwrap_or_default()
+    }
+
+    pub fn boolean(&self, key: &str) -> Result<Option<bool>, Error> {
+        self.parse(key, "a boolean", keyfile::parse_bool)
+    }
+
+    pub fn integer(&self, key: &str) -> Result<Option<i64>, Error> {
+        self.parse(key, "an integer", |value| value.trim_end().parse().ok())
+    }
+
+    pub(crate) fn parse<T>(
+        &self,
+        key: &str,
+        expected: &'static str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Result<Option<T>, Error> {
+        let Some(entry) = self.entry(key) else {
+            return Ok(None);
+        };
+        parse(&entry.value)
+            .map(Some)
+            .ok_or_else(|| Error::InvalidValue {
+                location: entry
+                    .origin
+                    .as_ref()
+                    .map(|origin| (origin.path.to_path_buf(), origin.line)),
+                section: self.name.clone(),
+                key: key.to_owned(),
+                value: entry.value.clone(),
+                expected,
+            })
+    }
+
+    fn merge(&mut self, other: &[Entry]) {
+        for entry in other {
+            match self.entries.iter_mut().find(|old| old.key == entry.key) {
+                Some(old) => *old = entry.clone(),
+                None => self.entries.push(entry.clone()),
+            }
+        }
+    }
+}
+
+/// The effective settings of one seat.
+#[derive(Debug, Clone)]
+pub struct SeatConfig {
+    section: Section,
+}
+
+impl SeatConfig {
+    /// The merged keys, for settings without a getter here.
+    pub fn section(&self) -> &Section {
+        &self.section
+    }
+
+    /// `type`: `local`, `xremote` or `unity`.
+    pub fn seat_type(&self) -> Option<String> {
+        self.section.string("type")
+    }
+
+    pub fn autologin_user(&self) -> Option<String> {
+        self.section.string("autologin-user")
+    }
+
+    pub fn autologin_user_timeout(&self) -> Result<i64, Error> {
+        Ok(self
+            .section
+            .integer("autologin-user-timeout")?
+            .unwrap_or_default())
+    }
+
+    pub fn autologin_guest(&self) -> Result<bool, Error> {
+        Ok(self.section.boolean("autologin-guest")?.unwrap_or_default())
+    }
+
+    pub fn autologin_session(&self) -> Option<String> {
+        self.section.string("autologin-session")
+    }
+
+    pub fn greeter_session(&self) -> Option<String> {
+        self.section.string("greeter-session")
+    }
+
+    pub fn greeter_hide_users(&self) -> Result<bool, Error> {
+        Ok(self
+            .section
+            .boolean("greeter-hide-users")?
+            .unwrap_or_default())
+    }
+
+    pub fn greeter_allow_guest(&self) -> Result<bool, Error> {
+        Ok(self
+            .section
+            .boolean("greeter-allow-guest")?
+            .unwrap_or_default())
+    }
+
+    pub fn greeter_show_manual_login(&self) -> Result<bool, Error> {
+        Ok(self
+            .section
+            .boolean("greeter-show-manual-login")?
+            .unwrap_or_default())
+    }
+
+    pub fn greeter_show_remote_login(&self) -> Result<bool, Error> {
+        Ok(self
+            .section
+            .boolean("greeter-show-remote-login")?
+            .unwrap_or_default())
+    }
+
+    pub fn user_session(&self) -> Option<String> {
+        self.section.string("user-session")
+    }
+
+    pub fn allow_guest(&self) -> Result<bool, Error> {
+        Ok(self.section.boolean("allow-guest")?.unwrap_or_default())
+    }
+
+    pub fn allow_user_switching(&self) -> Result<bool, Error> {
+        Ok(self
+            .section
+            .boolean("allow-user-switching")?
+            .unwrap_or_default())
+    }
+
+    pub fn session_wrapper(&self) -> Option<String> {
+        self.section.string("session-wrapper")
+    }
+}
+
+/// `users.conf`, which filters the users shown by greeters when AccountsService is not used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsersConfig {
+    pub minimum_uid: u32,
+    pub hidden_users: Vec<String>,
+    pub hidden_shells: Vec<String>,
+}
+
+impl Default for UsersConfig {
+    fn default() -> Self {
+        Self {
+            minimum_uid: 500,
+            hidden_users: vec!["nobody".into(), "nobody4".into(), "noaccess".into()],
+            hidden_shells: vec!["/bin/false".into(), "/usr/sbin/nologin".into()],
+        }
+    }
+}
+
+impl UsersConfig {
+    fn from_section(section: &Section) -> Result<Self, Error> {
+        let mut config = Self::default();
+        if let Some(minimum_uid) = section.parse("minimum-uid", "a user id", |value| {
+            value.trim_end().parse().ok()
+        })? {
+            config.minimum_uid = minimum_uid;
+        }
+        let words = |value: String| value.split_whitespace().map(str::to_owned).collect();
+        if section.contains("hidden-users") {
+            config.hidden_users = section
+                .string("hidden-users")
+                .map(words)
+                .unwrap_or_default();
+        }
+        if section.contains("hidden-shells") {
+            config.hidden_shells = section
+                .string("hidden-shells")
+                .map(words)
+                .unwrap_or_default();
+        }
+        Ok(config)
+    }
+}
+
+/// The daemon configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    keyfile: KeyFile,
+    sources: Vec<PathBuf>,
+    users: UsersConfig,
+    keys: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// Loads the configuration from the standard locations.
+    pub fn load() -> Result<Self, Error> {
+        Self::load_from(&ConfigPaths::default())
+    }
+
+    pub fn load_from(paths: &ConfigPaths) -> Result<Self, Error> {
+        let mut keyfile = Self::defaults();
+        let sources = paths.lightdm_conf_files()?;
+        for path in &sources {
+            keyfile.load_file(path, rename_legacy)?;
+        }
+
+        let mut users = KeyFile::default();
+        users.load_file(&paths.config_dir.join("users.conf"), |name| name)?;
+        let users = match users.group("UserList") {
+            Some(group) => {
+                UsersConfig::from_section(&Section::new("UserList", group.entries.clone()))?
+            }
+            None => UsersConfig::default(),
+        };
+
+        let mut keys = KeyFile::default();
+        keys.load_file(&paths.config_dir.join("keys.conf"), |name| name)?;
+        let keys = keys
+            .group("keys")
+            .into_iter()
+            .flat_map(|group| &group.entries)
+            .map(|entry| (entry.key.clone(), keyfile::unescape(&entry.value)))
+            .collect();
+
+        Ok(Self {
+            keyfile,
+            sources,
+            users,
+            keys,
+        })
+    }
+
+    /// Parses a single `lightdm.conf`, e.g. for validating a file before installing it.
+    pub fn parse(text: &str, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut keyfile = Self::defaults();
+        keyfile.load_str(text, path.as_ref(), rename_legacy)?;
+        Ok(Self {
+            keyfile,
+            sources: vec![path.as_ref().to_owned()],
+            users: UsersConfig::default(),
+            keys: BTreeMap::new(),
+        })
+    }
+
+    fn defaults() -> KeyFile {
+        let mut keyfile = KeyFile::default();
+        for (group, key, value) in DEFAULTS {
+            keyfile.set_default(group, key, value);
+        }
+        keyfile
+    }
+
+    /// The `lightdm.conf` files which were read, in load order.
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    /// Names of all sections, e.g. `LightDM`, `Seat:*` or `XDMCPServer`.
+    pub fn section_names(&self) -> impl Iterator<Item = &str> {
+        self.keyfile.groups().map(|group| group.name.as_str())
+    }
+
+    pub fn section(&self, name: &str) -> Option<Section> {
+        let group = self.keyfile.group(name)?;
+        Some(Section::new(&group.name, group.entries.clone()))
+    }
+
+    /// The `[LightDM]` section.
+    pub fn lightdm(&self) -> Section {
+        self.section("LightDM").unwrap_or_default()
+    }
+
+    /// The glob patterns of the `[Seat:pattern]` sections.
+    pub fn seat_patterns(&self) -> impl Iterator<Item = &str> {
+        self.section_names()
+            .filter_map(|name| name.strip_prefix("Seat:"))
+    }
+
+    /// The effective settings of the seat called `name`, e.g. `seat0`.
+    pub fn seat(&self, name: &str) -> SeatConfig {
+        let mut section = Section::new(&format!("Seat:{name}"), Vec::new());
+        for group in self.keyfile.groups() {
+            if let Some(pattern) = group.name.strip_prefix("Seat:")
+                && keyfile::glob_match(pattern, name)
+            {
+                section.merge(&group.entries);
+            }
+        }
+        SeatConfig { section }
+    }
+
+    /// `sessions-directory`, searched for session desktop files.
+    pub fn sessions_directories(&self) -> Vec<PathBuf> {
+        self.directories("sessions-directory")
+    }
+
+    /// `remote-sessions-directory`.
+    pub fn remote_sessions_directories(&self) -> Vec<PathBuf> {
+        self.directories("remote-sessions-directory")
+    }
+
+    /// `greeters-directory`.
+    pub fn greeters_directories(&self) -> Vec<PathBuf> {
+        self.directories("greeters-directory")
+    }
+
+    fn directories(&self, key: &str) -> Vec<PathBuf> {
+        self.lightdm()
+            .string(key)
+            .map(|value| std::env::split_paths(&value).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn minimum_vt(&self) -> Result<i64, Error> {
+        Ok(self.lightdm().integer("minimum-vt")?.unwrap_or_default())
+    }
+
+    pub fn greeter_user(&self) -> Option<String> {
+        self.lightdm().string("greeter-user")
+    }
+
+    /// `users.conf`, or its defaults if there is none.
+    pub fn users(&self) -> &UsersConfig {
+        &self.users
+    }
+
+    /// The XDMCP keys of `keys.conf`.
+    pub fn keys(&self) -> &BTreeMap<String, String> {
+        &self.keys
+    }
+}
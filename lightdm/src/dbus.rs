@@ -0,0 +1,454 @@
+//! Client for the `org.freedesktop.DisplayManager` D-Bus service of the LightDM daemon.
+//!
+//! [`DisplayManager`], [`Seat`] and [`Session`] wrap a [`gio::DBusProxy`] for the matching
+//! interface. Properties are read from the proxy cache, so they are kept up to date by
+//! `PropertiesChanged` while the thread-default main context of the creating thread is running.
+//! Signal handlers run on that context too.
+//!
+//! Session-side tools usually start from the paths LightDM puts in the environment of the
+//! sessions it starts:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), lightdm::Error> {
+//! use lightdm::dbus::{self, Seat};
+//!
+//! let connection = gio::bus_get_sync(gio::BusType::System, gio::Cancellable::NONE)?;
+//! if let Some(path) = dbus::current_seat_path() {
+//!     Seat::new_sync(&connection, &path)?.lock_sync()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use gio::{DBusCallFlags, DBusConnection, DBusProxy, DBusProxyFlags, prelude::*};
+use glib::{SignalHandlerId, Variant, variant::ObjectPath};
+
+use std::{boxed::Box as Box_, future::Future, pin::Pin};
+
+use crate::Error;
+
+/// Well-known bus name of the LightDM daemon.
+pub const BUS_NAME: &str = "org.freedesktop.DisplayManager";
+/// Object path of the [`DisplayManager`] object.
+pub const OBJECT_PATH: &str = "/org/freedesktop/DisplayManager";
+
+const DISPLAY_MANAGER_INTERFACE: &str = "org.freedesktop.DisplayManager";
+const SEAT_INTERFACE: &str = "org.freedesktop.DisplayManager.Seat";
+const SESSION_INTERFACE: &str = "org.freedesktop.DisplayManager.Session";
+
+/// The seat of the calling session, from `XDG_SEAT_PATH`.
+pub fn current_seat_path() -> Option<String> {
+    std::env::var("XDG_SEAT_PATH").ok()
+}
+
+/// The calling session, from `XDG_SESSION_PATH`.
+pub fn current_session_path() -> Option<String> {
+    std::env::var("XDG_SESSION_PATH").ok()
+}
+
+/// The future returned by the `_future` methods of the proxies.
+pub type CallFuture<T> = Pin<Box_<dyn Future<Output = Result<T, Error>> + 'static>>;
+
+pub(crate) fn unexpected_reply(method: &str, reply: &Variant) -> Error {
+    glib::Error::new(
+        gio::IOErrorEnum::InvalidData,
+        &format!("Unexpected reply of type {} to {method}", reply.type_()),
+    )
+    .into()
+}
+
+pub(crate) fn call_sync(
+    proxy: &DBusProxy,
+    method: &str,
+    parameters: Option<Variant>,
+) -> Result<Variant, Error> {
+    Ok(proxy.call_sync(
+        method,
+        parameters.as_ref(),
+        DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+    )?)
+}
+
+pub(crate) fn call_future(
+    proxy: &DBusProxy,
+    method: &str,
+    parameters: Option<Variant>,
+) -> impl Future<Output = Result<Variant, Error>> + 'static {
+    let call = proxy.call_future(method, parameters.as_ref(), DBusCallFlags::NONE, -1);
+    async move { Ok(call.await?) }
+}
+
+pub(crate) fn object_path_reply(method: &str, reply: Variant) -> Result<String, Error> {
+    reply
+        .get::<(ObjectPath,)>()
+        .map(|(path,)| path.as_str().to_owned())
+        .ok_or_else(|| unexpected_reply(method, &reply))
+}
+
+fn connect_path_signal<T: 'static, F: Fn(&T, &str) + Send + Sync + 'static>(
+    proxy: &DBusProxy,
+    signal: &'static str,
+    wrap: fn(&DBusProxy) -> T,
+    f: F,
+) -> SignalHandlerId {
+    proxy.connect_g_signal(move |proxy, _, signal_name, parameters| {
+        if signal_name != signal {
+            return;
+        }
+        if let Some((path,)) = parameters.get::<(ObjectPath,)>() {
+            f(&wrap(proxy), path.as_str())
+        }
+    })
+}
+
+macro_rules! proxy_wrapper {
+    ($(#[$attr:meta])* $name:ident, $interface:expr) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone)]
+        pub struct $name {
+            proxy: DBusProxy,
+        }
+
+        impl $name {
+            fn new_proxy_sync(connection: &DBusConnection, path: &str) -> Result<Self, Error> {
+                let proxy = DBusProxy::new_sync(
+                    connection,
+                    DBusProxyFlags::NONE,
+                    None,
+                    Some(BUS_NAME),
+                    path,
+                    $interface,
+                    gio::Cancellable::NONE,
+                )?;
+                Ok(Self { proxy })
+            }
+
+            fn new_proxy_future(connection: &DBusConnection, path: &str) -> CallFuture<Self> {
+                let proxy = DBusProxy::new_future(
+                    connection,
+                    DBusProxyFlags::NONE,
+                    None,
+                    Some(BUS_NAME),
+                    path,
+                    $interface,
+                );
+                Box_::pin(async move { Ok(Self { proxy: proxy.await? }) })
+            }
+
+            fn from_proxy(proxy: &DBusProxy) -> Self {
+                Self {
+                    proxy: proxy.clone(),
+                }
+            }
+
+            /// The underlying proxy, for calls this type does not wrap.
+            pub fn proxy(&self) -> &DBusProxy {
+                &self.proxy
+            }
+
+            pub fn object_path(&self) -> glib::GString {
+                self.proxy.object_path()
+            }
+
+            fn property<T: glib::variant::FromVariant>(&self, name: &str) -> Option<T> {
+                self.proxy.cached_property(name).and_then(|value| value.get())
+            }
+
+            /// Calls `f` with the names of the properties which changed or were invalidated.
+            #[doc(alias = "PropertiesChanged")]
+            pub fn connect_properties_changed<F: Fn(&Self, &[&str]) + Send + Sync + 'static>(
+                &self,
+                f: F,
+            ) -> SignalHandlerId {
+                self.proxy
+                    .connect_g_properties_changed(move |proxy, changed, invalidated| {
+                        let mut names = changed
+                            .iter()
+                            .filter_map(|entry| entry.child_value(0).str().map(ToOwned::to_owned))
+                            .collect::<Vec<_>>();
+                        names.extend(invalidated.iter().map(|name| name.to_string()));
+                        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+                        f(&Self::from_proxy(proxy), &names)
+                    })
+            }
+
+            pub fn disconnect(&self, id: SignalHandlerId) {
+                self.proxy.disconnect(id);
+            }
+        }
+    };
+}
+
+proxy_wrapper!(
+    /// The `org.freedesktop.DisplayManager` object at [`OBJECT_PATH`].
+    #[doc(alias = "org.freedesktop.DisplayManager")]
+    DisplayManager,
+    DISPLAY_MANAGER_INTERFACE
+);
+
+impl DisplayManager {
+    pub fn new_sync(connection: &DBusConnection) -> Result<Self, Error> {
+        Self::new_proxy_sync(connection, OBJECT_PATH)
+    }
+
+    pub fn new_future(connection: &DBusConnection) -> CallFuture<Self> {
+        Self::new_proxy_future(connection, OBJECT_PATH)
+    }
+
+    /// Connects to the daemon on the system bus.
+    pub fn for_system_bus_sync() -> Result<Self, Error> {
+        let connection = gio::bus_get_sync(gio::BusType::System, gio::Cancellable::NONE)?;
+        Self::new_sync(&connection)
+    }
+
+    /// Object paths of the seats.
+    pub fn seats(&self) -> Vec<String> {
+        paths(self.property("Seats"))
+    }
+
+    /// Object paths of the sessions on all seats.
+    pub fn sessions(&self) -> Vec<String> {
+        paths(self.property("Sessions"))
+    }
+
+    pub fn seat_sync(&self, path: &str) -> Result<Seat, Error> {
+        Seat::new_sync(&self.proxy.connection(), path)
+    }
+
+    pub fn session_sync(&self, path: &str) -> Result<Session, Error> {
+        Session::new_sync(&self.proxy.connection(), path)
+    }
+
+    /// Adds a seat of `seat_type` with the given seat properties, returning its object path.
+    #[doc(alias = "AddSeat")]
+    pub fn add_seat_sync(
+        &self,
+        seat_type: &str,
+        properties: &[(&str, &str)],
+    ) -> Result<String, Error> {
+        let reply = call_sync(
+            &self.proxy,
+            "AddSeat",
+            Some((seat_type, properties).to_variant()),
+        )?;
+        object_path_reply("AddSeat", reply)
+    }
+
+    #[doc(alias = "AddSeat")]
+    pub fn add_seat_future(
+        &self,
+        seat_type: &str,
+        properties: &[(&str, &str)],
+    ) -> CallFuture<String> {
+        let call = call_future(
+            &self.proxy,
+            "AddSeat",
+            Some((seat_type, properties).to_variant()),
+        );
+        Box_::pin(async move { object_path_reply("AddSeat", call.await?) })
+    }
+
+    /// Adds a seat for the X server already running on `:display_number`, returning its object
+    /// path.
+    #[doc(alias = "AddLocalXSeat")]
+    pub fn add_local_x_seat_sync(&self, display_number: i32) -> Result<String, Error> {
+        let reply = call_sync(
+            &self.proxy,
+            "AddLocalXSeat",
+            Some((display_number,).to_variant()),
+        )?;
+        object_path_reply("AddLocalXSeat", reply)
+    }
+
+    #[doc(alias = "AddLocalXSeat")]
+    pub fn add_local_x_seat_future(&self, display_number: i32) -> CallFuture<String> {
+        let call = call_future(
+            &self.proxy,
+            "AddLocalXSeat",
+            Some((display_number,).to_variant()),
+        );
+        Box_::pin(async move { object_path_reply("AddLocalXSeat", call.await?) })
+    }
+
+    #[doc(alias = "SeatAdded")]
+    pub fn connect_seat_added<F: Fn(&Self, &str) + Send + Sync + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        connect_path_signal(&self.proxy, "SeatAdded", Self::from_proxy, f)
+    }
+
+    #[doc(alias = "SeatRemoved")]
+    pub fn connect_seat_removed<F: Fn(&Self, &str) + Send + Sync + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        connect_path_signal(&self.proxy, "SeatRemoved", Self::from_proxy, f)
+    }
+
+    #[doc(alias = "SessionAdded")]
+    pub fn connect_session_added<F: Fn(&Self, &str) + Send + Sync + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        connect_path_signal(&self.proxy, "SessionAdded", Self::from_proxy, f)
+    }
+
+    #[doc(alias = "SessionRemoved")]
+    pub fn connect_session_removed<F: Fn(&Self, &str) + Send + Sync + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        connect_path_signal(&self.proxy, "SessionRemoved", Self::from_proxy, f)
+    }
+}
+
+proxy_wrapper!(
+    /// An `org.freedesktop.DisplayManager.Seat` object.
+    #[doc(alias = "org.freedesktop.DisplayManager.Seat")]
+    Seat,
+    SEAT_INTERFACE
+);
+
+impl Seat {
+    pub fn new_sync(connection: &DBusConnection, path: &str) -> Result<Self, Error> {
+        Self::new_proxy_sync(connection, path)
+    }
+
+    pub fn new_future(connection: &DBusConnection, path: &str) -> CallFuture<Self> {
+        Self::new_proxy_future(connection, path)
+    }
+
+    #[doc(alias = "CanSwitch")]
+    pub fn can_switch(&self) -> bool {
+        self.property("CanSwitch").unwrap_or_default()
+    }
+
+    #[doc(alias = "HasGuestAccount")]
+    pub fn has_guest_account(&self) -> bool {
+        self.property("HasGuestAccount").unwrap_or_default()
+    }
+
+    /// Object paths of the sessions on this seat.
+    pub fn sessions(&self) -> Vec<String> {
+        paths(self.property("Sessions"))
+    }
+
+    #[doc(alias = "SwitchToGreeter")]
+    pub fn switch_to_greeter_sync(&self) -> Result<(), Error> {
+        call_sync(&self.proxy, "SwitchToGreeter", None).map(drop)
+    }
+
+    #[doc(alias = "SwitchToGreeter")]
+    pub fn switch_to_greeter_future(&self) -> CallFuture<()> {
+        let call = call_future(&self.proxy, "SwitchToGreeter", None);
+        Box_::pin(async move { call.await.map(drop) })
+    }
+
+    /// Switches to `username`, starting `session` (or their default session) if they are not
+    /// logged in yet.
+    #[doc(alias = "SwitchToUser")]
+    pub fn switch_to_user_sync(&self, username: &str, session: Option<&str>) -> Result<(), Error> {
+        let parameters = (username, session.unwrap_or_default()).to_variant();
+        call_sync(&self.proxy, "SwitchToUser", Some(parameters)).map(drop)
+    }
+
+    #[doc(alias = "SwitchToUser")]
+    pub fn switch_to_user_future(&self, username: &str, session: Option<&str>) -> CallFuture<()> {
+        let parameters = (username, session.unwrap_or_default()).to_variant();
+        let call = call_future(&self.proxy, "SwitchToUser", Some(parameters));
+        Box_::pin(async move { call.await.map(drop) })
+    }
+
+    #[doc(alias = "SwitchToGuest")]
+    pub fn switch_to_guest_sync(&self, session: Option<&str>) -> Result<(), Error> {
+        let parameters = (session.unwrap_or_default(),).to_variant();
+        call_sync(&self.proxy, "SwitchToGuest", Some(parameters)).map(drop)
+    }
+
+    #[doc(alias = "SwitchToGuest")]
+    pub fn switch_to_guest_future(&self, session: Option<&str>) -> CallFuture<()> {
+        let parameters = (session.unwrap_or_default(),).to_variant();
+        let call = call_future(&self.proxy, "SwitchToGuest", Some(parameters));
+        Box_::pin(async move { call.await.map(drop) })
+    }
+
+    /// Locks the seat, switching to the greeter.
+    #[doc(alias = "Lock")]
+    pub fn lock_sync(&self) -> Result<(), Error> {
+        call_sync(&self.proxy, "Lock", None).map(drop)
+    }
+
+    #[doc(alias = "Lock")]
+    pub fn lock_future(&self) -> CallFuture<()> {
+        let call = call_future(&self.proxy, "Lock", None);
+        Box_::pin(async move { call.await.map(drop) })
+    }
+
+    #[doc(alias = "SessionAdded")]
+    pub fn connect_session_added<F: Fn(&Self, &str) + Send + Sync + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        connect_path_signal(&self.proxy, "SessionAdded", Self::from_proxy, f)
+    }
+
+    #[doc(alias = "SessionRemoved")]
+    pub fn connect_session_removed<F: Fn(&Self, &str) + Send + Sync + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        connect_path_signal(&self.proxy, "SessionRemoved", Self::from_proxy, f)
+    }
+}
+
+proxy_wrapper!(
+    /// An `org.freedesktop.DisplayManager.Session` object.
+    #[doc(alias = "org.freedesktop.DisplayManager.Session")]
+    Session,
+    SESSION_INTERFACE
+);
+
+impl Session {
+    pub fn new_sync(connection: &DBusConnection, path: &str) -> Result<Self, Error> {
+        Self::new_proxy_sync(connection, path)
+    }
+
+    pub fn new_future(connection: &DBusConnection, path: &str) -> CallFuture<Self> {
+        Self::new_proxy_future(connection, path)
+    }
+
+    /// Object path of the seat this session runs on.
+    #[doc(alias = "Seat")]
+    pub fn seat(&self) -> Option<String> {
+        self.property::<ObjectPath>("Seat")
+            .map(|path| path.as_str().to_owned())
+    }
+
+    #[doc(alias = "UserName")]
+    pub fn user_name(&self) -> Option<String> {
+        self.property("UserName")
+    }
+
+    /// Locks the session, switching its seat to the greeter.
+    #[doc(alias = "Lock")]
+    pub fn lock_sync(&self) -> Result<(), Error> {
+        call_sync(&self.proxy, "Lock", None).map(drop)
+    }
+
+    #[doc(alias = "Lock")]
+    pub fn lock_future(&self) -> CallFuture<()> {
+        let call = call_future(&self.proxy, "Lock", None);
+        Box_::pin(async move { call.await.map(drop) })
+    }
+}
+
+fn paths(value: Option<Vec<ObjectPath>>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .iter()
+        .map(|path| path.as_str().to_owned())
+        .collect()
+}
@@ -0,0 +1,170 @@
+use glib::translate::*;
+
+use std::fmt;
+
+use super::GreeterError;
+
+/// Error returned by the [`GreeterExtManual`](crate::prelude::GreeterExtManual) methods.
+///
+/// The first five variants mirror the [`GreeterError`] domain reported by liblightdm and keep the
+/// daemon's message. [`Error::CallFailed`] is used when a call returns `FALSE` (or `NULL`)
+/// without setting a `GError`, [`Error::NoSession`] when no installed session can be started,
+/// [`Error::UnknownLanguage`] for a language which is not installed and [`Error::SecretPrompt`]
+/// when a secret prompt is answered with plain text.
+/// Errors from any other domain are kept as-is in [`Error::Other`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Error {
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_COMMUNICATION_ERROR")]
+    CommunicationError(String),
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_CONNECTION_FAILED")]
+    ConnectionFailed(String),
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_SESSION_FAILED")]
+    SessionFailed(String),
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_NO_AUTOLOGIN")]
+    NoAutologin(String),
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_INVALID_USER")]
+    InvalidUser(String),
+    /// `function` reported failure without setting an error.
+    CallFailed { function: String },
+    /// No session is installed, so there is nothing to start.
+    NoSession,
+    /// The language is not installed.
+    UnknownLanguage(String),
+    /// `respond` was called for a [`PromptType::Secret`](crate::PromptType::Secret) prompt; use
+    /// `respond_secret` instead.
+    SecretPrompt,
+    /// An error outside the `LightDMGreeterError` domain, or with an unknown code.
+    Other(glib::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, glib::ErrorDomain)]
+#[error_domain(name = "lightdm-rs-error-quark")]
+enum ErrorCode {
+    CallFailed,
+    NoSession,
+    UnknownLanguage,
+    SecretPrompt,
+}
+
+impl Error {
+    pub(crate) fn call_failed(function: &str) -> Self {
+        Self::CallFailed {
+            function: function.to_owned(),
+        }
+    }
+
+    /// The [`GreeterError`] code of this error, if it belongs to that domain.
+    pub fn kind(&self) -> Option<GreeterError> {
+        match self {
+            Self::CommunicationError(_) => Some(GreeterError::CommunicationError),
+            Self::ConnectionFailed(_) => Some(GreeterError::ConnectionFailed),
+            Self::SessionFailed(_) => Some(GreeterError::SessionFailed),
+            Self::NoAutologin(_) => Some(GreeterError::NoAutologin),
+            Self::InvalidUser(_) => Some(GreeterError::InvalidUser),
+            Self::CallFailed { .. }
+            | Self::NoSession
+            | Self::UnknownLanguage(_)
+            | Self::SecretPrompt => None,
+            Self::Other(err) => err.kind::<GreeterError>(),
+        }
+    }
+
+    /// The message carried by the underlying error.
+    pub fn message(&self) -> &str {
+        match self {
+            Self::CommunicationError(message)
+            | Self::ConnectionFailed(message)
+            | Self::SessionFailed(message)
+            | Self::NoAutologin(message)
+            | Self::InvalidUser(message)
+            | Self::UnknownLanguage(message) => message,
+            Self::CallFailed { function } => function,
+            Self::NoSession => "No session is available",
+            Self::SecretPrompt => "Secret prompts must be answered with respond_secret",
+            Self::Other(err) => err.message(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CallFailed { function } => {
+                write!(f, "{function}() returned failure without setting an error")
+            }
+            Self::Other(err) => fmt::Display::fmt(err, f),
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Other(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<glib::Error> for Error {
+    fn from(err: glib::Error) -> Self {
+        if err.matches(ErrorCode::CallFailed) {
+            return Self::CallFailed {
+                function: err.message().to_owned(),
+            };
+        }
+        if err.matches(ErrorCode::NoSession) {
+            return Self::NoSession;
+        }
+        if err.matches(ErrorCode::UnknownLanguage) {
+            return Self::UnknownLanguage(err.message().to_owned());
+        }
+        if err.matches(ErrorCode::SecretPrompt) {
+            return Self::SecretPrompt;
+        }
+        let message = err.message().to_owned();
+        match err.kind::<GreeterError>() {
+            Some(GreeterError::CommunicationError) => Self::CommunicationError(message),
+            Some(GreeterError::ConnectionFailed) => Self::ConnectionFailed(message),
+            Some(GreeterError::SessionFailed) => Self::SessionFailed(message),
+            Some(GreeterError::NoAutologin) => Self::NoAutologin(message),
+            Some(GreeterError::InvalidUser) => Self::InvalidUser(message),
+            _ => Self::Other(err),
+        }
+    }
+}
+
+impl From<Error> for glib::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::CommunicationError(message) => {
+                glib::Error::new(GreeterError::CommunicationError, &message)
+            }
+            Error::ConnectionFailed(message) => {
+                glib::Error::new(GreeterError::ConnectionFailed, &message)
+            }
+            Error::SessionFailed(message) => {
+                glib::Error::new(GreeterError::SessionFailed, &message)
+            }
+            Error::NoAutologin(message) => glib::Error::new(GreeterError::NoAutologin, &message),
+            Error::InvalidUser(message) => glib::Error::new(GreeterError::InvalidUser, &message),
+            Error::CallFailed { function } => glib::Error::new(ErrorCode::CallFailed, &function),
+            Error::NoSession => glib::Error::new(ErrorCode::NoSession, err.message()),
+            Error::UnknownLanguage(language) => {
+                glib::Error::new(ErrorCode::UnknownLanguage, &language)
+            }
+            Error::SecretPrompt => glib::Error::new(ErrorCode::SecretPrompt, err.message()),
+            Error::Other(err) => err,
+        }
+    }
+}
+
+#[doc(hidden)]
+impl FromGlibPtrFull<*mut glib::ffi::GError> for Error {
+    #[inline]
+    unsafe fn from_glib_full(ptr: *mut glib::ffi::GError) -> Self {
+        Self::from(unsafe { <glib::Error as FromGlibPtrFull<_>>::from_glib_full(ptr) })
+    }
+}
@@ -0,0 +1,162 @@
+use futures_channel::mpsc;
+use futures_core::Stream;
+
+use std::{
+    boxed::Box as Box_,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use super::{MessageType, PromptType};
+
+/// A signal or hint change emitted by a [`Greeter`](crate::Greeter), as yielded by
+/// [`GreeterEvents`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GreeterEvent {
+    #[doc(alias = "show-prompt")]
+    ShowPrompt {
+        text: String,
+        prompt_type: PromptType,
+    },
+    #[doc(alias = "show-message")]
+    ShowMessage {
+        text: String,
+        message_type: MessageType,
+    },
+    #[doc(alias = "authentication-complete")]
+    AuthenticationComplete,
+    #[doc(alias = "autologin-timer-expired")]
+    AutologinTimerExpired,
+    #[doc(alias = "idle")]
+    Idle,
+    #[doc(alias = "reset")]
+    Reset,
+    /// The hint property with this name changed; read it back through
+    /// [`GreeterExt`](crate::prelude::GreeterExt).
+    HintChanged(&'static str),
+}
+
+/// Stream of [`GreeterEvent`]s returned by
+/// [`GreeterExtManual::events`](crate::prelude::GreeterExtManual::events).
+///
+/// The signal handlers are disconnected when the stream is dropped.
+#[must_use = "streams do nothing unless polled"]
+pub struct GreeterEvents {
+    receiver: mpsc::UnboundedReceiver<GreeterEvent>,
+    disconnect: Option<Box_<dyn FnOnce()>>,
+}
+
+/// Connects every signal of `$greeter` to a new [`GreeterEvents`], with the `connect_*` and
+/// `disconnect` methods of whichever backend's traits are in scope.
+macro_rules! greeter_events {
+    (@hint $greeter:ident, $sender:ident, $connect:ident, $name:literal) => {{
+        let sender = $sender.clone();
+        $greeter.$connect(move |_| {
+            let _ = sender.unbounded_send(GreeterEvent::HintChanged($name));
+        })
+    }};
+    ($greeter:expr) => {{
+        let greeter = $greeter;
+        let (sender, receiver) = mpsc::unbounded();
+        let handlers = vec![
+            greeter.connect_show_prompt({
+                let sender = sender.clone();
+                move |_, text, prompt_type| {
+                    let _ = sender.unbounded_send(GreeterEvent::ShowPrompt {
+                        text: text.to_owned(),
+                        prompt_type,
+                    });
+                }
+            }),
+            greeter.connect_show_message({
+                let sender = sender.clone();
+                move |_, text, message_type| {
+                    let _ = sender.unbounded_send(GreeterEvent::ShowMessage {
+                        text: text.to_owned(),
+                        message_type,
+                    });
+                }
+            }),
+            greeter.connect_authentication_complete({
+                let sender = sender.clone();
+                move |_| {
+                    let _ = sender.unbounded_send(GreeterEvent::AuthenticationComplete);
+                }
+            }),
+            greeter.connect_autologin_timer_expired({
+                let sender = sender.clone();
+                move |_| {
+                    let _ = sender.unbounded_send(GreeterEvent::AutologinTimerExpired);
+                }
+            }),
+            greeter.connect_idle({
+                let sender = sender.clone();
+                move |_| {
+                    let _ = sender.unbounded_send(GreeterEvent::Idle);
+                }
+            }),
+            greeter.connect_reset({
+                let sender = sender.clone();
+                move |_| {
+                    let _ = sender.unbounded_send(GreeterEvent::Reset);
+                }
+            }),
+            greeter_events!(@hint greeter, sender, connect_autologin_guest_hint_notify, "autologin-guest-hint"),
+            greeter_events!(@hint greeter, sender, connect_autologin_session_hint_notify, "autologin-session-hint"),
+            greeter_events!(@hint greeter, sender, connect_autologin_timeout_hint_notify, "autologin-timeout-hint"),
+            greeter_events!(@hint greeter, sender, connect_autologin_user_hint_notify, "autologin-user-hint"),
+            greeter_events!(@hint greeter, sender, connect_default_session_hint_notify, "default-session-hint"),
+            greeter_events!(@hint greeter, sender, connect_has_guest_account_hint_notify, "has-guest-account-hint"),
+            greeter_events!(@hint greeter, sender, connect_hide_users_hint_notify, "hide-users-hint"),
+            greeter_events!(@hint greeter, sender, connect_lock_hint_notify, "lock-hint"),
+            greeter_events!(@hint greeter, sender, connect_select_guest_hint_notify, "select-guest-hint"),
+            greeter_events!(@hint greeter, sender, connect_select_user_hint_notify, "select-user-hint"),
+            greeter_events!(@hint greeter, sender, connect_show_manual_login_hint_notify, "show-manual-login-hint"),
+            greeter_events!(@hint greeter, sender, connect_show_remote_login_hint_notify, "show-remote-login-hint"),
+        ];
+
+        let greeter = greeter.clone();
+        GreeterEvents {
+            receiver,
+            disconnect: Some(Box_::new(move || {
+                for id in handlers {
+                    greeter.disconnect(id);
+                }
+            })),
+        }
+    }};
+}
+
+impl GreeterEvents {
+    #[cfg(feature = "liblightdm")]
+    pub(crate) fn new(greeter: &crate::Greeter) -> Self {
+        use crate::prelude::GreeterExt;
+        use glib::object::ObjectExt;
+
+        greeter_events!(greeter)
+    }
+
+    #[cfg(feature = "native")]
+    pub(crate) fn new_native(greeter: &crate::native::Greeter) -> Self {
+        use crate::native::GreeterExt;
+
+        greeter_events!(greeter)
+    }
+}
+
+impl Stream for GreeterEvents {
+    type Item = GreeterEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl Drop for GreeterEvents {
+    fn drop(&mut self) {
+        if let Some(disconnect) = self.disconnect.take() {
+            disconnect();
+        }
+    }
+}
@@ -1,12 +1,23 @@
-use glib::{object::IsA, translate::*};
+use glib::{object::IsA, prelude::ObjectExt, translate::*};
 
-use std::{boxed::Box as Box_, pin::Pin};
+use std::{
+    boxed::Box as Box_,
+    cell::{Cell, RefCell},
+    pin::Pin,
+};
 
-use super::{Greeter, GreeterError};
+use super::{
+    Error, Greeter, GreeterEvents, Layout, PromptType, Secret, Session, User, functions,
+    locale::LocaleCode,
+    prelude::{GreeterExt, LanguageExt, UserExt},
+    sessions::{self, SessionSource},
+    xkb::{self, UserLayouts},
+};
 
 pub trait GreeterExtManual: IsA<Greeter> + 'static {
     #[doc(alias = "lightdm_greeter_authenticate")]
-    fn authenticate(&self, username: Option<&str>) -> Result<(), glib::Error> {
+    fn authenticate(&self, username: Option<&str>) -> Result<(), Error> {
+        forget_prompts(self.as_ref());
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok = ffi::lightdm_greeter_authenticate(
@@ -18,10 +29,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(5),
-                        "lightdm_greeter_authenticate() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_authenticate"))
                 }
             } else {
                 Err(from_glib_full(error))
@@ -30,7 +38,8 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     }
 
     #[doc(alias = "lightdm_greeter_authenticate_as_guest")]
-    fn authenticate_as_guest(&self) -> Result<(), glib::Error> {
+    fn authenticate_as_guest(&self) -> Result<(), Error> {
+        forget_prompts(self.as_ref());
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok = ffi::lightdm_greeter_authenticate_as_guest(
@@ -41,10 +50,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(6),
-                        "lightdm_greeter_authenticate_as_guest() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_authenticate_as_guest"))
                 }
             } else {
                 Err(from_glib_full(error))
@@ -53,7 +59,8 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     }
 
     #[doc(alias = "lightdm_greeter_authenticate_autologin")]
-    fn authenticate_autologin(&self) -> Result<(), glib::Error> {
+    fn authenticate_autologin(&self) -> Result<(), Error> {
+        forget_prompts(self.as_ref());
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok = ffi::lightdm_greeter_authenticate_autologin(
@@ -64,10 +71,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(7),
-                        "lightdm_greeter_authenticate_autologin() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_authenticate_autologin"))
                 }
             } else {
                 Err(from_glib_full(error))
@@ -76,11 +80,8 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     }
 
     #[doc(alias = "lightdm_greeter_authenticate_remote")]
-    fn authenticate_remote(
-        &self,
-        session: &str,
-        username: Option<&str>,
-    ) -> Result<(), glib::Error> {
+    fn authenticate_remote(&self, session: &str, username: Option<&str>) -> Result<(), Error> {
+        forget_prompts(self.as_ref());
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok = ffi::lightdm_greeter_authenticate_remote(
@@ -93,10 +94,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(8),
-                        "lightdm_greeter_authenticate_remote() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_authenticate_remote"))
                 }
             } else {
                 Err(from_glib_full(error))
@@ -105,7 +103,8 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     }
 
     #[doc(alias = "lightdm_greeter_cancel_authentication")]
-    fn cancel_authentication(&self) -> Result<(), glib::Error> {
+    fn cancel_authentication(&self) -> Result<(), Error> {
+        forget_prompts(self.as_ref());
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok = ffi::lightdm_greeter_cancel_authentication(
@@ -116,10 +115,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(9),
-                        "lightdm_greeter_cancel_authentication() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_cancel_authentication"))
                 }
             } else {
                 Err(from_glib_full(error))
@@ -130,7 +126,9 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     #[cfg_attr(feature = "v1_11_1", deprecated = "Since 1.11.1")]
     #[allow(deprecated)]
     #[doc(alias = "lightdm_greeter_connect_sync")]
-    fn connect_sync(&self) -> Result<(), glib::Error> {
+    fn connect_sync(&self) -> Result<(), Error> {
+        remember_default_layout(self.as_ref());
+        track_prompts(self.as_ref());
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok =
@@ -139,10 +137,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(10),
-                        "lightdm_greeter_connect_sync() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_connect_sync"))
                 }
             } else {
                 Err(from_glib_full(error))
@@ -151,7 +146,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     }
 
     #[doc(alias = "lightdm_greeter_connect_to_daemon")]
-    fn connect_to_daemon<P: FnOnce(Result<(), glib::Error>) + 'static>(
+    fn connect_to_daemon<P: FnOnce(Result<(), Error>) + 'static>(
         &self,
         cancellable: Option<&impl IsA<gio::Cancellable>>,
         callback: P,
@@ -166,10 +161,12 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
             "Async operations only allowed if the thread is owning the MainContext"
         );
 
+        remember_default_layout(self.as_ref());
+        track_prompts(self.as_ref());
         let user_data: Box_<glib::thread_guard::ThreadGuard<P>> =
             Box_::new(glib::thread_guard::ThreadGuard::new(callback));
         unsafe extern "C" fn connect_to_daemon_trampoline<
-            P: FnOnce(Result<(), glib::Error>) + 'static,
+            P: FnOnce(Result<(), Error>) + 'static,
         >(
             _source_object: *mut glib::gobject_ffi::GObject,
             res: *mut gio::ffi::GAsyncResult,
@@ -187,9 +184,8 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(11),
-                        "lightdm_greeter_connect_to_daemon_finish() return false with null error",
+                    Err(Error::call_failed(
+                        "lightdm_greeter_connect_to_daemon_finish",
                     ))
                 }
             } else {
@@ -213,7 +209,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
 
     fn connect_to_daemon_future(
         &self,
-    ) -> Pin<Box_<dyn std::future::Future<Output = Result<(), glib::Error>> + 'static>> {
+    ) -> Pin<Box_<dyn std::future::Future<Output = Result<(), Error>> + 'static>> {
         Box_::pin(gio::GioFuture::new(self, move |obj, cancellable, send| {
             obj.connect_to_daemon(Some(cancellable), move |res| {
                 send.resolve(res);
@@ -222,7 +218,9 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     }
 
     #[doc(alias = "lightdm_greeter_connect_to_daemon_sync")]
-    fn connect_to_daemon_sync(&self) -> Result<(), glib::Error> {
+    fn connect_to_daemon_sync(&self) -> Result<(), Error> {
+        remember_default_layout(self.as_ref());
+        track_prompts(self.as_ref());
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok = ffi::lightdm_greeter_connect_to_daemon_sync(
@@ -233,10 +231,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(12),
-                        "lightdm_greeter_connect_to_daemon_sync() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_connect_to_daemon_sync"))
                 }
             } else {
                 Err(from_glib_full(error))
@@ -245,7 +240,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     }
 
     #[doc(alias = "lightdm_greeter_ensure_shared_data_dir")]
-    fn ensure_shared_data_dir<P: FnOnce(Result<glib::GString, glib::Error>) + 'static>(
+    fn ensure_shared_data_dir<P: FnOnce(Result<glib::GString, Error>) + 'static>(
         &self,
         username: &str,
         cancellable: Option<&impl IsA<gio::Cancellable>>,
@@ -264,7 +259,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
         let user_data: Box_<glib::thread_guard::ThreadGuard<P>> =
             Box_::new(glib::thread_guard::ThreadGuard::new(callback));
         unsafe extern "C" fn ensure_shared_data_dir_trampoline<
-            P: FnOnce(Result<glib::GString, glib::Error>) + 'static,
+            P: FnOnce(Result<glib::GString, Error>) + 'static,
         >(
             _source_object: *mut glib::gobject_ffi::GObject,
             res: *mut gio::ffi::GAsyncResult,
@@ -281,9 +276,8 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                     if !ret.is_null() {
                         Ok(from_glib_full(ret))
                     } else {
-                        Err(glib::Error::new(
-                            GreeterError::__Unknown(13),
-                            "lightdm_greeter_ensure_shared_data_dir_finish() return null with null error",
+                        Err(Error::call_failed(
+                            "lightdm_greeter_ensure_shared_data_dir_finish",
                         ))
                     }
                 } else {
@@ -310,8 +304,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     fn ensure_shared_data_dir_future(
         &self,
         username: &str,
-    ) -> Pin<Box_<dyn std::future::Future<Output = Result<glib::GString, glib::Error>> + 'static>>
-    {
+    ) -> Pin<Box_<dyn std::future::Future<Output = Result<glib::GString, Error>> + 'static>> {
         let username = String::from(username);
         Box_::pin(gio::GioFuture::new(self, move |obj, cancellable, send| {
             obj.ensure_shared_data_dir(&username, Some(cancellable), move |res| {
@@ -321,7 +314,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     }
 
     #[doc(alias = "lightdm_greeter_ensure_shared_data_dir_sync")]
-    fn ensure_shared_data_dir_sync(&self, username: &str) -> Result<glib::GString, glib::Error> {
+    fn ensure_shared_data_dir_sync(&self, username: &str) -> Result<glib::GString, Error> {
         unsafe {
             let mut error = std::ptr::null_mut();
             let ret = ffi::lightdm_greeter_ensure_shared_data_dir_sync(
@@ -333,9 +326,8 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if !ret.is_null() {
                     Ok(from_glib_full(ret))
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(14),
-                        "lightdm_greeter_ensure_shared_data_dir_sync() return null with null error",
+                    Err(Error::call_failed(
+                        "lightdm_greeter_ensure_shared_data_dir_sync",
                     ))
                 }
             } else {
@@ -344,8 +336,18 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
         }
     }
 
+    /// Returns a stream of the signals and hint changes emitted by this greeter.
+    fn events(&self) -> GreeterEvents {
+        GreeterEvents::new(self.as_ref())
+    }
+
+    /// Answers a prompt, failing with [`Error::SecretPrompt`] if the prompt is a
+    /// [`PromptType::Secret`] one, which [`respond_secret`](Self::respond_secret) answers.
     #[doc(alias = "lightdm_greeter_respond")]
-    fn respond(&self, response: &str) -> Result<(), glib::Error> {
+    fn respond(&self, response: &str) -> Result<(), Error> {
+        if next_prompt(self.as_ref()) == Some(PromptType::Secret) {
+            return Err(Error::SecretPrompt);
+        }
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok = ffi::lightdm_greeter_respond(
@@ -355,12 +357,37 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
             );
             if error.is_null() {
                 if is_ok == glib::ffi::GTRUE {
+                    prompt_answered(self.as_ref());
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(15),
-                        "lightdm_greeter_respond() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_respond"))
+                }
+            } else {
+                Err(from_glib_full(error))
+            }
+        }
+    }
+
+    /// Answers a prompt with a secret, wiping the copy passed to liblightdm afterwards.
+    ///
+    /// liblightdm keeps its own copy of the response until it is sent to the daemon. Fails if
+    /// the response contains a NUL byte.
+    #[doc(alias = "lightdm_greeter_respond")]
+    fn respond_secret(&self, response: &Secret) -> Result<(), Error> {
+        let response = response.to_c_bytes()?;
+        unsafe {
+            let mut error = std::ptr::null_mut();
+            let is_ok = ffi::lightdm_greeter_respond(
+                self.as_ref().to_glib_none().0,
+                response.as_ptr() as *const _,
+                &mut error,
+            );
+            if error.is_null() {
+                if is_ok == glib::ffi::GTRUE {
+                    prompt_answered(self.as_ref());
+                    Ok(())
+                } else {
+                    Err(Error::call_failed("lightdm_greeter_respond"))
                 }
             } else {
                 Err(from_glib_full(error))
@@ -368,8 +395,9 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
         }
     }
 
+    #[deprecated = "use `set_locale`, which checks the code is a well-formed, installed language"]
     #[doc(alias = "lightdm_greeter_set_language")]
-    fn set_language(&self, language: &str) -> Result<(), glib::Error> {
+    fn set_language(&self, language: &str) -> Result<(), Error> {
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok = ffi::lightdm_greeter_set_language(
@@ -381,10 +409,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(16),
-                        "lightdm_greeter_set_language() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_set_language"))
                 }
             } else {
                 Err(from_glib_full(error))
@@ -392,8 +417,26 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
         }
     }
 
+    /// Sets the language of the session to the installed language matching `code`, ignoring
+    /// the codeset. Fails with [`Error::UnknownLanguage`] if there is none.
+    #[allow(deprecated)]
+    #[doc(alias = "lightdm_greeter_set_language")]
+    fn set_locale(&self, code: &LocaleCode) -> Result<(), Error> {
+        let wanted = code.without_codeset();
+        let language = functions::languages()
+            .into_iter()
+            .filter_map(|language| language.code())
+            .find(|other| {
+                other
+                    .parse::<LocaleCode>()
+                    .is_ok_and(|other| other.without_codeset() == wanted)
+            })
+            .ok_or_else(|| Error::UnknownLanguage(code.to_string()))?;
+        self.set_language(&language)
+    }
+
     #[doc(alias = "lightdm_greeter_start_session")]
-    fn start_session<P: FnOnce(Result<(), glib::Error>) + 'static>(
+    fn start_session<P: FnOnce(Result<(), Error>) + 'static>(
         &self,
         session: Option<&str>,
         cancellable: Option<&impl IsA<gio::Cancellable>>,
@@ -411,9 +454,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
 
         let user_data: Box_<glib::thread_guard::ThreadGuard<P>> =
             Box_::new(glib::thread_guard::ThreadGuard::new(callback));
-        unsafe extern "C" fn start_session_trampoline<
-            P: FnOnce(Result<(), glib::Error>) + 'static,
-        >(
+        unsafe extern "C" fn start_session_trampoline<P: FnOnce(Result<(), Error>) + 'static>(
             _source_object: *mut glib::gobject_ffi::GObject,
             res: *mut gio::ffi::GAsyncResult,
             user_data: glib::ffi::gpointer,
@@ -429,10 +470,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                     if is_ok == glib::ffi::GTRUE {
                         Ok(())
                     } else {
-                        Err(glib::Error::new(
-                            GreeterError::__Unknown(17),
-                            "lightdm_greeter_start_session_finish() return false with null error",
-                        ))
+                        Err(Error::call_failed("lightdm_greeter_start_session_finish"))
                     }
                 } else {
                     Err(from_glib_full(error))
@@ -458,7 +496,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     fn start_session_future(
         &self,
         session: Option<&str>,
-    ) -> Pin<Box_<dyn std::future::Future<Output = Result<(), glib::Error>> + 'static>> {
+    ) -> Pin<Box_<dyn std::future::Future<Output = Result<(), Error>> + 'static>> {
         let session = session.map(ToOwned::to_owned);
         Box_::pin(gio::GioFuture::new(self, move |obj, cancellable, send| {
             obj.start_session(
@@ -472,7 +510,7 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
     }
 
     #[doc(alias = "lightdm_greeter_start_session_sync")]
-    fn start_session_sync(&self, session: Option<&str>) -> Result<(), glib::Error> {
+    fn start_session_sync(&self, session: Option<&str>) -> Result<(), Error> {
         unsafe {
             let mut error = std::ptr::null_mut();
             let is_ok = ffi::lightdm_greeter_start_session_sync(
@@ -484,16 +522,155 @@ pub trait GreeterExtManual: IsA<Greeter> + 'static {
                 if is_ok == glib::ffi::GTRUE {
                     Ok(())
                 } else {
-                    Err(glib::Error::new(
-                        GreeterError::__Unknown(18),
-                        "lightdm_greeter_start_session_sync() return false with null error",
-                    ))
+                    Err(Error::call_failed("lightdm_greeter_start_session_sync"))
                 }
             } else {
                 Err(from_glib_full(error))
             }
         }
     }
+
+    /// The session [`start_session_sync`](Self::start_session_sync) should be given for `user`,
+    /// and why it was chosen.
+    ///
+    /// Tries the user's last session, then the `autologin-session` hint if `user` is `None` or
+    /// the autologin user, then the `default-session` hint, then the first installed session.
+    /// Keys which are no longer installed are skipped. Fails with [`Error::NoSession`] if no
+    /// session is installed at all.
+    fn resolve_session(&self, user: Option<&User>) -> Result<(Session, SessionSource), Error> {
+        sessions::preferred_session(self.as_ref(), user, &functions::sessions())
+            .ok_or(Error::NoSession)
+    }
+
+    /// Applies the keyboard layouts of `user` (`layouts()`, or else `layout()`) and returns them
+    /// in order for the layout indicator, along with the names which are not installed.
+    ///
+    /// The first resolved layout is set with `functions::set_layout`. If none resolves, the
+    /// default layout is restored as by [`restore_default_layout`](Self::restore_default_layout).
+    /// The first call also connects a `reset` handler which restores the default layout.
+    fn apply_user_layouts(&self, user: &impl IsA<User>) -> UserLayouts {
+        let greeter = self.as_ref();
+        if let Some(default) = default_layout(greeter)
+            && !default.restore_on_reset.replace(true)
+        {
+            greeter.connect_reset(|greeter| greeter.restore_default_layout());
+        }
+        let user = user.as_ref();
+        let mut names = user.layouts();
+        if names.is_empty() {
+            names.extend(user.layout());
+        }
+        let resolved = xkb::resolve_layouts(&names);
+        match resolved.layouts.first() {
+            Some(layout) => functions::set_layout(layout),
+            None => self.restore_default_layout(),
+        }
+        resolved
+    }
+
+    /// Sets the layout back to the one active when the greeter connected to the daemon, e.g.
+    /// when the user selection is cleared. Does nothing if the greeter never connected.
+    fn restore_default_layout(&self) {
+        if let Some(layout) =
+            default_layout(self.as_ref()).and_then(|default| default.layout.as_ref())
+        {
+            functions::set_layout(layout);
+        }
+    }
 }
 
 impl<O: IsA<Greeter>> GreeterExtManual for O {}
+
+const DEFAULT_LAYOUT_KEY: &str = "lightdm-rs-default-layout";
+
+/// The layout active when a greeter first connected, kept on the greeter object.
+struct DefaultLayout {
+    layout: Option<Layout>,
+    /// Whether a `reset` handler restoring the layout was connected.
+    restore_on_reset: Cell<bool>,
+}
+
+fn default_layout(greeter: &Greeter) -> Option<&DefaultLayout> {
+    // SAFETY: the data is only ever set to a `DefaultLayout`, and never replaced or removed.
+    unsafe {
+        greeter
+            .data::<DefaultLayout>(DEFAULT_LAYOUT_KEY)
+            .map(|default| default.as_ref())
+    }
+}
+
+fn remember_default_layout(greeter: &Greeter) {
+    if default_layout(greeter).is_none() {
+        // SAFETY: see `default_layout`.
+        unsafe {
+            greeter.set_data(
+                DEFAULT_LAYOUT_KEY,
+                DefaultLayout {
+                    layout: functions::layout(),
+                    restore_on_reset: Cell::new(false),
+                },
+            );
+        }
+    }
+}
+
+const PROMPTS_KEY: &str = "lightdm-rs-prompts";
+
+/// The prompts shown since the responses were last sent, kept on the greeter object so that
+/// `respond` can tell whether the next one is secret.
+#[derive(Default)]
+struct Prompts {
+    types: RefCell<Vec<PromptType>>,
+    answered: Cell<usize>,
+}
+
+fn prompts(greeter: &Greeter) -> Option<&Prompts> {
+    // SAFETY: the data is only ever set to `Prompts`, and never replaced or removed.
+    unsafe {
+        greeter
+            .data::<Prompts>(PROMPTS_KEY)
+            .map(|prompts| prompts.as_ref())
+    }
+}
+
+fn track_prompts(greeter: &Greeter) {
+    if prompts(greeter).is_some() {
+        return;
+    }
+    // SAFETY: see `prompts`.
+    unsafe {
+        greeter.set_data(PROMPTS_KEY, Prompts::default());
+    }
+    greeter.connect_show_prompt(|greeter, _, prompt_type| {
+        let Some(prompts) = prompts(greeter) else {
+            return;
+        };
+        // liblightdm sends the responses once every prompt of a request was answered.
+        if prompts.answered.get() == prompts.types.borrow().len() {
+            prompts.types.borrow_mut().clear();
+            prompts.answered.set(0);
+        }
+        prompts.types.borrow_mut().push(prompt_type);
+    });
+    greeter.connect_authentication_complete(forget_prompts);
+    greeter.connect_reset(forget_prompts);
+}
+
+fn forget_prompts(greeter: &Greeter) {
+    if let Some(prompts) = prompts(greeter) {
+        prompts.types.borrow_mut().clear();
+        prompts.answered.set(0);
+    }
+}
+
+/// The type of the first prompt not answered yet, if the greeter connected and one is pending.
+fn next_prompt(greeter: &Greeter) -> Option<PromptType> {
+    let prompts = prompts(greeter)?;
+    prompts.types.borrow().get(prompts.answered.get()).copied()
+}
+
+fn prompt_answered(greeter: &Greeter) {
+    if let Some(prompts) = prompts(greeter) {
+        prompts.answered.set(prompts.answered.get() + 1);
+    }
+}
@@ -0,0 +1,249 @@
+//! Reader for the GKeyFile format used by LightDM's configuration and by desktop entries.
+//!
+//! Later files loaded into the same [`KeyFile`] override keys of earlier ones, and groups keep
+//! the order in which they first appeared.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::config::Error;
+
+/// Where a value was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Origin {
+    pub(crate) path: Arc<Path>,
+    pub(crate) line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Entry {
+    pub(crate) key: String,
+    /// The raw value, with escape sequences still in place.
+    pub(crate) value: String,
+    /// `None` for built-in defaults.
+    pub(crate) origin: Option<Origin>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Group {
+    pub(crate) name: String,
+    pub(crate) entries: Vec<Entry>,
+}
+
+impl Group {
+    pub(crate) fn set(&mut self, entry: Entry) {
+        match self.entries.iter_mut().find(|old| old.key == entry.key) {
+            Some(old) => *old = entry,
+            None => self.entries.push(entry),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KeyFile {
+    groups: Vec<Group>,
+}
+
+impl KeyFile {
+    pub(crate) fn groups(&self) -> impl Iterator<Item = &Group> {
+        self.groups.iter()
+    }
+
+    pub(crate) fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    /// Sets a built-in default.
+    pub(crate) fn set_default(&mut self, group: &str, key: &str, value: &str) {
+        self.group_mut(group).set(Entry {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            origin: None,
+        });
+    }
+
+    pub(crate) fn group_mut(&mut self, name: &str) -> &mut Group {
+        let index = match self.groups.iter().position(|group| group.name == name) {
+            Some(index) => index,
+            None => {
+                self.groups.push(Group {
+                    name: name.to_owned(),
+                    entries: Vec::new(),
+                });
+                self.groups.len() - 1
+            }
+        };
+        &mut self.groups[index]
+    }
+
+    /// Parses `text` on top of the keys already loaded. `rename` maps legacy group names.
+    pub(crate) fn load_str(
+        &mut self,
+        text: &str,
+        path: &Path,
+        rename: impl Fn(&str) -> &str,
+    ) -> Result<(), Error> {
+        let source: Arc<Path> = path.into();
+        let mut group = None;
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| Error::Parse {
+                path: path.to_owned(),
+                line: index + 1,
+                message,
+            };
+            if let Some(name) = line.strip_prefix('[') {
+                let Some(name) = name.strip_suffix(']') else {
+                    return Err(error(format!("Unterminated group header {line:?}")));
+                };
+                if name.is_empty() || name.contains(['[', ']']) {
+                    return Err(error(format!("Invalid group name {name:?}")));
+                }
+                group = Some(rename(name).to_owned());
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error(format!("Expected key=value, found {line:?}")));
+            };
+            let key = key.trim_end();
+            if key.is_empty() {
+                return Err(error("Missing key name".to_owned()));
+            }
+            let Some(group) = &group else {
+                return Err(error(format!("Key {key:?} is not in a group")));
+            };
+            self.group_mut(group).set(Entry {
+                key: key.to_owned(),
+                value: value.trim_start().to_owned(),
+                origin: Some(Origin {
+                    path: source.clone(),
+                    line: index + 1,
+                }),
+            });
+        }
+        Ok(())
+    }
+
+    /// Loads `path`, returning `Ok(false)` if it does not exist.
+    pub(crate) fn load_file(
+        &mut self,
+        path: &Path,
+        rename: impl Fn(&str) -> &str,
+    ) -> Result<bool, Error> {
+        match fs::read_to_string(path) {
+            Ok(text) => self.load_str(&text, path, rename).map(|()| true),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(Error::Io {
+                path: path.to_owned(),
+                source: err,
+            }),
+        }
+    }
+}
+
+/// The `*.<extension>` files of `dir` in the order LightDM loads them.
+pub(crate) fn files(dir: &Path, extension: &str) -> Result<Vec<PathBuf>, Error> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(Error::Io {
+                path: dir.to_owned(),
+                source: err,
+            });
+        }
+    };
+    let mut files = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+/// Undoes the `\s`, `\n`, `\t`, `\r` and `\\` escapes of a value.
+pub(crate) fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Splits a `;`-separated list, honouring `\;` and dropping the trailing separator.
+pub(crate) fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                item.push(c);
+                if let Some(c) = chars.next() {
+                    item.push(c);
+                }
+            }
+            ';' => items.push(unescape(&std::mem::take(&mut item))),
+            c => item.push(c),
+        }
+    }
+    if !item.is_empty() {
+        items.push(unescape(&item));
+    }
+    items
+}
+
+pub(crate) fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim_end() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Matches `name` against a glob where `*` matches any run of characters and `?` any one.
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (pattern.as_bytes(), name.as_bytes());
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
@@ -0,0 +1,39 @@
+use glib::object::IsA;
+
+use super::{
+    Language,
+    locale::{LocaleCode, LocaleNames, language_code},
+    prelude::LanguageExt,
+};
+
+pub trait LanguageExtManual: IsA<Language> + 'static {
+    /// The name of the language for someone reading `display`, falling back to `name()`.
+    fn name_in(&self, names: &LocaleNames, display: &LocaleCode) -> Option<String> {
+        language_code(self)
+            .and_then(|code| names.language_name_in(&code.language, display))
+            .or_else(|| self.as_ref().name().map(Into::into))
+    }
+
+    /// The name of the territory for someone reading `display`, falling back to `territory()`.
+    fn territory_in(&self, names: &LocaleNames, display: &LocaleCode) -> Option<String> {
+        language_code(self)
+            .and_then(|code| names.territory_name_in(code.territory.as_deref()?, display))
+            .or_else(|| self.as_ref().territory().map(Into::into))
+    }
+
+    /// The name of the language in itself, e.g. `Deutsch`, falling back to `name()`.
+    fn native_name(&self, names: &LocaleNames) -> Option<String> {
+        language_code(self)
+            .and_then(|code| names.native_language_name(&code))
+            .or_else(|| self.as_ref().name().map(Into::into))
+    }
+
+    /// The name of the territory in the language itself, falling back to `territory()`.
+    fn native_territory(&self, names: &LocaleNames) -> Option<String> {
+        language_code(self)
+            .and_then(|code| names.native_territory_name(&code))
+            .or_else(|| self.as_ref().territory().map(Into::into))
+    }
+}
+
+impl<O: IsA<Language>> LanguageExtManual for O {}
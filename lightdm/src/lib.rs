@@ -1,27 +1,102 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(not(any(feature = "liblightdm", feature = "native")))]
+compile_error!("either the `liblightdm` or the `native` feature must be enabled");
+
+#[cfg(feature = "liblightdm")]
 macro_rules! skip_assert_initialized {
     () => {};
 }
 
+#[cfg(feature = "liblightdm")]
 macro_rules! assert_initialized_main_thread {
     () => {};
 }
 
+#[cfg(feature = "liblightdm")]
 pub use ffi;
 pub use glib;
 
+#[cfg(feature = "liblightdm")]
 #[allow(unused_imports)]
 mod auto;
+#[cfg(feature = "liblightdm")]
 pub use auto::*;
 
+pub mod auth;
+
+pub mod accounts;
+
+pub mod avatar;
+
+pub mod config;
+pub mod dbus;
+
+mod error;
+pub use error::Error;
+
+mod events;
+pub use events::{GreeterEvent, GreeterEvents};
+
+#[cfg(feature = "liblightdm")]
 mod greeter;
 
+mod keyfile;
+
+#[cfg(feature = "liblightdm")]
+mod language;
+pub mod locale;
+
+pub mod motd;
+
+#[cfg(any(feature = "native", feature = "testing"))]
+mod protocol;
+
+#[cfg(feature = "native")]
+#[cfg_attr(docsrs, doc(cfg(feature = "native")))]
+pub mod native;
+#[cfg(not(feature = "liblightdm"))]
+pub use native::{Greeter, GreeterError, MessageType, PromptType};
+
+pub mod os_release;
+
+pub mod power;
+
+mod secret;
+pub use secret::Secret;
+
+#[cfg(feature = "liblightdm")]
+mod session;
+pub mod sessions;
+
+#[cfg(feature = "liblightdm")]
+pub mod snapshot;
+
+#[cfg(feature = "testing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
+pub mod testing;
+
+#[cfg(feature = "liblightdm")]
+mod user;
+
+pub mod xkb;
+
 pub mod prelude {
+    #[cfg(feature = "liblightdm")]
     pub use super::auto::traits::*;
+    #[cfg(feature = "liblightdm")]
     pub use super::greeter::GreeterExtManual;
+    #[cfg(feature = "liblightdm")]
+    pub use super::language::LanguageExtManual;
+    #[cfg(not(feature = "liblightdm"))]
+    pub use super::native::{GreeterExt, GreeterExtManual};
+    #[cfg(feature = "liblightdm")]
+    pub use super::session::SessionExtManual;
+    #[cfg(feature = "liblightdm")]
+    pub use super::user::UserExtManual;
 }
 
+#[cfg(feature = "liblightdm")]
 pub mod functions {
     pub use super::auto::functions::*;
 }
@@ -0,0 +1,328 @@
+//! POSIX locale codes as used by `Language::code` and `set_locale`, and negotiation of the
+//! best installed language for a list of preferences.
+//!
+//! [`LocaleCode`] parses `language[_TERRITORY][.codeset][@modifier]` as well as BCP-47 tags
+//! like `sr-Latn-RS`, and prints the POSIX form with the codeset normalised, so that
+//! `en_US.utf8` and `en_US.UTF-8` compare equal.
+
+#[cfg(feature = "liblightdm")]
+use glib::object::IsA;
+
+use std::{fmt, str::FromStr};
+
+#[cfg(feature = "liblightdm")]
+use crate::{Language, functions, prelude::LanguageExt};
+
+mod names;
+pub use names::LocaleNames;
+
+/// Error parsing a [`LocaleCode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidLocale(pub String);
+
+impl fmt::Display for InvalidLocale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid locale code {:?}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidLocale {}
+
+/// A locale such as `en_US.UTF-8` or `sr_RS@latin`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocaleCode {
+    /// Lowercase ISO 639 code, or `C` for the C and POSIX locales.
+    pub language: String,
+    /// Uppercase ISO 3166 code or UN M.49 number.
+    pub territory: Option<String>,
+    pub codeset: Option<String>,
+    pub modifier: Option<String>,
+}
+
+const SCRIPTS: [(&str, &str); 4] = [
+    ("latin", "Latn"),
+    ("cyrillic", "Cyrl"),
+    ("devanagari", "Deva"),
+    ("arabic", "Arab"),
+];
+
+impl LocaleCode {
+    pub fn new(language: &str, territory: Option<&str>) -> Result<Self, InvalidLocale> {
+        let invalid = || InvalidLocale(language.to_owned());
+        if !is_language(language) || territory.is_some_and(|territory| !is_territory(territory)) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            language: match language {
+                "C" | "POSIX" => "C".to_owned(),
+                language => language.to_ascii_lowercase(),
+            },
+            territory: territory.map(str::to_ascii_uppercase),
+            codeset: None,
+            modifier: None,
+        })
+    }
+
+    /// Parses a BCP-47 tag: `language[-Script][-REGION]`, ignoring any further subtags.
+    pub fn from_bcp47(tag: &str) -> Result<Self, InvalidLocale> {
+        let invalid = || InvalidLocale(tag.to_owned());
+        let mut subtags = tag.split(['-', '_']);
+        let language = subtags.next().filter(|language| is_language(language));
+        let mut code = Self::new(language.ok_or_else(invalid)?, None)?;
+        for subtag in subtags {
+            if subtag.len() == 4 && subtag.chars().all(|c| c.is_ascii_alphabetic()) {
+                let script = SCRIPTS
+                    .iter()
+                    .find(|(_, script)| script.eq_ignore_ascii_case(subtag));
+                code.modifier = script.map(|(modifier, _)| (*modifier).to_owned());
+            } else if is_territory(subtag) {
+                code.territory = Some(subtag.to_ascii_uppercase());
+                break;
+            } else {
+                break;
+            }
+        }
+        Ok(code)
+    }
+
+    /// The BCP-47 tag, e.g. `en-US` or `sr-Latn-RS`; `und` for the C locale.
+    pub fn to_bcp47(&self) -> String {
+        if self.is_c() {
+            return "und".to_owned();
+        }
+        let mut tag = self.language.clone();
+        let script = self.modifier.as_deref().and_then(|modifier| {
+            SCRIPTS
+                .iter()
+                .find(|(name, _)| *name == modifier)
+                .map(|(_, script)| *script)
+        });
+        if let Some(script) = script {
+            tag.push('-');
+            tag.push_str(script);
+        }
+        if let Some(territory) = &self.territory {
+            tag.push('-');
+            tag.push_str(territory);
+        }
+        tag
+    }
+
+    /// Whether this is the `C` or `POSIX` locale.
+    pub fn is_c(&self) -> bool {
+        self.language == "C"
+    }
+
+    /// The code without its codeset, which LightDM ignores when comparing languages.
+    pub fn without_codeset(&self) -> Self {
+        Self {
+            codeset: None,
+            ..self.clone()
+        }
+    }
+
+    /// The names to look translations up under, most specific first: `sr_RS@latin`, `sr_RS`,
+    /// `sr@latin`, `sr`.
+    pub fn fallbacks(&self) -> Vec<String> {
+        split_fallbacks(&self.without_codeset().to_string())
+    }
+
+    /// The [`fallbacks`](Self::fallbacks) of `locale`, or for a code which does not parse, such
+    /// as the `zh_Hant` of a `Name[zh_Hant]` key, its parts as GLib splits them.
+    pub fn fallbacks_of(locale: &str) -> Vec<String> {
+        match locale.parse::<Self>() {
+            Ok(code) => code.fallbacks(),
+            Err(_) => split_fallbacks(locale),
+        }
+    }
+
+    /// How well `self` serves someone asking for `wanted`: 0 if the languages or the modifiers
+    /// differ, else 1 plus two points for a matching territory, one for no territory and one for
+    /// a matching modifier. A missing territory or modifier in `wanted` matches anything.
+    ///
+    /// Another territory of the same language still scores, below a match, as the language
+    /// alone is a better answer than none (RFC 4647 lookup): `de_DE` serves `de_CH`.
+    pub fn score(&self, wanted: &LocaleCode) -> u32 {
+        if self.language != wanted.language {
+            return 0;
+        }
+        let territory = match (&self.territory, &wanted.territory) {
+            (_, None) => 2,
+            (Some(ours), Some(theirs)) if ours == theirs => 2,
+            (None, Some(_)) => 1,
+            (Some(_), Some(_)) => 0,
+        };
+        match (&self.modifier, &wanted.modifier) {
+            (_, None) => 1 + territory,
+            (Some(ours), Some(theirs)) if ours == theirs => 2 + territory,
+            _ => 0,
+        }
+    }
+}
+
+/// `language_TERRITORY.codeset@modifier` reduced the way GLib does for translated keys.
+fn split_fallbacks(locale: &str) -> Vec<String> {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let locale = locale.split_once('.').map_or(locale, |(locale, _)| locale);
+    let (language, territory) = match locale.split_once('_') {
+        Some((language, territory)) => (language, Some(territory)),
+        None => (locale, None),
+    };
+    let mut fallbacks = Vec::new();
+    if let (Some(territory), Some(modifier)) = (territory, modifier) {
+        fallbacks.push(format!("{language}_{territory}@{modifier}"));
+    }
+    if let Some(territory) = territory {
+        fallbacks.push(format!("{language}_{territory}"));
+    }
+    if let Some(modifier) = modifier {
+        fallbacks.push(format!("{language}@{modifier}"));
+    }
+    fallbacks.push(language.to_owned());
+    fallbacks
+}
+
+fn is_language(language: &str) -> bool {
+    matches!(language, "C" | "POSIX")
+        || ((2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+fn is_territory(territory: &str) -> bool {
+    (territory.len() == 2 && territory.chars().all(|c| c.is_ascii_alphabetic()))
+        || (territory.len() == 3 && territory.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// `utf8` and `UTF-8` both become `UTF-8`, `iso885915` becomes `ISO-8859-15`.
+fn normalize_codeset(codeset: &str) -> String {
+    let plain = codeset
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if plain == "utf8" {
+        "UTF-8".to_owned()
+    } else if let Some(part) = plain.strip_prefix("iso8859") {
+        format!("ISO-8859-{part}")
+    } else {
+        codeset.to_ascii_uppercase()
+    }
+}
+
+impl FromStr for LocaleCode {
+    type Err = InvalidLocale;
+
+    /// Parses a POSIX locale, or a BCP-47 tag if it contains `-`.
+    fn from_str(s: &str) -> Result<Self, InvalidLocale> {
+        if s.contains('-') && !s.contains(['.', '@']) {
+            return Self::from_bcp47(s);
+        }
+        let invalid = || InvalidLocale(s.to_owned());
+        let (rest, modifier) = match s.split_once('@') {
+            Some((rest, modifier)) if !modifier.is_empty() => (rest, Some(modifier)),
+            Some(_) => return Err(invalid()),
+            None => (s, None),
+        };
+        let (rest, codeset) = match rest.split_once('.') {
+            Some((rest, codeset)) if !codeset.is_empty() => (rest, Some(codeset)),
+            Some(_) => return Err(invalid()),
+            None => (rest, None),
+        };
+        let (language, territory) = match rest.split_once('_') {
+            Some((language, territory)) => (language, Some(territory)),
+            None => (rest, None),
+        };
+        let mut code = Self::new(language, territory).map_err(|_| invalid())?;
+        code.codeset = codeset.map(normalize_codeset);
+        code.modifier = modifier.map(str::to_ascii_lowercase);
+        Ok(code)
+    }
+}
+
+impl fmt::Display for LocaleCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.language)?;
+        if let Some(territory) = &self.territory {
+            write!(f, "_{territory}")?;
+        }
+        if let Some(codeset) = &self.codeset {
+            write!(f, ".{codeset}")?;
+        }
+        if let Some(modifier) = &self.modifier {
+            write!(f, "@{modifier}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `$LANGUAGE`-style list such as `de_CH:de:en`, skipping invalid entries.
+pub fn parse_language_list(list: &str) -> Vec<LocaleCode> {
+    list.split(':')
+        .filter_map(|code| code.trim().parse().ok())
+        .collect()
+}
+
+/// Parses an `Accept-Language` header such as `de-CH, de;q=0.9, en;q=0.5` into codes ordered
+/// by weight, skipping `*`, invalid tags and `q=0`.
+pub fn parse_accept_language(header: &str) -> Vec<LocaleCode> {
+    let mut weighted = header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let code = LocaleCode::from_bcp47(parts.next()?.trim()).ok()?;
+            let weight = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |weight| weight.trim().parse::<f32>().ok())?;
+            (weight > 0.0).then_some((code, weight))
+        })
+        .collect::<Vec<_>>();
+    weighted.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    weighted.into_iter().map(|(code, _)| code).collect()
+}
+
+/// The index of the entry of `available` which best serves `preferences`.
+///
+/// Preferences are tried in order and the first one any code [scores](LocaleCode::score) for
+/// wins, so `de_CH:en` picks `de_DE` over `en_GB` when `de_CH` is not installed. Among equal
+/// scores the code whose territory repeats the language (`de_DE` for `de`)
+/// is preferred, then the first one.
+pub fn negotiate(preferences: &[LocaleCode], available: &[LocaleCode]) -> Option<usize> {
+    preferences.iter().find_map(|wanted| {
+        let rank = |code: &LocaleCode| {
+            let main = code
+                .territory
+                .as_deref()
+                .is_some_and(|territory| territory.eq_ignore_ascii_case(&code.language));
+            (code.score(wanted), main)
+        };
+        available
+            .iter()
+            .enumerate()
+            .filter(|(_, code)| code.score(wanted) > 0)
+            .max_by(|(a_index, a), (b_index, b)| rank(a).cmp(&rank(b)).then(b_index.cmp(a_index)))
+            .map(|(index, _)| index)
+    })
+}
+
+/// The parsed `code` of `language`.
+#[cfg(feature = "liblightdm")]
+pub fn language_code(language: &impl IsA<Language>) -> Option<LocaleCode> {
+    language.code()?.parse().ok()
+}
+
+/// The entry of `functions::languages()` which best serves `preferences`.
+#[cfg(feature = "liblightdm")]
+pub fn negotiate_language(preferences: &[LocaleCode]) -> Option<Language> {
+    let languages = functions::languages()
+        .into_iter()
+        .filter_map(|language| Some((language_code(&language)?, language)))
+        .collect::<Vec<_>>();
+    let codes = languages
+        .iter()
+        .map(|(code, _)| code.clone())
+        .collect::<Vec<_>>();
+    let index = negotiate(preferences, &codes)?;
+    Some(languages[index].1.clone())
+}
@@ -0,0 +1,261 @@
+use serde_json::Value;
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use super::LocaleCode;
+
+type Catalog = Arc<HashMap<String, String>>;
+
+/// Language and territory names from the iso-codes and glibc locale data of the system.
+///
+/// English names come from `iso-codes/json`, their translations from the iso-codes gettext
+/// catalogs in `locale/<locale>/LC_MESSAGES`, and the name of a language in itself from the
+/// `lang_name` and `country_name` of `i18n/locales/<locale>`. Missing or unreadable files
+/// leave the names they would have provided unresolved.
+#[derive(Debug)]
+pub struct LocaleNames {
+    data_dir: PathBuf,
+    languages: HashMap<String, String>,
+    alpha_3: HashMap<String, String>,
+    language_domain: &'static str,
+    territories: HashMap<String, String>,
+    catalogs: Mutex<HashMap<(&'static str, String), Option<Catalog>>>,
+}
+
+impl Default for LocaleNames {
+    fn default() -> Self {
+        Self::load()
+    }
+}
+
+impl LocaleNames {
+    /// Reads the data under `/usr/share`.
+    pub fn load() -> Self {
+        Self::load_from("/usr/share")
+    }
+
+    pub fn load_from(data_dir: impl Into<PathBuf>) -> Self {
+        let data_dir = data_dir.into();
+        let json = data_dir.join("iso-codes/json");
+        let (language_domain, languages) = ["iso_639-3", "iso_639-2"]
+            .into_iter()
+            .find_map(|domain| {
+                let text = fs::read_to_string(json.join(format!("{domain}.json"))).ok()?;
+                Some((domain, objects(&text)))
+            })
+            .unwrap_or_default();
+        let territories = fs::read_to_string(json.join("iso_3166-1.json"))
+            .map(|text| objects(&text))
+            .unwrap_or_default();
+        Self {
+            data_dir,
+            alpha_3: index(&languages, &["alpha_2"], "alpha_3"),
+            languages: index(&languages, &["alpha_2", "alpha_3"], "name"),
+            language_domain,
+            territories: index(&territories, &["alpha_2", "alpha_3", "numeric"], "name"),
+            catalogs: Mutex::default(),
+        }
+    }
+
+    /// The English name of an ISO 639 language code.
+    pub fn language_name(&self, language: &str) -> Option<&str> {
+        self.languages.get(language).map(String::as_str)
+    }
+
+    /// The three-letter ISO 639 code of `language`, e.g. `deu` for `de`, as used by XKB.
+    pub fn alpha_3<'a>(&'a self, language: &'a str) -> &'a str {
+        match language.len() {
+            2 => self.alpha_3.get(language).map_or(language, String::as_str),
+            _ => language,
+        }
+    }
+
+    /// The English name of an ISO 3166 territory code.
+    pub fn territory_name(&self, territory: &str) -> Option<&str> {
+        self.territories.get(territory).map(String::as_str)
+    }
+
+    /// The name of `language` for someone reading `display`, e.g. `allemand` for `de` in `fr`.
+    pub fn language_name_in(&self, language: &str, display: &LocaleCode) -> Option<String> {
+        let name = self.language_name(language)?;
+        Some(self.translate(self.language_domain, name, display))
+    }
+
+    /// The name of `territory` for someone reading `display`.
+    pub fn territory_name_in(&self, territory: &str, display: &LocaleCode) -> Option<String> {
+        let name = self.territory_name(territory)?;
+        Some(self.translate("iso_3166-1", name, display))
+    }
+
+    /// The name of the language of `code` in itself, e.g. `Deutsch` for `de_AT`.
+    pub fn native_language_name(&self, code: &LocaleCode) -> Option<String> {
+        self.glibc_name(code, "lang_name")
+            .or_else(|| self.language_name_in(&code.language, code))
+    }
+
+    /// The name of the territory of `code` in its language, e.g. `Österreich` for `de_AT`.
+    pub fn native_territory_name(&self, code: &LocaleCode) -> Option<String> {
+        self.glibc_name(code, "country_name")
+            .or_else(|| self.territory_name_in(code.territory.as_deref()?, code))
+    }
+
+    fn translate(&self, domain: &'static str, name: &str, display: &LocaleCode) -> String {
+        self.catalog(domain, display)
+            .and_then(|catalog| catalog.get(name).cloned())
+            .unwrap_or_else(|| name.to_owned())
+    }
+
+    fn catalog(&self, domain: &'static str, display: &LocaleCode) -> Option<Catalog> {
+        let key = (domain, display.without_codeset().to_string());
+        let mut catalogs = self.catalogs.lock().unwrap_or_else(PoisonError::into_inner);
+        catalogs
+            .entry(key)
+            .or_insert_with(|| {
+                display.fallbacks().iter().find_map(|locale| {
+                    let path = self
+                        .data_dir
+                        .join("locale")
+                        .join(locale)
+                        .join("LC_MESSAGES")
+                        .join(format!("{domain}.mo"));
+                    Some(Arc::new(read_mo(&fs::read(path).ok()?)?))
+                })
+            })
+            .clone()
+    }
+
+    /// `key` of the `LC_ADDRESS` section of the glibc locale source of `code`.
+    fn glibc_name(&self, code: &LocaleCode, key: &str) -> Option<String> {
+        let locales = self.data_dir.join("i18n/locales");
+        let mut name = code.without_codeset().to_string();
+        // Follow `copy` directives, but not forever.
+        for _ in 0..4 {
+            let text = fs::read_to_string(locales.join(&name)).ok()?;
+            let section = text
+                .lines()
+                .map(str::trim)
+                .skip_while(|line| *line != "LC_ADDRESS")
+                .take_while(|line| *line != "END LC_ADDRESS");
+            let mut copy = None;
+            for line in section {
+                let Some((word, value)) = line.split_once(char::is_whitespace) else {
+                    continue;
+                };
+                if word == key {
+                    return Some(decode_glibc_string(value.trim()));
+                }
+                if word == "copy" {
+                    copy = Some(decode_glibc_string(value.trim()));
+                }
+            }
+            name = copy?;
+        }
+        None
+    }
+}
+
+/// The string members of every object in `json`, none if it is not valid JSON.
+fn objects(json: &str) -> Vec<HashMap<String, String>> {
+    fn collect(value: &Value, objects: &mut Vec<HashMap<String, String>>) {
+        match value {
+            Value::Object(members) => {
+                objects.push(
+                    members
+                        .iter()
+                        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_owned())))
+                        .collect(),
+                );
+                members.values().for_each(|value| collect(value, objects));
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect(value, objects)),
+            _ => {}
+        }
+    }
+    let mut objects = Vec::new();
+    if let Ok(value) = serde_json::from_str(json) {
+        collect(&value, &mut objects);
+    }
+    objects
+}
+
+/// Maps each of the `keys` of the iso-codes entries to their `value`.
+fn index(
+    objects: &[HashMap<String, String>],
+    keys: &[&str],
+    value: &str,
+) -> HashMap<String, String> {
+    let mut index = HashMap::new();
+    for object in objects {
+        let Some(value) = object.get(value) else {
+            continue;
+        };
+        for key in keys {
+            if let Some(code) = object.get(*key) {
+                index.insert(code.clone(), value.clone());
+            }
+        }
+    }
+    index
+}
+
+/// Undoes the quoting and `<Uxxxx>` escapes of a glibc locale source string.
+fn decode_glibc_string(value: &str) -> String {
+    let value = value.trim_matches('"');
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("<U") {
+        decoded.push_str(&rest[..start]);
+        let escape = &rest[start + 2..];
+        let end = escape.find('>').unwrap_or(escape.len());
+        match u32::from_str_radix(&escape[..end], 16)
+            .ok()
+            .and_then(char::from_u32)
+        {
+            Some(c) => decoded.push(c),
+            None => decoded.push_str(&rest[start..start + 2 + end]),
+        }
+        rest = escape.get(end + 1..).unwrap_or_default();
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Reads the singular messages of a gettext `.mo` catalog.
+fn read_mo(data: &[u8]) -> Option<HashMap<String, String>> {
+    let magic = data.get(..4)?;
+    let read = |offset: usize| -> Option<usize> {
+        let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(match magic {
+            [0xde, 0x12, 0x04, 0x95] => u32::from_le_bytes(bytes),
+            [0x95, 0x04, 0x12, 0xde] => u32::from_be_bytes(bytes),
+            _ => return None,
+        } as usize)
+    };
+    let string = |table: usize, index: usize| -> Option<&str> {
+        let length = read(table + index * 8)?;
+        let offset = read(table + index * 8 + 4)?;
+        std::str::from_utf8(data.get(offset..offset + length)?).ok()
+    };
+    let count = read(8)?;
+    let (originals, translations) = (read(12)?, read(16)?);
+    // Each message takes 8 bytes in each table, so the header cannot claim more than fit.
+    let mut catalog = HashMap::with_capacity(count.min(data.len() / 8));
+    for index in 0..count {
+        let original = string(originals, index)?;
+        let translation = string(translations, index)?;
+        // Skip the header and plural or context entries.
+        if original.is_empty() || original.contains(['\0', '\u{4}']) {
+            continue;
+        }
+        let translation = translation.split('\0').next().unwrap_or_default();
+        if !translation.is_empty() {
+            catalog.insert(original.to_owned(), translation.to_owned());
+        }
+    }
+    Some(catalog)
+}
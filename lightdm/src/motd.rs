@@ -0,0 +1,347 @@
+//! The message of the day as styled text rather than the raw terminal output of
+//! `functions::motd()`.
+//!
+//! [`Motd::parse`] turns ANSI SGR sequences into [`Style`]s and drops every other escape or
+//! control character, so the text can be shown as plain text or as Pango markup.
+//! [`MotdLoader`] reads `/etc/motd` like liblightdm, optionally with the `update-motd` output
+//! in `/run/motd.dynamic` and the `motd.d` fragments `pam_motd` shows.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::config::Error;
+
+/// A terminal color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// One of the 256 colors of the xterm palette; 0 to 15 are the basic and bright colors.
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// The red, green and blue components, using the xterm palette for indexed colors.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        const BASIC: [(u8, u8, u8); 16] = [
+            (0x00, 0x00, 0x00),
+            (0xcd, 0x00, 0x00),
+            (0x00, 0xcd, 0x00),
+            (0xcd, 0xcd, 0x00),
+            (0x00, 0x00, 0xee),
+            (0xcd, 0x00, 0xcd),
+            (0x00, 0xcd, 0xcd),
+            (0xe5, 0xe5, 0xe5),
+            (0x7f, 0x7f, 0x7f),
+            (0xff, 0x00, 0x00),
+            (0x00, 0xff, 0x00),
+            (0xff, 0xff, 0x00),
+            (0x5c, 0x5c, 0xff),
+            (0xff, 0x00, 0xff),
+            (0x00, 0xff, 0xff),
+            (0xff, 0xff, 0xff),
+        ];
+        match self {
+            Self::Rgb(r, g, b) => (r, g, b),
+            Self::Indexed(index @ 0..16) => BASIC[usize::from(index)],
+            Self::Indexed(index @ 16..232) => {
+                let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+                let index = index - 16;
+                (level(index / 36), level(index / 6 % 6), level(index % 6))
+            }
+            Self::Indexed(index) => {
+                let gray = 8 + (index - 232) * 10;
+                (gray, gray, gray)
+            }
+        }
+    }
+
+    /// `#rrggbb`, as used by Pango and CSS.
+    pub fn to_hex(self) -> String {
+        let (r, g, b) = self.rgb();
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+}
+
+/// The SGR attributes of a [`Span`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Style {
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+}
+
+impl Style {
+    /// Applies the parameters of one `ESC [ … m` sequence.
+    fn apply(&mut self, params: &[u16]) {
+        let mut params = params.iter().copied();
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Self::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => (self.bold, self.dim) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                30..=37 => self.foreground = Some(Color::Indexed(param as u8 - 30)),
+                38 => self.foreground = extended_color(&mut params),
+                39 => self.foreground = None,
+                40..=47 => self.background = Some(Color::Indexed(param as u8 - 40)),
+                48 => self.background = extended_color(&mut params),
+                49 => self.background = None,
+                90..=97 => self.foreground = Some(Color::Indexed(param as u8 - 90 + 8)),
+                100..=107 => self.background = Some(Color::Indexed(param as u8 - 100 + 8)),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The color of a `38;5;n` or `38;2;r;g;b` parameter list.
+fn extended_color(params: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    let mut component = || params.next().map(|value| value.min(255) as u8);
+    match component()? {
+        5 => Some(Color::Indexed(component()?)),
+        2 => Some(Color::Rgb(component()?, component()?, component()?)),
+        _ => None,
+    }
+}
+
+/// A run of text in one style.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+/// A message of the day split into styled spans.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Motd {
+    spans: Vec<Span>,
+}
+
+impl Motd {
+    /// Parses terminal text. Newlines and tabs are kept; escape sequences other than SGR and
+    /// other control characters, including carriage returns, are dropped.
+    pub fn parse(text: &str) -> Self {
+        let mut motd = Self::default();
+        let mut style = Style::default();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\u{1b}' => match chars.next() {
+                    // CSI: parameters and intermediates, then a final byte.
+                    Some('[') => {
+                        let mut sequence = String::new();
+                        let mut end = None;
+                        for c in chars.by_ref() {
+                            if ('\u{40}'..='\u{7e}').contains(&c) {
+                                end = Some(c);
+                                break;
+                            }
+                            sequence.push(c);
+                        }
+                        if end == Some('m') {
+                            let params = sequence
+                                .split([';', ':'])
+                                .map(|param| param.parse().unwrap_or(0))
+                                .collect::<Vec<_>>();
+                            style.apply(&params);
+                        }
+                    }
+                    // OSC, DCS and friends: a string ended by BEL or `ESC \`.
+                    Some(']' | 'P' | 'X' | '^' | '_') => {
+                        while let Some(c) = chars.next() {
+                            if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some())
+                            {
+                                break;
+                            }
+                        }
+                    }
+                    // Character set selection takes one more character.
+                    Some('(' | ')' | '*' | '+') => {
+                        chars.next();
+                    }
+                    _ => {}
+                },
+                '\n' | '\t' => motd.push(c, style),
+                c if c.is_control() => {}
+                c => motd.push(c, style),
+            }
+        }
+        motd
+    }
+
+    fn push(&mut self, c: char, style: Style) {
+        match self.spans.last_mut() {
+            Some(span) if span.style == style => span.text.push(c),
+            _ => self.spans.push(Span {
+                text: c.to_string(),
+                style,
+            }),
+        }
+    }
+
+    /// The message liblightdm read from `/etc/motd`.
+    #[cfg(feature = "liblightdm")]
+    pub fn from_lightdm() -> Self {
+        crate::functions::motd()
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    /// Whether there is nothing but whitespace to show.
+    pub fn is_empty(&self) -> bool {
+        self.spans.iter().all(|span| span.text.trim().is_empty())
+    }
+
+    /// The text without any styling.
+    pub fn to_plain(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// The text as Pango markup, with a `<span>` for each styled run.
+    pub fn to_markup(&self) -> String {
+        let mut markup = String::new();
+        for span in &self.spans {
+            let text = glib::markup_escape_text(&span.text);
+            let style = span.style;
+            let mut attributes = Vec::new();
+            if style.bold {
+                attributes.push("weight=\"bold\"".to_owned());
+            }
+            if style.dim {
+                attributes.push("fgalpha=\"50%\"".to_owned());
+            }
+            if style.italic {
+                attributes.push("style=\"italic\"".to_owned());
+            }
+            if style.underline {
+                attributes.push("underline=\"single\"".to_owned());
+            }
+            if let Some(color) = style.foreground {
+                attributes.push(format!("foreground=\"{}\"", color.to_hex()));
+            }
+            if let Some(color) = style.background {
+                attributes.push(format!("background=\"{}\"", color.to_hex()));
+            }
+            if attributes.is_empty() {
+                markup.push_str(&text);
+            } else {
+                markup.push_str(&format!("<span {}>{text}</span>", attributes.join(" ")));
+            }
+        }
+        markup
+    }
+}
+
+impl fmt::Display for Motd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_plain())
+    }
+}
+
+/// The `motd.d` directories, most important first: a fragment hides those of the same name in
+/// the directories after it.
+const FRAGMENT_DIRS: [&str; 3] = ["/etc/motd.d", "/run/motd.d", "/usr/lib/motd.d"];
+
+/// Reads the message of the day from its files.
+///
+/// By default only `/etc/motd` is read, as liblightdm does. With [`MotdLoader::dynamic`] the
+/// message is put together like `pam_motd` does on Debian and Ubuntu: `/run/motd.dynamic`,
+/// `/etc/motd`, then the `motd.d` fragments sorted by name. Missing files are skipped, and a
+/// file reached twice through symlinks is only read once.
+#[derive(Debug, Clone)]
+pub struct MotdLoader {
+    root: PathBuf,
+    dynamic: bool,
+}
+
+impl Default for MotdLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MotdLoader {
+    pub fn new() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+            dynamic: false,
+        }
+    }
+
+    /// Reads the files below `root` instead of `/`, for tests.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Also reads `/run/motd.dynamic` and the `motd.d` fragments.
+    pub fn dynamic(mut self, dynamic: bool) -> Self {
+        self.dynamic = dynamic;
+        self
+    }
+
+    /// The files which exist, in the order they are shown.
+    pub fn files(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut files = Vec::new();
+        if self.dynamic {
+            files.push(self.path("/run/motd.dynamic"));
+        }
+        files.push(self.path("/etc/motd"));
+        if self.dynamic {
+            let mut fragments = BTreeMap::new();
+            for dir in FRAGMENT_DIRS.iter().rev() {
+                let dir = self.path(dir);
+                let entries = match fs::read_dir(&dir) {
+                    Ok(entries) => entries,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                    Err(source) => return Err(Error::Io { path: dir, source }),
+                };
+                for entry in entries.filter_map(Result::ok) {
+                    fragments.insert(entry.file_name(), entry.path());
+                }
+            }
+            files.extend(fragments.into_values());
+        }
+
+        let mut seen = Vec::new();
+        files.retain(|path| match fs::canonicalize(path) {
+            Ok(path) if path.is_file() && !seen.contains(&path) => {
+                seen.push(path);
+                true
+            }
+            _ => false,
+        });
+        Ok(files)
+    }
+
+    pub fn load(&self) -> Result<Motd, Error> {
+        let mut text = String::new();
+        for path in self.files()? {
+            let part = fs::read_to_string(&path).map_err(|source| Error::Io { path, source })?;
+            text.push_str(&part);
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+        Ok(Motd::parse(&text))
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        let path = Path::new(path);
+        self.root.join(path.strip_prefix("/").unwrap_or(path))
+    }
+}
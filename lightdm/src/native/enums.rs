@@ -0,0 +1,38 @@
+// Stand-ins for the enums generated from liblightdm-gobject, used when it is not linked.
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, glib::ErrorDomain)]
+#[error_domain(name = "lightdm_greeter_error")]
+#[non_exhaustive]
+#[doc(alias = "LightDMGreeterError")]
+pub enum GreeterError {
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_COMMUNICATION_ERROR")]
+    CommunicationError,
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_CONNECTION_FAILED")]
+    ConnectionFailed,
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_SESSION_FAILED")]
+    SessionFailed,
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_NO_AUTOLOGIN")]
+    NoAutologin,
+    #[doc(alias = "LIGHTDM_GREETER_ERROR_INVALID_USER")]
+    InvalidUser,
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[non_exhaustive]
+#[doc(alias = "LightDMMessageType")]
+pub enum MessageType {
+    #[doc(alias = "LIGHTDM_MESSAGE_TYPE_INFO")]
+    Info,
+    #[doc(alias = "LIGHTDM_MESSAGE_TYPE_ERROR")]
+    Error,
+}
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
+#[non_exhaustive]
+#[doc(alias = "LightDMPromptType")]
+pub enum PromptType {
+    #[doc(alias = "LIGHTDM_PROMPT_TYPE_QUESTION")]
+    Question,
+    #[doc(alias = "LIGHTDM_PROMPT_TYPE_SECRET")]
+    Secret,
+}
@@ -0,0 +1,1167 @@
+//! Greeter speaking the LightDM greeter protocol directly, without liblightdm-gobject.
+//!
+//! [`Greeter`] talks to the daemon over the pipes passed in `LIGHTDM_TO_SERVER_FD` and
+//! `LIGHTDM_FROM_SERVER_FD`. Incoming messages are watched on the default main context, so
+//! the `connect_*` handlers run from the main loop just like the GObject signals do.
+//!
+//! When the `liblightdm` feature is disabled this type is re-exported as [`crate::Greeter`], and
+//! the [`GreeterExt`] and [`GreeterExtManual`] traits take the place of the generated ones in the
+//! prelude.
+
+#[cfg(not(feature = "liblightdm"))]
+mod enums;
+#[cfg(feature = "liblightdm")]
+use crate::{MessageType, PromptType};
+#[cfg(not(feature = "liblightdm"))]
+pub use enums::{GreeterError, MessageType, PromptType};
+
+use futures_channel::oneshot;
+use zeroize::Zeroizing;
+
+use std::{
+    boxed::Box as Box_,
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{self, Read, Write},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    pin::Pin,
+    rc::{Rc, Weak},
+    time::Duration,
+};
+
+use glib::object::IsA;
+
+use crate::{
+    Error, GreeterEvents, Secret,
+    locale::LocaleCode,
+    protocol::{self, GreeterMessage, MessageBuilder, Payload, ServerMessage},
+};
+
+/// Identifies a handler added with one of the `connect_*` methods.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct SignalHandlerId(u64);
+
+type Handler = Rc<dyn Fn(&Greeter)>;
+type PromptHandler = Rc<dyn Fn(&Greeter, &str, PromptType)>;
+type MessageHandler = Rc<dyn Fn(&Greeter, &str, MessageType)>;
+type NotifyHandler = (&'static str, Handler);
+
+/// The hint properties of the liblightdm greeter, with the hints they report.
+const HINT_PROPERTIES: [(&str, &str); 12] = [
+    ("autologin-guest-hint", "autologin-guest"),
+    ("autologin-session-hint", "autologin-session"),
+    ("autologin-timeout-hint", "autologin-timeout"),
+    ("autologin-user-hint", "autologin-user"),
+    ("default-session-hint", "default-session"),
+    ("has-guest-account-hint", "has-guest-account"),
+    ("hide-users-hint", "hide-users"),
+    ("lock-hint", "lock-screen"),
+    ("select-guest-hint", "select-guest"),
+    ("select-user-hint", "select-user"),
+    ("show-manual-login-hint", "show-manual-login"),
+    ("show-remote-login-hint", "show-remote-login"),
+];
+
+#[derive(Default)]
+struct Handlers {
+    next_id: u64,
+    show_prompt: Vec<(u64, PromptHandler)>,
+    show_message: Vec<(u64, MessageHandler)>,
+    authentication_complete: Vec<(u64, Handler)>,
+    autologin_timer_expired: Vec<(u64, Handler)>,
+    idle: Vec<(u64, Handler)>,
+    reset: Vec<(u64, Handler)>,
+    notify: Vec<(u64, NotifyHandler)>,
+}
+
+impl Handlers {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+#[derive(Default)]
+struct State {
+    resettable: bool,
+    hints: HashMap<String, String>,
+    authenticate_sequence_number: u32,
+    in_authentication: bool,
+    is_authenticated: bool,
+    cancelling_authentication: bool,
+    authentication_user: Option<String>,
+    n_responses_waiting: usize,
+    responses_received: Vec<Zeroizing<String>>,
+    /// The types of the prompts of the current request, answered or not.
+    prompt_types: Vec<PromptType>,
+}
+
+struct Connection {
+    to_server: File,
+    from_server: File,
+    /// Bytes read from the daemon which do not form a complete message yet.
+    input: Vec<u8>,
+    watch: u32,
+}
+
+enum Reply {
+    Connected,
+    SessionResult(u32),
+    SharedDirResult(String),
+}
+
+enum Pending {
+    Connected(oneshot::Sender<Result<(), Error>>),
+    SessionResult(oneshot::Sender<Result<(), Error>>),
+    SharedDirResult(oneshot::Sender<Result<glib::GString, Error>>),
+}
+
+#[derive(Default)]
+struct Inner {
+    connection: RefCell<Option<Connection>>,
+    state: RefCell<State>,
+    handlers: RefCell<Handlers>,
+    pending: RefCell<VecDeque<Pending>>,
+    autologin_timeout: RefCell<Option<glib::SourceId>>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.get_mut() {
+            unsafe {
+                glib::ffi::g_source_remove(connection.watch);
+            }
+        }
+        if let Some(source) = self.autologin_timeout.get_mut().take() {
+            source.remove();
+        }
+    }
+}
+
+/// A greeter connected to the daemon without going through liblightdm-gobject.
+///
+/// Cloning a `Greeter` yields another handle to the same connection.
+#[derive(Clone, Default)]
+pub struct Greeter {
+    inner: Rc<Inner>,
+}
+
+impl Greeter {
+    pub fn new() -> Greeter {
+        Self::default()
+    }
+
+    fn send(&self, message: impl AsRef<[u8]>) -> Result<(), Error> {
+        let mut connection = self.inner.connection.borrow_mut();
+        let Some(connection) = connection.as_mut() else {
+            return Err(Error::CommunicationError(
+                "Not connected to daemon".to_owned(),
+            ));
+        };
+        connection
+            .to_server
+            .write_all(message.as_ref())
+            .and_then(|()| connection.to_server.flush())
+            .map_err(|err| Error::CommunicationError(format!("Failed to write to daemon: {err}")))
+    }
+
+    /// Appends what the daemon sent to the input buffer, blocking until something arrives.
+    fn fill_input(&self) -> Result<(), Error> {
+        let mut connection = self.inner.connection.borrow_mut();
+        let Some(connection) = connection.as_mut() else {
+            return Err(Error::CommunicationError(
+                "Not connected to daemon".to_owned(),
+            ));
+        };
+        let mut chunk = [0; 4096];
+        loop {
+            match connection.from_server.read(&mut chunk) {
+                Ok(0) => return Err(read_failed(io::ErrorKind::UnexpectedEof.into())),
+                Ok(length) => {
+                    connection.input.extend_from_slice(&chunk[..length]);
+                    return Ok(());
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(read_failed(err)),
+            }
+        }
+    }
+
+    /// Takes the next complete message out of the input buffer.
+    fn buffered_message(&self) -> Result<Option<(u32, Vec<u8>)>, Error> {
+        let mut connection = self.inner.connection.borrow_mut();
+        let Some(connection) = connection.as_mut() else {
+            return Ok(None);
+        };
+        protocol::take_message(&mut connection.input).map_err(read_failed)
+    }
+
+    /// Returns the next message, blocking until it has been received in full.
+    fn read_message(&self) -> Result<(u32, Vec<u8>), Error> {
+        loop {
+            if let Some(message) = self.buffered_message()? {
+                return Ok(message);
+            }
+            self.fill_input()?;
+        }
+    }
+
+    /// Handles every complete message in the input buffer.
+    fn dispatch_buffered(&self) -> Result<(), Error> {
+        while let Some((id, payload)) = self.buffered_message()? {
+            if let Some(reply) = self.handle_message(id, &payload)? {
+                self.resolve_pending(reply);
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the messages a synchronous call read along with its reply from an idle source,
+    /// as the watch is not woken up for data which was already read.
+    fn dispatch_later(&self) {
+        let buffered = matches!(
+            self.inner.connection.borrow().as_ref(),
+            Some(connection) if !connection.input.is_empty()
+        );
+        if !buffered {
+            return;
+        }
+        let weak = Rc::downgrade(&self.inner);
+        glib::idle_add_local_once(move || {
+            let Some(inner) = weak.upgrade() else { return };
+            let greeter = Greeter { inner };
+            if let Err(err) = greeter.dispatch_buffered() {
+                greeter.close(&err);
+            }
+        });
+    }
+
+    /// Fails the pending calls with `err` and drops the connection.
+    fn close(&self, err: &Error) {
+        self.fail_pending(err);
+        if let Some(connection) = self.inner.connection.take() {
+            unsafe {
+                glib::ffi::g_source_remove(connection.watch);
+            }
+        }
+    }
+
+    fn has_pending_input(&self) -> bool {
+        let connection = self.inner.connection.borrow();
+        let Some(connection) = connection.as_ref() else {
+            return false;
+        };
+        let mut fds = libc::pollfd {
+            fd: connection.from_server.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut fds, 1, 0) > 0 }
+    }
+
+    /// Reads messages until one matching `wanted` arrives, dispatching everything else.
+    fn wait_for(&self, wanted: fn(&Reply) -> bool) -> Result<Reply, Error> {
+        loop {
+            let (id, payload) = self.read_message()?;
+            if let Some(reply) = self.handle_message(id, &payload)? {
+                if wanted(&reply) {
+                    self.dispatch_later();
+                    return Ok(reply);
+                }
+                self.resolve_pending(reply);
+            }
+        }
+    }
+
+    fn resolve_pending(&self, reply: Reply) {
+        let mut pending = self.inner.pending.borrow_mut();
+        let position = pending.iter().position(|p| {
+            matches!(
+                (p, &reply),
+                (Pending::Connected(_), Reply::Connected)
+                    | (Pending::SessionResult(_), Reply::SessionResult(_))
+                    | (Pending::SharedDirResult(_), Reply::SharedDirResult(_))
+            )
+        });
+        let Some(p) = position.and_then(|position| pending.remove(position)) else {
+            return;
+        };
+        drop(pending);
+        match (p, reply) {
+            (Pending::Connected(sender), Reply::Connected) => {
+                let _ = sender.send(Ok(()));
+            }
+            (Pending::SessionResult(sender), Reply::SessionResult(code)) => {
+                let _ = sender.send(session_result(code));
+            }
+            (Pending::SharedDirResult(sender), Reply::SharedDirResult(dir)) => {
+                let _ = sender.send(shared_dir_result(dir));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn fail_pending(&self, err: &Error) {
+        let pending = self.inner.pending.take();
+        for p in pending {
+            match p {
+                Pending::Connected(sender) | Pending::SessionResult(sender) => {
+                    let _ = sender.send(Err(err.clone()));
+                }
+                Pending::SharedDirResult(sender) => {
+                    let _ = sender.send(Err(err.clone()));
+                }
+            }
+        }
+    }
+
+    fn handle_message(&self, id: u32, data: &[u8]) -> Result<Option<Reply>, Error> {
+        let mut payload = Payload::new(data);
+        let reply = match ServerMessage::from_id(id) {
+            Some(ServerMessage::Connected) => {
+                payload.string().map_err(malformed)?;
+                let hints = read_hints(&mut payload).map_err(malformed)?;
+                self.set_hints(hints);
+                self.start_autologin_timer();
+                Some(Reply::Connected)
+            }
+            Some(ServerMessage::ConnectedV2) => {
+                payload.string().map_err(malformed)?;
+                payload.int().map_err(malformed)?;
+                let n_hints = payload.int().map_err(malformed)?;
+                let mut hints = HashMap::new();
+                for _ in 0..n_hints {
+                    let name = payload.string().map_err(malformed)?;
+                    let value = payload.string().map_err(malformed)?;
+                    hints.insert(name, value);
+                }
+                self.set_hints(hints);
+                self.start_autologin_timer();
+                Some(Reply::Connected)
+            }
+            Some(ServerMessage::PromptAuthentication) => {
+                self.handle_prompt_authentication(&mut payload)
+                    .map_err(malformed)?;
+                None
+            }
+            Some(ServerMessage::EndAuthentication) => {
+                let sequence_number = payload.int().map_err(malformed)?;
+                let username = payload.string().map_err(malformed)?;
+                let return_code = payload.int().map_err(malformed)?;
+                {
+                    let mut state = self.inner.state.borrow_mut();
+                    if sequence_number != state.authenticate_sequence_number {
+                        return Ok(None);
+                    }
+                    state.authentication_user = Some(username);
+                    state.is_authenticated = return_code == protocol::PAM_SUCCESS;
+                    state.in_authentication = false;
+                    state.cancelling_authentication = false;
+                }
+                self.emit(|h| &h.authentication_complete, |f| f(self));
+                None
+            }
+            Some(ServerMessage::SessionResult) => {
+                Some(Reply::SessionResult(payload.int().map_err(malformed)?))
+            }
+            Some(ServerMessage::SharedDirResult) => {
+                Some(Reply::SharedDirResult(payload.string().map_err(malformed)?))
+            }
+            Some(ServerMessage::Idle) => {
+                self.emit(|h| &h.idle, |f| f(self));
+                None
+            }
+            Some(ServerMessage::Reset) => {
+                let hints = read_hints(&mut payload).map_err(malformed)?;
+                self.set_hints(hints);
+                self.emit(|h| &h.reset, |f| f(self));
+                None
+            }
+            None => None,
+        };
+        Ok(reply)
+    }
+
+    fn handle_prompt_authentication(&self, payload: &mut Payload) -> std::io::Result<()> {
+        let sequence_number = payload.int()?;
+        let username = payload.string()?;
+        let n_messages = payload.int()?;
+        let mut messages = Vec::new();
+        for _ in 0..n_messages {
+            let style = payload.int()?;
+            let text = payload.string()?;
+            messages.push((style, text));
+        }
+
+        {
+            let mut state = self.inner.state.borrow_mut();
+            if sequence_number != state.authenticate_sequence_number
+                || state.cancelling_authentication
+            {
+                return Ok(());
+            }
+            state.authentication_user = Some(username);
+            state.responses_received.clear();
+            state.n_responses_waiting = 0;
+            state.prompt_types.clear();
+        }
+
+        for (style, text) in messages {
+            match style {
+                protocol::PAM_PROMPT_ECHO_OFF | protocol::PAM_PROMPT_ECHO_ON => {
+                    let prompt_type = if style == protocol::PAM_PROMPT_ECHO_OFF {
+                        PromptType::Secret
+                    } else {
+                        PromptType::Question
+                    };
+                    let mut state = self.inner.state.borrow_mut();
+                    state.n_responses_waiting += 1;
+                    state.prompt_types.push(prompt_type);
+                    drop(state);
+                    self.emit(|h| &h.show_prompt, |f| f(self, &text, prompt_type));
+                }
+                protocol::PAM_ERROR_MSG => {
+                    self.emit(|h| &h.show_message, |f| f(self, &text, MessageType::Error));
+                }
+                protocol::PAM_TEXT_INFO => {
+                    self.emit(|h| &h.show_message, |f| f(self, &text, MessageType::Info));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the hints, then notifies the handlers of the hint properties which changed.
+    fn set_hints(&self, hints: HashMap<String, String>) {
+        let old = std::mem::replace(&mut self.inner.state.borrow_mut().hints, hints);
+        let changed = {
+            let state = self.inner.state.borrow();
+            HINT_PROPERTIES
+                .iter()
+                .filter(|(_, hint)| old.get(*hint) != state.hints.get(*hint))
+                .map(|(property, _)| *property)
+                .collect::<Vec<_>>()
+        };
+        for changed in changed {
+            self.emit(
+                |h| &h.notify,
+                |(property, f)| {
+                    if *property == changed {
+                        f(self)
+                    }
+                },
+            );
+        }
+    }
+
+    fn emit<H: Clone>(&self, list: impl Fn(&Handlers) -> &Vec<(u64, H)>, call: impl Fn(&H)) {
+        let handlers: Vec<H> = list(&self.inner.handlers.borrow())
+            .iter()
+            .map(|(_, h)| h.clone())
+            .collect();
+        for handler in &handlers {
+            call(handler);
+        }
+    }
+
+    fn start_autologin_timer(&self) {
+        let timeout = self.autologin_timeout_hint();
+        if timeout <= 0 {
+            return;
+        }
+        let weak = Rc::downgrade(&self.inner);
+        let source = glib::timeout_add_local_once(Duration::from_secs(timeout as u64), move || {
+            let Some(inner) = weak.upgrade() else { return };
+            inner.autologin_timeout.take();
+            let greeter = Greeter { inner };
+            greeter.emit(|h| &h.autologin_timer_expired, |f| f(&greeter));
+        });
+        if let Some(old) = self.inner.autologin_timeout.replace(Some(source)) {
+            old.remove();
+        }
+    }
+
+    fn open_connection(&self) -> Result<(), Error> {
+        if self.inner.connection.borrow().is_some() {
+            return Ok(());
+        }
+        // The descriptors stay in the environment for anyone else to use, so the connection
+        // works on duplicates which it can close.
+        let fd = |name: &str| {
+            let fd = std::env::var(name)
+                .ok()
+                .and_then(|value| value.parse::<i32>().ok())
+                .filter(|fd| *fd >= 0)
+                .ok_or_else(|| {
+                    Error::ConnectionFailed("Unable to determine socket to daemon".to_owned())
+                })?;
+            let copy = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) };
+            if copy < 0 {
+                return Err(Error::ConnectionFailed(format!(
+                    "Unable to use socket to daemon: {}",
+                    io::Error::last_os_error()
+                )));
+            }
+            Ok(File::from(unsafe { OwnedFd::from_raw_fd(copy) }))
+        };
+        let to_server = fd("LIGHTDM_TO_SERVER_FD")?;
+        let from_server = fd("LIGHTDM_FROM_SERVER_FD")?;
+
+        let watch = unsafe {
+            let channel = glib::ffi::g_io_channel_unix_new(from_server.as_raw_fd());
+            let user_data: Box_<Weak<Inner>> = Box_::new(Rc::downgrade(&self.inner));
+            let watch = glib::ffi::g_io_add_watch_full(
+                channel,
+                glib::ffi::G_PRIORITY_DEFAULT,
+                glib::ffi::G_IO_IN | glib::ffi::G_IO_HUP,
+                Some(from_server_trampoline),
+                Box_::into_raw(user_data) as *mut _,
+                Some(from_server_destroy),
+            );
+            glib::ffi::g_io_channel_unref(channel);
+            watch
+        };
+
+        self.inner.connection.replace(Some(Connection {
+            to_server,
+            from_server,
+            input: Vec::new(),
+            watch,
+        }));
+        Ok(())
+    }
+
+    fn connect_message(&self) -> Vec<u8> {
+        MessageBuilder::new(GreeterMessage::Connect as u32)
+            .string(Some(env!("CARGO_PKG_VERSION")))
+            .int(self.inner.state.borrow().resettable as u32)
+            .int(protocol::API_VERSION)
+            .finish()
+    }
+
+    fn hint_bool(&self, name: &str) -> bool {
+        self.inner
+            .state
+            .borrow()
+            .hints
+            .get(name)
+            .map(String::as_str)
+            == Some("true")
+    }
+
+    fn add_handler<H>(
+        &self,
+        list: impl FnOnce(&mut Handlers) -> &mut Vec<(u64, H)>,
+        handler: H,
+    ) -> SignalHandlerId {
+        let mut handlers = self.inner.handlers.borrow_mut();
+        let id = handlers.next_id();
+        list(&mut handlers).push((id, handler));
+        SignalHandlerId(id)
+    }
+
+    fn connect_notify<F: Fn(&Greeter) + 'static>(
+        &self,
+        property: &'static str,
+        f: F,
+    ) -> SignalHandlerId {
+        self.add_handler(|h| &mut h.notify, (property, Rc::new(f) as Handler))
+    }
+}
+
+unsafe extern "C" fn from_server_trampoline(
+    _channel: *mut glib::ffi::GIOChannel,
+    _condition: glib::ffi::GIOCondition,
+    user_data: glib::ffi::gpointer,
+) -> glib::ffi::gboolean {
+    let weak: &Weak<Inner> = unsafe { &*(user_data as *const Weak<Inner>) };
+    let Some(inner) = weak.upgrade() else {
+        return glib::ffi::GFALSE;
+    };
+    let greeter = Greeter { inner };
+    // A synchronous call may already have consumed the data which woke us up, in which case
+    // reading would block.
+    let result = if greeter.has_pending_input() {
+        greeter.fill_input()
+    } else {
+        Ok(())
+    };
+    match result.and_then(|()| greeter.dispatch_buffered()) {
+        Ok(()) => glib::ffi::GTRUE,
+        Err(err) => {
+            greeter.close(&err);
+            glib::ffi::GFALSE
+        }
+    }
+}
+
+unsafe extern "C" fn from_server_destroy(user_data: glib::ffi::gpointer) {
+    let _: Box_<Weak<Inner>> = unsafe { Box_::from_raw(user_data as *mut _) };
+}
+
+fn read_failed(err: io::Error) -> Error {
+    Error::CommunicationError(format!("Failed to read from daemon: {err}"))
+}
+
+fn malformed(err: std::io::Error) -> Error {
+    Error::CommunicationError(format!("Malformed message from daemon: {err}"))
+}
+
+fn read_hints(payload: &mut Payload) -> std::io::Result<HashMap<String, String>> {
+    let mut hints = HashMap::new();
+    while !payload.is_empty() {
+        let name = payload.string()?;
+        let value = payload.string()?;
+        hints.insert(name, value);
+    }
+    Ok(hints)
+}
+
+fn session_result(code: u32) -> Result<(), Error> {
+    if code == 0 {
+        Ok(())
+    } else {
+        Err(Error::SessionFailed(format!(
+            "Session returned error code {code}"
+        )))
+    }
+}
+
+fn shared_dir_result(dir: String) -> Result<glib::GString, Error> {
+    if dir.is_empty() {
+        Err(Error::call_failed("lightdm_greeter_ensure_shared_data_dir"))
+    } else {
+        Ok(dir.into())
+    }
+}
+
+/// Queues `response`, sending all of them once every pending prompt has been answered.
+///
+/// The responses and the encoded message are wiped once sent; the message buffer is sized up
+/// front so growing it never leaves a copy behind.
+fn respond(greeter: &Greeter, response: Zeroizing<String>) -> Result<(), Error> {
+    let responses = {
+        let mut state = greeter.inner.state.borrow_mut();
+        if state.n_responses_waiting == 0 {
+            return Err(Error::call_failed("lightdm_greeter_respond"));
+        }
+        state.n_responses_waiting -= 1;
+        state.responses_received.push(response);
+        if state.n_responses_waiting > 0 {
+            return Ok(());
+        }
+        std::mem::take(&mut state.responses_received)
+    };
+    let length = 4 + responses.iter().map(|r| 4 + r.len()).sum::<usize>();
+    let message = responses.iter().fold(
+        MessageBuilder::with_capacity(GreeterMessage::ContinueAuthentication as u32, length)
+            .int(responses.len() as u32),
+        |message, response| message.string(Some(response)),
+    );
+    greeter.send(Zeroizing::new(message.finish()))
+}
+
+/// Runs `future` on the thread-default main context and hands its result to `callback`, or a
+/// `G_IO_ERROR_CANCELLED` error once `cancellable` is cancelled.
+fn spawn_with_callback<T: 'static>(
+    future: Pin<Box_<dyn std::future::Future<Output = Result<T, Error>> + 'static>>,
+    cancellable: Option<&impl IsA<gio::Cancellable>>,
+    callback: impl FnOnce(Result<T, Error>) + 'static,
+) {
+    let cancellable = cancellable.map(|cancellable| cancellable.as_ref().clone());
+    glib::MainContext::ref_thread_default().spawn_local(async move {
+        let result = match cancellable {
+            Some(cancellable) => gio::CancellableFuture::new(future, cancellable)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(Error::Other(glib::Error::new(
+                        gio::IOErrorEnum::Cancelled,
+                        "Operation was cancelled",
+                    )))
+                }),
+            None => future.await,
+        };
+        callback(result);
+    });
+}
+
+impl AsRef<Greeter> for Greeter {
+    fn as_ref(&self) -> &Greeter {
+        self
+    }
+}
+
+impl std::fmt::Debug for Greeter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Greeter")
+            .field("connected", &self.inner.connection.borrow().is_some())
+            .finish()
+    }
+}
+
+/// Hints, authentication properties and signals, mirroring the generated `GreeterExt`.
+pub trait GreeterExt: AsRef<Greeter> + 'static {
+    fn cancel_autologin(&self) {
+        if let Some(source) = self.as_ref().inner.autologin_timeout.take() {
+            source.remove();
+        }
+    }
+
+    fn authentication_user(&self) -> Option<glib::GString> {
+        let state = self.as_ref().inner.state.borrow();
+        state.authentication_user.as_deref().map(Into::into)
+    }
+
+    fn is_autologin_guest_hint(&self) -> bool {
+        self.as_ref().hint_bool("autologin-guest")
+    }
+
+    fn autologin_session_hint(&self) -> Option<glib::GString> {
+        self.hint("autologin-session")
+    }
+
+    fn autologin_timeout_hint(&self) -> i32 {
+        self.hint("autologin-timeout")
+            .and_then(|timeout| timeout.parse().ok())
+            .unwrap_or(0)
+    }
+
+    fn autologin_user_hint(&self) -> Option<glib::GString> {
+        self.hint("autologin-user")
+    }
+
+    fn default_session_hint(&self) -> Option<glib::GString> {
+        self.hint("default-session")
+    }
+
+    fn has_guest_account_hint(&self) -> bool {
+        self.as_ref().hint_bool("has-guest-account")
+    }
+
+    fn hides_users_hint(&self) -> bool {
+        self.as_ref().hint_bool("hide-users")
+    }
+
+    fn hint(&self, name: &str) -> Option<glib::GString> {
+        let state = self.as_ref().inner.state.borrow();
+        state.hints.get(name).map(|value| value.as_str().into())
+    }
+
+    fn is_in_authentication(&self) -> bool {
+        self.as_ref().inner.state.borrow().in_authentication
+    }
+
+    fn is_authenticated(&self) -> bool {
+        self.as_ref().inner.state.borrow().is_authenticated
+    }
+
+    fn is_lock_hint(&self) -> bool {
+        self.as_ref().hint_bool("lock-screen")
+    }
+
+    fn selects_guest_hint(&self) -> bool {
+        self.as_ref().hint_bool("select-guest")
+    }
+
+    fn select_user_hint(&self) -> Option<glib::GString> {
+        self.hint("select-user")
+    }
+
+    fn shows_manual_login_hint(&self) -> bool {
+        self.as_ref().hint_bool("show-manual-login")
+    }
+
+    fn shows_remote_login_hint(&self) -> bool {
+        self.as_ref().hint_bool("show-remote-login")
+    }
+
+    fn set_resettable(&self, resettable: bool) {
+        let greeter = self.as_ref();
+        greeter.inner.state.borrow_mut().resettable = resettable;
+        if greeter.inner.connection.borrow().is_some() {
+            let message = MessageBuilder::new(GreeterMessage::SetResettable as u32)
+                .int(resettable as u32)
+                .finish();
+            let _ = greeter.send(message);
+        }
+    }
+
+    fn connect_authentication_complete<F: Fn(&Greeter) + 'static>(&self, f: F) -> SignalHandlerId {
+        self.as_ref()
+            .add_handler(|h| &mut h.authentication_complete, Rc::new(f) as Handler)
+    }
+
+    fn connect_autologin_timer_expired<F: Fn(&Greeter) + 'static>(&self, f: F) -> SignalHandlerId {
+        self.as_ref()
+            .add_handler(|h| &mut h.autologin_timer_expired, Rc::new(f) as Handler)
+    }
+
+    fn connect_idle<F: Fn(&Greeter) + 'static>(&self, f: F) -> SignalHandlerId {
+        self.as_ref()
+            .add_handler(|h| &mut h.idle, Rc::new(f) as Handler)
+    }
+
+    fn connect_reset<F: Fn(&Greeter) + 'static>(&self, f: F) -> SignalHandlerId {
+        self.as_ref()
+            .add_handler(|h| &mut h.reset, Rc::new(f) as Handler)
+    }
+
+    fn connect_show_message<F: Fn(&Greeter, &str, MessageType) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref()
+            .add_handler(|h| &mut h.show_message, Rc::new(f) as MessageHandler)
+    }
+
+    fn connect_show_prompt<F: Fn(&Greeter, &str, PromptType) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref()
+            .add_handler(|h| &mut h.show_prompt, Rc::new(f) as PromptHandler)
+    }
+
+    fn connect_autologin_guest_hint_notify<F: Fn(&Greeter) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref().connect_notify("autologin-guest-hint", f)
+    }
+
+    fn connect_autologin_session_hint_notify<F: Fn(&Greeter) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref().connect_notify("autologin-session-hint", f)
+    }
+
+    fn connect_autologin_timeout_hint_notify<F: Fn(&Greeter) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref().connect_notify("autologin-timeout-hint", f)
+    }
+
+    fn connect_autologin_user_hint_notify<F: Fn(&Greeter) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref().connect_notify("autologin-user-hint", f)
+    }
+
+    fn connect_default_session_hint_notify<F: Fn(&Greeter) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref().connect_notify("default-session-hint", f)
+    }
+
+    fn connect_has_guest_account_hint_notify<F: Fn(&Greeter) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref().connect_notify("has-guest-account-hint", f)
+    }
+
+    fn connect_hide_users_hint_notify<F: Fn(&Greeter) + 'static>(&self, f: F) -> SignalHandlerId {
+        self.as_ref().connect_notify("hide-users-hint", f)
+    }
+
+    fn connect_lock_hint_notify<F: Fn(&Greeter) + 'static>(&self, f: F) -> SignalHandlerId {
+        self.as_ref().connect_notify("lock-hint", f)
+    }
+
+    fn connect_select_guest_hint_notify<F: Fn(&Greeter) + 'static>(&self, f: F) -> SignalHandlerId {
+        self.as_ref().connect_notify("select-guest-hint", f)
+    }
+
+    fn connect_select_user_hint_notify<F: Fn(&Greeter) + 'static>(&self, f: F) -> SignalHandlerId {
+        self.as_ref().connect_notify("select-user-hint", f)
+    }
+
+    fn connect_show_manual_login_hint_notify<F: Fn(&Greeter) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref().connect_notify("show-manual-login-hint", f)
+    }
+
+    fn connect_show_remote_login_hint_notify<F: Fn(&Greeter) + 'static>(
+        &self,
+        f: F,
+    ) -> SignalHandlerId {
+        self.as_ref().connect_notify("show-remote-login-hint", f)
+    }
+
+    /// Removes a handler added with one of the `connect_*` methods.
+    fn disconnect(&self, handler_id: SignalHandlerId) {
+        let mut handlers = self.as_ref().inner.handlers.borrow_mut();
+        let id = handler_id.0;
+        handlers.show_prompt.retain(|(i, _)| *i != id);
+        handlers.show_message.retain(|(i, _)| *i != id);
+        handlers.authentication_complete.retain(|(i, _)| *i != id);
+        handlers.autologin_timer_expired.retain(|(i, _)| *i != id);
+        handlers.idle.retain(|(i, _)| *i != id);
+        handlers.reset.retain(|(i, _)| *i != id);
+        handlers.notify.retain(|(i, _)| *i != id);
+    }
+}
+
+impl GreeterExt for Greeter {}
+
+/// Requests to the daemon, mirroring [`crate::prelude::GreeterExtManual`] for liblightdm.
+pub trait GreeterExtManual: AsRef<Greeter> + 'static {
+    fn authenticate(&self, username: Option<&str>) -> Result<(), Error> {
+        let greeter = self.as_ref();
+        let sequence_number = {
+            let mut state = greeter.inner.state.borrow_mut();
+            state.cancelling_authentication = false;
+            state.authenticate_sequence_number += 1;
+            state.in_authentication = true;
+            state.is_authenticated = false;
+            state.authentication_user = username.map(ToOwned::to_owned);
+            state.authenticate_sequence_number
+        };
+        greeter.send(
+            MessageBuilder::new(GreeterMessage::Authenticate as u32)
+                .int(sequence_number)
+                .string(username)
+                .finish(),
+        )
+    }
+
+    fn authenticate_as_guest(&self) -> Result<(), Error> {
+        let greeter = self.as_ref();
+        let sequence_number = {
+            let mut state = greeter.inner.state.borrow_mut();
+            state.cancelling_authentication = false;
+            state.authenticate_sequence_number += 1;
+            state.in_authentication = true;
+            state.is_authenticated = false;
+            state.authentication_user = None;
+            state.authenticate_sequence_number
+        };
+        greeter.send(
+            MessageBuilder::new(GreeterMessage::AuthenticateAsGuest as u32)
+                .int(sequence_number)
+                .finish(),
+        )
+    }
+
+    fn authenticate_autologin(&self) -> Result<(), Error> {
+        let greeter = self.as_ref();
+        if greeter.is_autologin_guest_hint() {
+            greeter.authenticate_as_guest()
+        } else if let Some(user) = greeter.autologin_user_hint() {
+            greeter.authenticate(Some(&user))
+        } else {
+            Err(Error::NoAutologin(
+                "Can't authenticate autologin; autologin not configured".to_owned(),
+            ))
+        }
+    }
+
+    fn authenticate_remote(&self, session: &str, username: Option<&str>) -> Result<(), Error> {
+        let greeter = self.as_ref();
+        let sequence_number = {
+            let mut state = greeter.inner.state.borrow_mut();
+            state.cancelling_authentication = false;
+            state.authenticate_sequence_number += 1;
+            state.in_authentication = true;
+            state.is_authenticated = false;
+            state.authentication_user = None;
+            state.authenticate_sequence_number
+        };
+        greeter.send(
+            MessageBuilder::new(GreeterMessage::AuthenticateRemote as u32)
+                .int(sequence_number)
+                .string(Some(session))
+                .string(username)
+                .finish(),
+        )
+    }
+
+    fn cancel_authentication(&self) -> Result<(), Error> {
+        let greeter = self.as_ref();
+        greeter.inner.state.borrow_mut().cancelling_authentication = true;
+        greeter.send(MessageBuilder::new(GreeterMessage::CancelAuthentication as u32).finish())
+    }
+
+    fn connect_to_daemon<P: FnOnce(Result<(), Error>) + 'static>(
+        &self,
+        cancellable: Option<&impl IsA<gio::Cancellable>>,
+        callback: P,
+    ) {
+        spawn_with_callback(self.connect_to_daemon_future(), cancellable, callback);
+    }
+
+    fn connect_to_daemon_future(
+        &self,
+    ) -> Pin<Box_<dyn std::future::Future<Output = Result<(), Error>> + 'static>> {
+        let greeter = self.as_ref().clone();
+        Box_::pin(async move {
+            greeter.open_connection()?;
+            let (sender, receiver) = oneshot::channel();
+            greeter
+                .inner
+                .pending
+                .borrow_mut()
+                .push_back(Pending::Connected(sender));
+            greeter.send(greeter.connect_message())?;
+            receiver.await.unwrap_or_else(|_| {
+                Err(Error::ConnectionFailed(
+                    "Connection to daemon closed".to_owned(),
+                ))
+            })
+        })
+    }
+
+    fn connect_to_daemon_sync(&self) -> Result<(), Error> {
+        let greeter = self.as_ref();
+        greeter.open_connection()?;
+        greeter.send(greeter.connect_message())?;
+        greeter.wait_for(|reply| matches!(reply, Reply::Connected))?;
+        Ok(())
+    }
+
+    fn ensure_shared_data_dir<P: FnOnce(Result<glib::GString, Error>) + 'static>(
+        &self,
+        username: &str,
+        cancellable: Option<&impl IsA<gio::Cancellable>>,
+        callback: P,
+    ) {
+        spawn_with_callback(
+            self.ensure_shared_data_dir_future(username),
+            cancellable,
+            callback,
+        );
+    }
+
+    fn ensure_shared_data_dir_future(
+        &self,
+        username: &str,
+    ) -> Pin<Box_<dyn std::future::Future<Output = Result<glib::GString, Error>> + 'static>> {
+        let greeter = self.as_ref().clone();
+        let message = MessageBuilder::new(GreeterMessage::EnsureSharedDir as u32)
+            .string(Some(username))
+            .finish();
+        Box_::pin(async move {
+            let (sender, receiver) = oneshot::channel();
+            greeter
+                .inner
+                .pending
+                .borrow_mut()
+                .push_back(Pending::SharedDirResult(sender));
+            greeter.send(message)?;
+            receiver.await.unwrap_or_else(|_| {
+                Err(Error::CommunicationError(
+                    "Connection to daemon closed".to_owned(),
+                ))
+            })
+        })
+    }
+
+    fn ensure_shared_data_dir_sync(&self, username: &str) -> Result<glib::GString, Error> {
+        let greeter = self.as_ref();
+        greeter.send(
+            MessageBuilder::new(GreeterMessage::EnsureSharedDir as u32)
+                .string(Some(username))
+                .finish(),
+        )?;
+        match greeter.wait_for(|reply| matches!(reply, Reply::SharedDirResult(_)))? {
+            Reply::SharedDirResult(dir) => shared_dir_result(dir),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns a stream of the signals and hint changes emitted by this greeter.
+    fn events(&self) -> GreeterEvents {
+        GreeterEvents::new_native(self.as_ref())
+    }
+
+    /// Answers a prompt, failing with [`Error::SecretPrompt`] if the prompt is a
+    /// [`PromptType::Secret`] one, which [`respond_secret`](Self::respond_secret) answers.
+    fn respond(&self, response: &str) -> Result<(), Error> {
+        let state = self.as_ref().inner.state.borrow();
+        if state.prompt_types.get(state.responses_received.len()) == Some(&PromptType::Secret) {
+            return Err(Error::SecretPrompt);
+        }
+        drop(state);
+        respond(self.as_ref(), Zeroizing::new(response.to_owned()))
+    }
+
+    /// Answers a prompt with a secret, failing if it contains a NUL byte.
+    fn respond_secret(&self, response: &Secret) -> Result<(), Error> {
+        response.check_nul()?;
+        let mut copy = Zeroizing::new(String::with_capacity(response.len()));
+        copy.push_str(response.expose());
+        respond(self.as_ref(), copy)
+    }
+
+    #[deprecated = "use `set_locale`, which only takes well-formed locale codes"]
+    fn set_language(&self, language: &str) -> Result<(), Error> {
+        self.as_ref().send(
+            MessageBuilder::new(GreeterMessage::SetLanguage as u32)
+                .string(Some(language))
+                .finish(),
+        )
+    }
+
+    /// Sets the language of the session to `code`. Without liblightdm there is no list of
+    /// installed languages to check it against, so any well-formed code is sent.
+    #[allow(deprecated)]
+    fn set_locale(&self, code: &LocaleCode) -> Result<(), Error> {
+        self.set_language(&code.to_string())
+    }
+
+    fn start_session<P: FnOnce(Result<(), Error>) + 'static>(
+        &self,
+        session: Option<&str>,
+        cancellable: Option<&impl IsA<gio::Cancellable>>,
+        callback: P,
+    ) {
+        spawn_with_callback(self.start_session_future(session), cancellable, callback);
+    }
+
+    fn start_session_future(
+        &self,
+        session: Option<&str>,
+    ) -> Pin<Box_<dyn std::future::Future<Output = Result<(), Error>> + 'static>> {
+        let greeter = self.as_ref().clone();
+        let message = MessageBuilder::new(GreeterMessage::StartSession as u32)
+            .string(session)
+            .finish();
+        Box_::pin(async move {
+            let (sender, receiver) = oneshot::channel();
+            greeter
+                .inner
+                .pending
+                .borrow_mut()
+                .push_back(Pending::SessionResult(sender));
+            greeter.send(message)?;
+            receiver.await.unwrap_or_else(|_| {
+                Err(Error::CommunicationError(
+                    "Connection to daemon closed".to_owned(),
+                ))
+            })
+        })
+    }
+
+    fn start_session_sync(&self, session: Option<&str>) -> Result<(), Error> {
+        let greeter = self.as_ref();
+        greeter.send(
+            MessageBuilder::new(GreeterMessage::StartSession as u32)
+                .string(session)
+                .finish(),
+        )?;
+        match greeter.wait_for(|reply| matches!(reply, Reply::SessionResult(_)))? {
+            Reply::SessionResult(code) => session_result(code),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl GreeterExtManual for Greeter {}
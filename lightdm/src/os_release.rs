@@ -0,0 +1,143 @@
+//! Parser for `os-release(5)`, exposing every key rather than the five of `functions::os_id()`
+//! and friends.
+//!
+//! For the keys they share, [`OsRelease`] agrees with the liblightdm getters on any file they
+//! read the same way; it additionally undoes shell escapes and single quotes, which liblightdm
+//! leaves in place, and falls back to `/usr/lib/os-release`.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::config::Error;
+
+/// The files read by [`OsRelease::load`], in order.
+pub const OS_RELEASE_PATHS: [&str; 2] = ["/etc/os-release", "/usr/lib/os-release"];
+
+/// The `KEY=value` assignments of an os-release file.
+///
+/// The getters return `None` for missing keys without applying the defaults of
+/// `os-release(5)` (e.g. `Linux` for `NAME`), as liblightdm does.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OsRelease {
+    fields: BTreeMap<String, String>,
+}
+
+macro_rules! keys {
+    ($($(#[$attr:meta])* $name:ident => $key:literal,)*) => {
+        impl OsRelease {
+            $(
+                #[doc = concat!("The `", $key, "` value.")]
+                $(#[$attr])*
+                pub fn $name(&self) -> Option<&str> {
+                    self.get($key)
+                }
+            )*
+        }
+    };
+}
+
+keys! {
+    id => "ID",
+    name => "NAME",
+    pretty_name => "PRETTY_NAME",
+    version => "VERSION",
+    version_id => "VERSION_ID",
+    version_codename => "VERSION_CODENAME",
+    variant => "VARIANT",
+    variant_id => "VARIANT_ID",
+    build_id => "BUILD_ID",
+    image_id => "IMAGE_ID",
+    image_version => "IMAGE_VERSION",
+    /// An icon name from the icon theme, e.g. `fedora-logo-icon`.
+    logo => "LOGO",
+    /// An SGR color like `0;38;2;60;110;180`, for coloring the name in terminals.
+    ansi_color => "ANSI_COLOR",
+    home_url => "HOME_URL",
+    documentation_url => "DOCUMENTATION_URL",
+    support_url => "SUPPORT_URL",
+    bug_report_url => "BUG_REPORT_URL",
+    privacy_policy_url => "PRIVACY_POLICY_URL",
+    cpe_name => "CPE_NAME",
+}
+
+impl OsRelease {
+    /// Reads the first of [`OS_RELEASE_PATHS`] which exists.
+    pub fn load() -> Result<Self, Error> {
+        Self::load_first(OS_RELEASE_PATHS)
+    }
+
+    /// Reads the first of `paths` which exists, failing with the last error if none does.
+    pub fn load_first<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Result<Self, Error> {
+        let mut last = None;
+        for path in paths {
+            match Self::load_from(path.as_ref()) {
+                Err(Error::Io { path, source }) if source.kind() == io::ErrorKind::NotFound => {
+                    last = Some(Error::Io { path, source });
+                }
+                result => return result,
+            }
+        }
+        Err(last.unwrap_or_else(|| Error::Io {
+            path: PathBuf::new(),
+            source: io::ErrorKind::NotFound.into(),
+        }))
+    }
+
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map(|text| Self::parse(&text))
+            .map_err(|source| Error::Io {
+                path: path.to_owned(),
+                source,
+            })
+    }
+
+    /// Parses the text of an os-release file. Comments, blank lines and lines which are not
+    /// assignments are skipped.
+    pub fn parse(text: &str) -> Self {
+        let fields = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                let key = key.trim();
+                let valid =
+                    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                valid.then(|| (key.to_owned(), unquote(value.trim())))
+            })
+            .collect();
+        Self { fields }
+    }
+
+    /// The value of any key, including vendor extensions.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+
+    /// The space-separated `ID_LIKE` list of related distributions, closest first.
+    pub fn id_like(&self) -> Vec<&str> {
+        self.get("ID_LIKE")
+            .map(|ids| ids.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    /// Every key and value, sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+}
+
+/// Undoes shell quoting; values the shell would reject are kept as written.
+fn unquote(value: &str) -> String {
+    glib::shell_unquote(value)
+        .ok()
+        .and_then(|value| value.into_string().ok())
+        .unwrap_or_else(|| value.to_owned())
+}
@@ -0,0 +1,463 @@
+//! Power actions behind a [`PowerManager`] trait, so that power menus can be tested and run in
+//! demos without shutting the machine down.
+//!
+//! [`LibLightDm`] calls `functions::shutdown` and friends, [`Logind`] talks to
+//! `org.freedesktop.login1` directly, and [`DryRun`] only records what it was asked to do.
+//!
+//! Beyond the yes/no of [`PowerManager::can`], [`PowerManager::capability`] tells apart actions
+//! needing authentication and actions blocked by an [`Inhibitor`], and
+//! [`PowerManager::run_with`] refuses blocked actions unless asked to ignore inhibitors.
+
+use gio::{DBusCallFlags, DBusConnection, prelude::*};
+use glib::{Variant, VariantTy};
+
+use std::{
+    error, fmt,
+    sync::{Mutex, PoisonError},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerAction {
+    Shutdown,
+    Restart,
+    Suspend,
+    Hibernate,
+}
+
+impl PowerAction {
+    pub const ALL: [Self; 4] = [
+        Self::Shutdown,
+        Self::Restart,
+        Self::Suspend,
+        Self::Hibernate,
+    ];
+}
+
+impl fmt::Display for PowerAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Shutdown => "shutdown",
+            Self::Restart => "restart",
+            Self::Suspend => "suspend",
+            Self::Hibernate => "hibernate",
+        })
+    }
+}
+
+/// Whether a power action may be run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PowerCapability {
+    Yes,
+    /// Allowed after an administrator authenticates (logind's `challenge`).
+    Challenge,
+    /// Blocked by an inhibitor, see [`PowerManager::inhibitors`].
+    Inhibited,
+    No,
+    /// Not supported by the hardware or the system configuration (logind's `na`).
+    NotAvailable,
+}
+
+impl PowerCapability {
+    /// Whether a power menu should offer the action, possibly behind a password prompt or a
+    /// warning about the inhibitors.
+    pub fn is_offered(self) -> bool {
+        matches!(self, Self::Yes | Self::Challenge | Self::Inhibited)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InhibitMode {
+    /// The action cannot happen while the inhibitor is held.
+    Block,
+    /// Like [`InhibitMode::Block`], but ignored by privileged callers.
+    BlockWeak,
+    /// The action is only delayed until the holder is ready.
+    Delay,
+    Unknown(String),
+}
+
+impl From<&str> for InhibitMode {
+    fn from(mode: &str) -> Self {
+        match mode {
+            "block" => Self::Block,
+            "block-weak" => Self::BlockWeak,
+            "delay" => Self::Delay,
+            mode => Self::Unknown(mode.to_owned()),
+        }
+    }
+}
+
+/// A lock taken with logind's `Inhibit`, as listed by `ListInhibitors`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Inhibitor {
+    /// What is inhibited, e.g. `shutdown`, `sleep` or `idle`.
+    pub what: Vec<String>,
+    /// A human readable name of the holder, e.g. `GNOME Software`.
+    pub who: String,
+    /// Why, e.g. `Installing updates`.
+    pub why: String,
+    pub mode: InhibitMode,
+    pub uid: u32,
+    pub pid: u32,
+}
+
+impl Inhibitor {
+    /// Whether this inhibitor prevents `action`.
+    pub fn blocks(&self, action: PowerAction) -> bool {
+        let what = match action {
+            PowerAction::Shutdown | PowerAction::Restart => "shutdown",
+            PowerAction::Suspend | PowerAction::Hibernate => "sleep",
+        };
+        matches!(self.mode, InhibitMode::Block | InhibitMode::BlockWeak)
+            && self.what.iter().any(|inhibited| inhibited == what)
+    }
+}
+
+impl fmt::Display for Inhibitor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.who, self.why)
+    }
+}
+
+/// Error returned by [`PowerManager`] methods.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum PowerError {
+    /// `action` is blocked by `inhibitors`, see [`PowerManager::run_with`].
+    Inhibited {
+        action: PowerAction,
+        inhibitors: Vec<Inhibitor>,
+    },
+    /// The back-end refused or failed to perform the action.
+    Failed(glib::Error),
+}
+
+impl fmt::Display for PowerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Inhibited { action, inhibitors } => {
+                write!(f, "{action} is inhibited by ")?;
+                for (index, inhibitor) in inhibitors.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{inhibitor}")?;
+                }
+                Ok(())
+            }
+            Self::Failed(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl error::Error for PowerError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Failed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<glib::Error> for PowerError {
+    fn from(err: glib::Error) -> Self {
+        Self::Failed(err)
+    }
+}
+
+/// Something which can shut down, restart, suspend or hibernate the machine.
+pub trait PowerManager {
+    /// Whether `action` is allowed.
+    fn can(&self, action: PowerAction) -> bool;
+
+    /// Performs `action`.
+    fn run(&self, action: PowerAction) -> Result<(), PowerError>;
+
+    /// Whether `action` is allowed, needs authentication or is inhibited. Back-ends which only
+    /// know yes or no report [`PowerCapability::Yes`] or [`PowerCapability::No`].
+    fn capability(&self, action: PowerAction) -> PowerCapability {
+        if self.can(action) {
+            PowerCapability::Yes
+        } else {
+            PowerCapability::No
+        }
+    }
+
+    /// The inhibitors currently held. Back-ends which cannot see them report none.
+    fn inhibitors(&self) -> Result<Vec<Inhibitor>, PowerError> {
+        Ok(Vec::new())
+    }
+
+    /// Performs `action`, failing with [`PowerError::Inhibited`] if an inhibitor blocks it and
+    /// `ignore_inhibitors` is not set.
+    fn run_with(&self, action: PowerAction, ignore_inhibitors: bool) -> Result<(), PowerError> {
+        if !ignore_inhibitors {
+            let inhibitors = self
+                .inhibitors()?
+                .into_iter()
+                .filter(|inhibitor| inhibitor.blocks(action))
+                .collect::<Vec<_>>();
+            if !inhibitors.is_empty() {
+                return Err(PowerError::Inhibited { action, inhibitors });
+            }
+        }
+        self.run(action)
+    }
+
+    fn can_shutdown(&self) -> bool {
+        self.can(PowerAction::Shutdown)
+    }
+
+    fn can_restart(&self) -> bool {
+        self.can(PowerAction::Restart)
+    }
+
+    fn can_suspend(&self) -> bool {
+        self.can(PowerAction::Suspend)
+    }
+
+    fn can_hibernate(&self) -> bool {
+        self.can(PowerAction::Hibernate)
+    }
+
+    fn shutdown(&self) -> Result<(), PowerError> {
+        self.run(PowerAction::Shutdown)
+    }
+
+    fn restart(&self) -> Result<(), PowerError> {
+        self.run(PowerAction::Restart)
+    }
+
+    fn suspend(&self) -> Result<(), PowerError> {
+        self.run(PowerAction::Suspend)
+    }
+
+    fn hibernate(&self) -> Result<(), PowerError> {
+        self.run(PowerAction::Hibernate)
+    }
+}
+
+/// The power functions of liblightdm.
+#[cfg(feature = "liblightdm")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LibLightDm;
+
+#[cfg(feature = "liblightdm")]
+impl PowerManager for LibLightDm {
+    fn can(&self, action: PowerAction) -> bool {
+        use crate::functions::*;
+        match action {
+            PowerAction::Shutdown => can_shutdown(),
+            PowerAction::Restart => can_restart(),
+            PowerAction::Suspend => can_suspend(),
+            PowerAction::Hibernate => can_hibernate(),
+        }
+    }
+
+    fn run(&self, action: PowerAction) -> Result<(), PowerError> {
+        use crate::functions::*;
+        match action {
+            PowerAction::Shutdown => shutdown(),
+            PowerAction::Restart => restart(),
+            PowerAction::Suspend => suspend(),
+            PowerAction::Hibernate => hibernate(),
+        }
+        .map_err(Into::into)
+    }
+}
+
+/// Well-known bus name of systemd-logind.
+pub const LOGIND_BUS_NAME: &str = "org.freedesktop.login1";
+const LOGIND_PATH: &str = "/org/freedesktop/login1";
+const LOGIND_MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+
+/// The `org.freedesktop.login1.Manager` of systemd-logind.
+///
+/// logind checks inhibitors itself too: running a blocked action, even through
+/// [`PowerManager::run_with`] with `ignore_inhibitors`, needs the polkit `*-ignore-inhibit`
+/// authorization.
+#[derive(Debug, Clone)]
+pub struct Logind {
+    connection: DBusConnection,
+    interactive: bool,
+}
+
+impl Logind {
+    /// Connects to logind on the system bus.
+    pub fn new_sync() -> Result<Self, PowerError> {
+        let connection = gio::bus_get_sync(gio::BusType::System, gio::Cancellable::NONE)?;
+        Ok(Self::with_connection(&connection))
+    }
+
+    /// Uses logind on `connection`.
+    pub fn with_connection(connection: &DBusConnection) -> Self {
+        Self {
+            connection: connection.clone(),
+            interactive: false,
+        }
+    }
+
+    /// Whether polkit may ask for authentication. Off by default, as a greeter has no
+    /// authentication agent.
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    pub fn connection(&self) -> &DBusConnection {
+        &self.connection
+    }
+
+    fn call(
+        &self,
+        method: &str,
+        parameters: Option<&Variant>,
+        reply_type: &VariantTy,
+    ) -> Result<Variant, PowerError> {
+        Ok(self.connection.call_sync(
+            Some(LOGIND_BUS_NAME),
+            LOGIND_PATH,
+            LOGIND_MANAGER_INTERFACE,
+            method,
+            parameters,
+            Some(reply_type),
+            DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+        )?)
+    }
+
+    /// The answer of `Can<Action>`: `yes`, `no`, `challenge` or `na`.
+    fn can_reply(&self, action: PowerAction) -> Result<String, PowerError> {
+        let reply = self.call(
+            &format!("Can{}", logind_method(action)),
+            None,
+            VariantTy::new("(s)").unwrap(),
+        )?;
+        Ok(reply.child_value(0).str().unwrap_or_default().to_owned())
+    }
+}
+
+fn logind_method(action: PowerAction) -> &'static str {
+    match action {
+        PowerAction::Shutdown => "PowerOff",
+        PowerAction::Restart => "Reboot",
+        PowerAction::Suspend => "Suspend",
+        PowerAction::Hibernate => "Hibernate",
+    }
+}
+
+impl PowerManager for Logind {
+    /// Whether logind answers `yes`, as liblightdm does.
+    fn can(&self, action: PowerAction) -> bool {
+        self.can_reply(action).is_ok_and(|reply| reply == "yes")
+    }
+
+    /// The answer of logind, reported as [`PowerCapability::Inhibited`] when an inhibitor
+    /// blocks an action which logind answers `yes` or `challenge` for. Failed calls report
+    /// [`PowerCapability::No`].
+    fn capability(&self, action: PowerAction) -> PowerCapability {
+        let Ok(reply) = self.can_reply(action) else {
+            return PowerCapability::No;
+        };
+        let inhibited = || {
+            self.inhibitors()
+                .is_ok_and(|inhibitors| inhibitors.iter().any(|inhibitor| inhibitor.blocks(action)))
+        };
+        match reply.as_str() {
+            "yes" | "challenge" if inhibited() => PowerCapability::Inhibited,
+            "yes" => PowerCapability::Yes,
+            "challenge" => PowerCapability::Challenge,
+            "na" => PowerCapability::NotAvailable,
+            _ => PowerCapability::No,
+        }
+    }
+
+    fn inhibitors(&self) -> Result<Vec<Inhibitor>, PowerError> {
+        let reply = self.call(
+            "ListInhibitors",
+            None,
+            VariantTy::new("(a(ssssuu))").unwrap(),
+        )?;
+        let (inhibitors,) = reply
+            .get::<(Vec<(String, String, String, String, u32, u32)>,)>()
+            .unwrap_or_default();
+        Ok(inhibitors
+            .into_iter()
+            .map(|(what, who, why, mode, uid, pid)| Inhibitor {
+                what: what.split(':').map(str::to_owned).collect(),
+                who,
+                why,
+                mode: InhibitMode::from(mode.as_str()),
+                uid,
+                pid,
+            })
+            .collect())
+    }
+
+    fn run(&self, action: PowerAction) -> Result<(), PowerError> {
+        self.call(
+            logind_method(action),
+            Some(&(self.interactive,).to_variant()),
+            VariantTy::UNIT,
+        )
+        .map(drop)
+    }
+}
+
+/// Records actions instead of performing them, for kiosks, demos and tests.
+#[derive(Debug)]
+pub struct DryRun {
+    allowed: Vec<PowerAction>,
+    actions: Mutex<Vec<PowerAction>>,
+}
+
+impl Default for DryRun {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DryRun {
+    /// Allows every action.
+    pub fn new() -> Self {
+        Self::allowing(&PowerAction::ALL)
+    }
+
+    /// Allows only `allowed`; the others fail as liblightdm would when they are not permitted.
+    pub fn allowing(allowed: &[PowerAction]) -> Self {
+        Self {
+            allowed: allowed.to_vec(),
+            actions: Mutex::default(),
+        }
+    }
+
+    /// The actions run so far, oldest first.
+    pub fn actions(&self) -> Vec<PowerAction> {
+        self.actions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl PowerManager for DryRun {
+    fn can(&self, action: PowerAction) -> bool {
+        self.allowed.contains(&action)
+    }
+
+    fn run(&self, action: PowerAction) -> Result<(), PowerError> {
+        if !self.can(action) {
+            return Err(glib::Error::new(
+                gio::IOErrorEnum::PermissionDenied,
+                &format!("{action} is not allowed"),
+            )
+            .into());
+        }
+        self.actions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(action);
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+//! Framing of the messages exchanged between a greeter and the LightDM daemon.
+//!
+//! Every message starts with an 8 byte header holding the message id and the payload length.
+//! Integers are 32 bit big-endian and strings are a length followed by UTF-8 bytes, the same
+//! layout as `liblightdm-gobject/greeter.c`.
+
+use std::io;
+#[cfg(feature = "testing")]
+use std::io::Read;
+
+pub(crate) const HEADER_SIZE: usize = 8;
+
+/// Largest payload accepted from the other end.
+pub(crate) const MAX_MESSAGE_LENGTH: usize = 1 << 20;
+
+pub(crate) const API_VERSION: u32 = 1;
+
+/// Messages sent by the greeter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GreeterMessage {
+    Connect = 0,
+    Authenticate,
+    AuthenticateAsGuest,
+    ContinueAuthentication,
+    StartSession,
+    CancelAuthentication,
+    SetLanguage,
+    AuthenticateRemote,
+    EnsureSharedDir,
+    SetResettable,
+}
+
+impl GreeterMessage {
+    #[cfg(feature = "testing")]
+    pub(crate) fn from_id(id: u32) -> Option<Self> {
+        Some(match id {
+            0 => Self::Connect,
+            1 => Self::Authenticate,
+            2 => Self::AuthenticateAsGuest,
+            3 => Self::ContinueAuthentication,
+            4 => Self::StartSession,
+            5 => Self::CancelAuthentication,
+            6 => Self::SetLanguage,
+            7 => Self::AuthenticateRemote,
+            8 => Self::EnsureSharedDir,
+            9 => Self::SetResettable,
+            _ => return None,
+        })
+    }
+}
+
+/// Messages sent by the daemon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "native"), allow(dead_code))]
+pub(crate) enum ServerMessage {
+    Connected = 0,
+    PromptAuthentication,
+    EndAuthentication,
+    SessionResult,
+    SharedDirResult,
+    Idle,
+    Reset,
+    ConnectedV2,
+}
+
+impl ServerMessage {
+    #[cfg(feature = "native")]
+    pub(crate) fn from_id(id: u32) -> Option<Self> {
+        Some(match id {
+            0 => Self::Connected,
+            1 => Self::PromptAuthentication,
+            2 => Self::EndAuthentication,
+            3 => Self::SessionResult,
+            4 => Self::SharedDirResult,
+            5 => Self::Idle,
+            6 => Self::Reset,
+            7 => Self::ConnectedV2,
+            _ => return None,
+        })
+    }
+}
+
+// PAM message styles carried by `PromptAuthentication`.
+pub(crate) const PAM_PROMPT_ECHO_OFF: u32 = 1;
+pub(crate) const PAM_PROMPT_ECHO_ON: u32 = 2;
+pub(crate) const PAM_ERROR_MSG: u32 = 3;
+pub(crate) const PAM_TEXT_INFO: u32 = 4;
+
+pub(crate) const PAM_SUCCESS: u32 = 0;
+
+/// Builds one message, filling in the header length on [`MessageBuilder::finish`].
+pub(crate) struct MessageBuilder {
+    buffer: Vec<u8>,
+}
+
+impl MessageBuilder {
+    pub(crate) fn new(id: u32) -> Self {
+        Self::with_capacity(id, 64 - HEADER_SIZE)
+    }
+
+    /// Reserves room for `payload` bytes up front, so the buffer is never reallocated while
+    /// building a message of at most that size.
+    pub(crate) fn with_capacity(id: u32, payload: usize) -> Self {
+        let mut buffer = Vec::with_capacity(HEADER_SIZE + payload);
+        buffer.extend_from_slice(&id.to_be_bytes());
+        buffer.extend_from_slice(&[0; 4]);
+        Self { buffer }
+    }
+
+    pub(crate) fn int(mut self, value: u32) -> Self {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    /// Writes `value`, with `None` encoded as an empty string like liblightdm does for `NULL`.
+    pub(crate) fn string(mut self, value: Option<&str>) -> Self {
+        let value = value.unwrap_or_default();
+        self.buffer
+            .extend_from_slice(&(value.len() as u32).to_be_bytes());
+        self.buffer.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        let length = (self.buffer.len() - HEADER_SIZE) as u32;
+        self.buffer[4..HEADER_SIZE].copy_from_slice(&length.to_be_bytes());
+        self.buffer
+    }
+}
+
+/// The message id and payload length of a header.
+fn parse_header(header: &[u8; HEADER_SIZE]) -> io::Result<(u32, usize)> {
+    let id = u32::from_be_bytes(header[..4].try_into().unwrap());
+    let length = u32::from_be_bytes(header[4..].try_into().unwrap()) as usize;
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {length} bytes exceeds the protocol limit"),
+        ));
+    }
+    Ok((id, length))
+}
+
+/// Reads one message, returning its id and payload.
+#[cfg(feature = "testing")]
+pub(crate) fn read_message(reader: &mut impl Read) -> io::Result<(u32, Vec<u8>)> {
+    let mut header = [0; HEADER_SIZE];
+    reader.read_exact(&mut header)?;
+    let (id, length) = parse_header(&header)?;
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+    Ok((id, payload))
+}
+
+/// Removes the first message from `buffer` if it was received in full, leaving a partial
+/// message in place until the rest arrives.
+#[cfg(feature = "native")]
+pub(crate) fn take_message(buffer: &mut Vec<u8>) -> io::Result<Option<(u32, Vec<u8>)>> {
+    let Some(header) = buffer.first_chunk::<HEADER_SIZE>() else {
+        return Ok(None);
+    };
+    let (id, length) = parse_header(header)?;
+    let Some(payload) = buffer.get(HEADER_SIZE..HEADER_SIZE + length) else {
+        return Ok(None);
+    };
+    let payload = payload.to_vec();
+    buffer.drain(..HEADER_SIZE + length);
+    Ok(Some((id, payload)))
+}
+
+/// Cursor over a message payload.
+pub(crate) struct Payload<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Payload<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    #[cfg(feature = "native")]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.offset..self.offset + length)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated message"))?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    pub(crate) fn int(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn string(&mut self) -> io::Result<String> {
+        let length = self.int()? as usize;
+        String::from_utf8(self.take(length)?.to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
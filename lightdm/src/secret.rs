@@ -0,0 +1,142 @@
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use std::fmt;
+
+use crate::Error;
+
+/// A response to a [`PromptType::Secret`](crate::PromptType::Secret) prompt, wiped from memory
+/// when dropped.
+///
+/// Growing the buffer moves the text into a larger allocation and wipes the old one, so no copy
+/// is left behind in freed memory. Answer secret prompts with
+/// [`GreeterExtManual::respond_secret`](crate::prelude::GreeterExtManual::respond_secret).
+#[derive(Default, Clone)]
+pub struct Secret {
+    text: Zeroizing<String>,
+}
+
+impl Secret {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            text: Zeroizing::new(String::with_capacity(capacity)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.reserve(c.len_utf8());
+        self.text.push(c);
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.reserve(s.len());
+        self.text.push_str(s);
+    }
+
+    pub fn pop(&mut self) -> Option<char> {
+        let c = self.text.pop();
+        // Wipe the bytes which are now spare capacity.
+        self.text.zeroize_spare();
+        c
+    }
+
+    pub fn clear(&mut self) {
+        self.text.zeroize();
+    }
+
+    /// The secret text. Avoid copying it into buffers which are not wiped.
+    pub fn expose(&self) -> &str {
+        &self.text
+    }
+
+    /// Fails if the text contains a NUL byte, which the daemon would cut the response at.
+    pub(crate) fn check_nul(&self) -> Result<(), Error> {
+        if self.text.contains('\0') {
+            return Err(Error::Other(glib::Error::new(
+                glib::ConvertError::EmbeddedNul,
+                "Response contains a NUL byte",
+            )));
+        }
+        Ok(())
+    }
+
+    /// A NUL-terminated copy for passing to C, wiped when dropped.
+    #[cfg(feature = "liblightdm")]
+    pub(crate) fn to_c_bytes(&self) -> Result<Zeroizing<Vec<u8>>, Error> {
+        self.check_nul()?;
+        let mut bytes = Zeroizing::new(Vec::with_capacity(self.text.len() + 1));
+        bytes.extend_from_slice(self.text.as_bytes());
+        bytes.push(0);
+        Ok(bytes)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        if self.text.capacity() - self.text.len() >= additional {
+            return;
+        }
+        let capacity = (self.text.len() + additional).max(self.text.capacity() * 2);
+        let mut text = Zeroizing::new(String::with_capacity(capacity));
+        text.push_str(&self.text);
+        // The old buffer is wiped as it is dropped here.
+        self.text = text;
+    }
+}
+
+trait ZeroizeSpare {
+    fn zeroize_spare(&mut self);
+}
+
+impl ZeroizeSpare for String {
+    fn zeroize_spare(&mut self) {
+        let len = self.len();
+        let mut bytes = std::mem::take(self).into_bytes();
+        bytes.spare_capacity_mut().zeroize();
+        bytes.truncate(len);
+        // SAFETY: the bytes are unchanged up to `len`, which is on a char boundary.
+        *self = unsafe { String::from_utf8_unchecked(bytes) };
+    }
+}
+
+impl From<String> for Secret {
+    /// Takes ownership of `text` without copying it.
+    fn from(text: String) -> Self {
+        Self {
+            text: Zeroizing::new(text),
+        }
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(text: &str) -> Self {
+        let mut secret = Self::with_capacity(text.len());
+        secret.push_str(text);
+        secret
+    }
+}
+
+impl ZeroizeOnDrop for Secret {}
+
+impl PartialEq for Secret {
+    fn eq(&self, other: &Self) -> bool {
+        *self.text == *other.text
+    }
+}
+
+impl Eq for Secret {}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
@@ -0,0 +1,24 @@
+use glib::object::IsA;
+
+use super::{
+    Session,
+    prelude::SessionExt,
+    sessions::{self, SessionType},
+};
+
+pub trait SessionExtManual: IsA<Session> + 'static {
+    /// The parsed `session_type`, [`SessionType::X`] if it is unset.
+    fn kind(&self) -> SessionType {
+        self.as_ref()
+            .session_type()
+            .map(|name| SessionType::from(name.as_str()))
+            .unwrap_or_default()
+    }
+
+    /// The desktop of the session key, see [`sessions::desktop_of`].
+    fn desktop(&self) -> String {
+        sessions::desktop_of(self.as_ref().key().as_deref().unwrap_or_default())
+    }
+}
+
+impl<O: IsA<Session>> SessionExtManual for O {}
@@ -0,0 +1,421 @@
+//! Catalog of the session desktop files, read directly instead of through liblightdm.
+//!
+//! [`SessionCatalog::load`] reads the `*.desktop` files of the `sessions-directory` and
+//! `remote-sessions-directory` of `lightdm.conf`, keeping the `Exec`, `TryExec`,
+//! `DesktopNames`, `Hidden` and `NoDisplay` keys which `Session` does not expose. As in
+//! liblightdm, the first file with a given key wins and a session from a `wayland-sessions`
+//! directory defaults to type `wayland`.
+//!
+//! [`SessionType`] parses the session type of entries and of `Session` objects, and with
+//! liblightdm the free functions here pick, pair up and group `Session`s by desktop.
+
+#[cfg(feature = "liblightdm")]
+use glib::object::IsA;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+#[cfg(feature = "liblightdm")]
+use crate::{
+    Greeter, Session, User,
+    prelude::{GreeterExt, SessionExt, SessionExtManual, UserExt},
+};
+use crate::{
+    config::{Config, Error, Section},
+    keyfile::{self, KeyFile},
+    locale::LocaleCode,
+};
+
+const GROUP: &str = "Desktop Entry";
+
+/// The `X-LightDM-Session-Type` of a session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(from = "String", into = "String")
+)]
+pub enum SessionType {
+    /// `x`, the default.
+    #[default]
+    X,
+    Wayland,
+    Mir,
+    /// Any other type, e.g. of a remote session.
+    Unknown(String),
+}
+
+impl SessionType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::X => "x",
+            Self::Wayland => "wayland",
+            Self::Mir => "mir",
+            Self::Unknown(name) => name,
+        }
+    }
+}
+
+impl From<&str> for SessionType {
+    fn from(name: &str) -> Self {
+        match name {
+            "x" => Self::X,
+            "wayland" => Self::Wayland,
+            "mir" => Self::Mir,
+            name => Self::Unknown(name.to_owned()),
+        }
+    }
+}
+
+impl From<String> for SessionType {
+    fn from(name: String) -> Self {
+        match Self::from(name.as_str()) {
+            Self::Unknown(_) => Self::Unknown(name),
+            known => known,
+        }
+    }
+}
+
+impl From<SessionType> for String {
+    fn from(session_type: SessionType) -> Self {
+        match session_type {
+            SessionType::Unknown(name) => name,
+            known => known.as_str().to_owned(),
+        }
+    }
+}
+
+impl FromStr for SessionType {
+    type Err = Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Infallible> {
+        Ok(name.into())
+    }
+}
+
+impl fmt::Display for SessionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The desktop a session key belongs to, with the display server variant suffix removed:
+/// `gnome-xorg`, `plasmawayland` and `ubuntu-wayland` give `gnome`, `plasma` and `ubuntu`.
+pub fn desktop_of(key: &str) -> String {
+    let key = key.to_lowercase();
+    for suffix in ["-wayland", "wayland", "-xorg", "xorg", "-x11", "x11"] {
+        if let Some(desktop) = key.strip_suffix(suffix)
+            && !desktop.is_empty()
+        {
+            return desktop.trim_end_matches(['-', '_']).to_owned();
+        }
+    }
+    key
+}
+
+/// One session desktop file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SessionEntry {
+    /// The file name without `.desktop`, as in `Session::key`.
+    pub key: String,
+    pub path: PathBuf,
+    pub name: String,
+    /// `Name[locale]` translations, by locale.
+    pub localized_names: BTreeMap<String, String>,
+    pub comment: Option<String>,
+    pub exec: Option<String>,
+    pub try_exec: Option<String>,
+    pub desktop_names: Vec<String>,
+    /// `X-LightDM-Session-Type`, or the default of the directory.
+    pub session_type: SessionType,
+    pub hidden: bool,
+    pub no_display: bool,
+    /// Whether it came from a `remote-sessions-directory`.
+    pub remote: bool,
+}
+
+impl SessionEntry {
+    fn parse(
+        path: &Path,
+        text: &str,
+        default_type: SessionType,
+        remote: bool,
+    ) -> Result<Self, Error> {
+        let mut keyfile = KeyFile::default();
+        keyfile.load_str(text, path, |name| name)?;
+        let section = match keyfile.group(GROUP) {
+            Some(group) => Section::new(GROUP, group.entries.clone()),
+            None => Section::new(GROUP, Vec::new()),
+        };
+        let localized_names = section
+            .keys()
+            .filter_map(|key| key.strip_prefix("Name[")?.strip_suffix(']'))
+            .filter_map(|locale| {
+                let name = section.string(&format!("Name[{locale}]"))?;
+                Some((locale.to_owned(), name))
+            })
+            .collect();
+        Ok(Self {
+            key: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: path.to_owned(),
+            name: section.string("Name").unwrap_or_default(),
+            localized_names,
+            comment: section.string("Comment"),
+            exec: section.string("Exec"),
+            try_exec: section.string("TryExec"),
+            desktop_names: section.list("DesktopNames"),
+            session_type: section
+                .string("X-LightDM-Session-Type")
+                .map_or(default_type, SessionType::from),
+            hidden: section.boolean("Hidden")?.unwrap_or_default(),
+            no_display: section.boolean("NoDisplay")?.unwrap_or_default(),
+            remote,
+        })
+    }
+
+    /// The name for `locale` (e.g. `de_DE.UTF-8`), falling back to less specific locales and
+    /// then to `Name`.
+    pub fn name_for(&self, locale: &str) -> &str {
+        LocaleCode::fallbacks_of(locale)
+            .iter()
+            .find_map(|locale| self.localized_names.get(locale))
+            .unwrap_or(&self.name)
+    }
+
+    /// The program `TryExec` names, or else the first word of `Exec`.
+    pub fn program(&self) -> Option<String> {
+        if let Some(try_exec) = &self.try_exec {
+            return Some(try_exec.clone());
+        }
+        let argv = glib::shell_parse_argv(self.exec.as_deref()?).ok()?;
+        argv.into_iter()
+            .next()
+            .map(|program| program.to_string_lossy().into_owned())
+    }
+
+    /// Whether [`program`](Self::program) is installed. Remote sessions without a program
+    /// count as installed.
+    pub fn program_exists(&self) -> bool {
+        match self.program() {
+            Some(program) => glib::find_program_in_path(program).is_some(),
+            None => self.remote,
+        }
+    }
+
+    /// Whether the session should be offered: it is neither `Hidden` nor `NoDisplay` and its
+    /// program exists.
+    pub fn is_available(&self) -> bool {
+        !self.hidden && !self.no_display && self.program_exists()
+    }
+}
+
+/// The local and remote session desktop files.
+#[derive(Debug, Default)]
+pub struct SessionCatalog {
+    sessions: Vec<SessionEntry>,
+    remote_sessions: Vec<SessionEntry>,
+    errors: Vec<Error>,
+}
+
+impl SessionCatalog {
+    /// Reads the directories configured in `config`.
+    pub fn load(config: &Config) -> Result<Self, Error> {
+        Self::load_dirs(
+            &config.sessions_directories(),
+            &config.remote_sessions_directories(),
+        )
+    }
+
+    pub fn load_dirs(
+        sessions_dirs: &[PathBuf],
+        remote_sessions_dirs: &[PathBuf],
+    ) -> Result<Self, Error> {
+        let mut catalog = Self::default();
+        catalog.sessions = catalog.load_entries(sessions_dirs, false)?;
+        catalog.remote_sessions = catalog.load_entries(remote_sessions_dirs, true)?;
+        Ok(catalog)
+    }
+
+    fn load_entries(&mut self, dirs: &[PathBuf], remote: bool) -> Result<Vec<SessionEntry>, Error> {
+        let mut entries = Vec::<SessionEntry>::new();
+        for dir in dirs {
+            let default_type = if dir.ends_with("wayland-sessions") {
+                SessionType::Wayland
+            } else {
+                SessionType::X
+            };
+            for path in keyfile::files(dir, "desktop")? {
+                let text = match fs::read_to_string(&path) {
+                    Ok(text) => text,
+                    Err(err) => {
+                        self.errors.push(Error::Io { path, source: err });
+                        continue;
+                    }
+                };
+                match SessionEntry::parse(&path, &text, default_type.clone(), remote) {
+                    Ok(entry) if entries.iter().any(|other| other.key == entry.key) => {}
+                    Ok(entry) => entries.push(entry),
+                    Err(err) => self.errors.push(err),
+                }
+            }
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /// The local sessions, sorted by name.
+    pub fn sessions(&self) -> &[SessionEntry] {
+        &self.sessions
+    }
+
+    /// The remote sessions, sorted by name.
+    pub fn remote_sessions(&self) -> &[SessionEntry] {
+        &self.remote_sessions
+    }
+
+    /// The files which could not be read or parsed, and were skipped.
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// The local sessions which [are available](SessionEntry::is_available).
+    pub fn available(&self) -> impl Iterator<Item = &SessionEntry> {
+        self.sessions.iter().filter(|entry| entry.is_available())
+    }
+
+    pub fn session(&self, key: &str) -> Option<&SessionEntry> {
+        self.sessions.iter().find(|entry| entry.key == key)
+    }
+
+    pub fn remote_session(&self, key: &str) -> Option<&SessionEntry> {
+        self.remote_sessions.iter().find(|entry| entry.key == key)
+    }
+
+    /// The entry with the same key as `session`, local ones first.
+    #[cfg(feature = "liblightdm")]
+    pub fn entry_for(&self, session: &impl IsA<Session>) -> Option<&SessionEntry> {
+        let key = session.key()?;
+        self.session(&key).or_else(|| self.remote_session(&key))
+    }
+
+    /// Drops the sessions whose entry is not available, e.g. to filter
+    /// `functions::sessions()` for a session chooser. Sessions without an entry are kept.
+    #[cfg(feature = "liblightdm")]
+    pub fn filter_available<S: IsA<Session>>(
+        &self,
+        sessions: impl IntoIterator<Item = S>,
+    ) -> Vec<S> {
+        sessions
+            .into_iter()
+            .filter(|session| {
+                self.entry_for(session)
+                    .is_none_or(SessionEntry::is_available)
+            })
+            .collect()
+    }
+}
+
+/// Why [`resolve_key`] picked a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionSource {
+    /// The user's last session.
+    User,
+    /// The `autologin-session` hint.
+    Autologin,
+    /// The `default-session` hint.
+    Default,
+    /// The first installed session, as none of the others is installed.
+    Fallback,
+}
+
+/// Picks the first of `user`, `autologin` and `default` which is one of `keys`, else the first
+/// of `keys`. Stale keys, e.g. of an uninstalled session, are skipped.
+pub fn resolve_key<'k>(
+    keys: &[&'k str],
+    user: Option<&str>,
+    autologin: Option<&str>,
+    default: Option<&str>,
+) -> Option<(&'k str, SessionSource)> {
+    [
+        (user, SessionSource::User),
+        (autologin, SessionSource::Autologin),
+        (default, SessionSource::Default),
+    ]
+    .into_iter()
+    .find_map(|(wanted, source)| {
+        let key = keys.iter().find(|key| Some(**key) == wanted)?;
+        Some((*key, source))
+    })
+    .or_else(|| Some((*keys.first()?, SessionSource::Fallback)))
+}
+
+/// The session of `sessions` to preselect for `user`, and why it was chosen, in the order of
+/// [`resolve_session`](crate::prelude::GreeterExtManual::resolve_session).
+#[cfg(feature = "liblightdm")]
+pub fn preferred_session<S: IsA<Session>>(
+    greeter: &impl IsA<Greeter>,
+    user: Option<&User>,
+    sessions: &[S],
+) -> Option<(S, SessionSource)> {
+    let greeter = greeter.as_ref();
+    let keys = sessions
+        .iter()
+        .map(|session| session.key().unwrap_or_default())
+        .collect::<Vec<_>>();
+    let name = user.and_then(|user| user.name());
+    let autologin = match (name, greeter.autologin_user_hint()) {
+        (None, _) => greeter.autologin_session_hint(),
+        (Some(name), Some(autologin_user)) if name == autologin_user => {
+            greeter.autologin_session_hint()
+        }
+        _ => None,
+    };
+    let (key, source) = resolve_key(
+        &keys.iter().map(|key| key.as_str()).collect::<Vec<_>>(),
+        user.and_then(|user| user.session()).as_deref(),
+        autologin.as_deref(),
+        greeter.default_session_hint().as_deref(),
+    )?;
+    let index = keys.iter().position(|other| other == key)?;
+    Some((sessions[index].clone(), source))
+}
+
+/// The session of the same desktop as `session` with type `session_type`, e.g. the Wayland
+/// variant of `gnome-xorg`, or `session` itself if there is none.
+#[cfg(feature = "liblightdm")]
+pub fn prefer_type<S: IsA<Session>>(sessions: &[S], session: &S, session_type: &SessionType) -> S {
+    if session.kind() == *session_type {
+        return session.clone();
+    }
+    let desktop = session.desktop();
+    sessions
+        .iter()
+        .find(|other| other.kind() == *session_type && other.desktop() == desktop)
+        .unwrap_or(session)
+        .clone()
+}
+
+/// `sessions` grouped by [desktop](desktop_of), in order of first appearance.
+#[cfg(feature = "liblightdm")]
+pub fn group_by_desktop<S: IsA<Session>>(sessions: &[S]) -> Vec<(String, Vec<S>)> {
+    let mut groups = Vec::<(String, Vec<S>)>::new();
+    for session in sessions {
+        let desktop = session.desktop();
+        match groups.iter_mut().find(|(name, _)| *name == desktop) {
+            Some((_, group)) => group.push(session.clone()),
+            None => groups.push((desktop, vec![session.clone()])),
+        }
+    }
+    groups
+}
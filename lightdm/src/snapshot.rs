@@ -0,0 +1,269 @@
+//! Plain-data copies of the liblightdm objects.
+//!
+//! Each `*Info` struct is built from its GObject with `From`, and the free functions here
+//! collect a whole list at once. Unlike the GObjects these are `Send + Sync`, so a
+//! [`SystemSnapshot`] can be read from worker threads while [`SnapshotRefresher`] keeps it up
+//! to date from the main thread.
+//!
+//! With the `serde` feature the structs are serializable, and the `*_json` functions dump a
+//! whole list in one call, e.g. `snapshot::users_json(&UserList::instance().unwrap())`.
+
+use glib::{
+    SignalHandlerId,
+    object::{IsA, ObjectExt},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::sync::{Arc, PoisonError, RwLock};
+
+use super::{
+    Language, Layout, Session, User, UserList, functions,
+    prelude::{LanguageExt, LayoutExt, SessionExt, UserExt, UserListExt},
+    sessions::SessionType,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UserInfo {
+    pub name: Option<String>,
+    pub real_name: Option<String>,
+    pub display_name: Option<String>,
+    pub home_directory: Option<String>,
+    pub image: Option<String>,
+    pub background: Option<String>,
+    pub language: Option<String>,
+    pub layout: Option<String>,
+    pub layouts: Vec<String>,
+    pub session: Option<String>,
+    pub uid: u32,
+    pub is_locked: bool,
+    pub is_logged_in: bool,
+    pub has_messages: bool,
+}
+
+impl<O: IsA<User>> From<&O> for UserInfo {
+    fn from(user: &O) -> Self {
+        Self {
+            name: user.name().map(Into::into),
+            real_name: user.real_name().map(Into::into),
+            display_name: user.display_name().map(Into::into),
+            home_directory: user.home_directory().map(Into::into),
+            image: user.image().map(Into::into),
+            background: user.background().map(Into::into),
+            language: user.language().map(Into::into),
+            layout: user.layout().map(Into::into),
+            layouts: user.layouts().into_iter().map(Into::into).collect(),
+            session: user.session().map(Into::into),
+            uid: user.uid(),
+            is_locked: user.is_locked(),
+            is_logged_in: user.is_logged_in(),
+            has_messages: user.has_messages(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SessionInfo {
+    pub key: Option<String>,
+    pub name: Option<String>,
+    pub comment: Option<String>,
+    pub session_type: Option<SessionType>,
+}
+
+impl<O: IsA<Session>> From<&O> for SessionInfo {
+    fn from(session: &O) -> Self {
+        Self {
+            key: session.key().map(Into::into),
+            name: session.name().map(Into::into),
+            comment: session.comment().map(Into::into),
+            session_type: session.session_type().map(|name| name.as_str().into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LanguageInfo {
+    pub code: Option<String>,
+    pub name: Option<String>,
+    pub territory: Option<String>,
+}
+
+impl<O: IsA<Language>> From<&O> for LanguageInfo {
+    fn from(language: &O) -> Self {
+        Self {
+            code: language.code().map(Into::into),
+            name: language.name().map(Into::into),
+            territory: language.territory().map(Into::into),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LayoutInfo {
+    pub name: Option<String>,
+    pub short_description: Option<String>,
+    pub description: Option<String>,
+}
+
+impl<O: IsA<Layout>> From<&O> for LayoutInfo {
+    fn from(layout: &O) -> Self {
+        Self {
+            name: layout.name().map(Into::into),
+            short_description: layout.short_description().map(Into::into),
+            description: layout.description().map(Into::into),
+        }
+    }
+}
+
+fn collect<T, I: for<'a> From<&'a T>>(objects: Vec<T>) -> Vec<I> {
+    objects.iter().map(I::from).collect()
+}
+
+pub fn users(user_list: &impl IsA<UserList>) -> Vec<UserInfo> {
+    collect(user_list.users())
+}
+
+pub fn sessions() -> Vec<SessionInfo> {
+    collect(functions::sessions())
+}
+
+pub fn remote_sessions() -> Vec<SessionInfo> {
+    collect(functions::remote_sessions())
+}
+
+pub fn languages() -> Vec<LanguageInfo> {
+    collect(functions::languages())
+}
+
+pub fn layouts() -> Vec<LayoutInfo> {
+    collect(functions::layouts())
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub fn users_json(user_list: &impl IsA<UserList>) -> serde_json::Result<String> {
+    serde_json::to_string(&users(user_list))
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub fn sessions_json() -> serde_json::Result<String> {
+    serde_json::to_string(&sessions())
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub fn remote_sessions_json() -> serde_json::Result<String> {
+    serde_json::to_string(&remote_sessions())
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub fn languages_json() -> serde_json::Result<String> {
+    serde_json::to_string(&languages())
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub fn layouts_json() -> serde_json::Result<String> {
+    serde_json::to_string(&layouts())
+}
+
+/// Everything liblightdm reports about the system, cheap to clone and safe to share.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SystemSnapshot {
+    pub users: Arc<[UserInfo]>,
+    pub sessions: Arc<[SessionInfo]>,
+    pub remote_sessions: Arc<[SessionInfo]>,
+    pub languages: Arc<[LanguageInfo]>,
+    pub layouts: Arc<[LayoutInfo]>,
+}
+
+impl SystemSnapshot {
+    /// Copies the current state. Must be called on the thread running the main context.
+    pub fn capture(user_list: &impl IsA<UserList>) -> Self {
+        Self {
+            users: users(user_list).into(),
+            sessions: sessions().into(),
+            remote_sessions: remote_sessions().into(),
+            languages: languages().into(),
+            layouts: layouts().into(),
+        }
+    }
+}
+
+/// Shared handle to the latest [`SystemSnapshot`], readable from any thread.
+#[derive(Debug, Clone, Default)]
+pub struct SharedSnapshot(Arc<RwLock<SystemSnapshot>>);
+
+impl SharedSnapshot {
+    pub fn get(&self) -> SystemSnapshot {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn set_users(&self, users: Arc<[UserInfo]>) {
+        self.0.write().unwrap_or_else(PoisonError::into_inner).users = users;
+    }
+}
+
+/// Rebuilds the users of a [`SharedSnapshot`] whenever the [`UserList`] emits `user-added`,
+/// `user-changed` or `user-removed`.
+///
+/// Lives on the main thread; the handlers are disconnected when it is dropped.
+pub struct SnapshotRefresher {
+    user_list: UserList,
+    shared: SharedSnapshot,
+    handlers: Vec<SignalHandlerId>,
+}
+
+impl SnapshotRefresher {
+    pub fn new(user_list: &impl IsA<UserList>) -> Self {
+        let user_list = user_list.as_ref().clone();
+        let shared = SharedSnapshot(Arc::new(RwLock::new(SystemSnapshot::capture(&user_list))));
+        let refresh = {
+            let shared = shared.clone();
+            move |user_list: &UserList, _: &User| shared.set_users(users(user_list).into())
+        };
+        let handlers = vec![
+            user_list.connect_user_added(refresh.clone()),
+            user_list.connect_user_changed(refresh.clone()),
+            user_list.connect_user_removed(refresh),
+        ];
+        Self {
+            user_list,
+            shared,
+            handlers,
+        }
+    }
+
+    /// Handle to pass to other threads.
+    pub fn shared(&self) -> SharedSnapshot {
+        self.shared.clone()
+    }
+
+    /// Recaptures everything, including sessions, languages and layouts.
+    pub fn refresh_all(&self) {
+        let snapshot = SystemSnapshot::capture(&self.user_list);
+        *self
+            .shared
+            .0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = snapshot;
+    }
+}
+
+impl Drop for SnapshotRefresher {
+    fn drop(&mut self) {
+        for id in self.handlers.drain(..) {
+            self.user_list.disconnect(id);
+        }
+    }
+}
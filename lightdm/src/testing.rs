@@ -0,0 +1,661 @@
+//! In-process stand-in for the LightDM daemon, for testing greeters without a display manager.
+//!
+//! [`MockDaemon`] creates the two pipes a greeter inherits from LightDM, points
+//! `LIGHTDM_TO_SERVER_FD`/`LIGHTDM_FROM_SERVER_FD` at them and answers the greeter protocol from
+//! a background thread, following the [`Conversation`]s scripted for each user.
+//!
+//! ```no_run
+//! use lightdm::prelude::*;
+//! use lightdm::testing::{Conversation, MockDaemon};
+//!
+//! let daemon = MockDaemon::builder()
+//!     .user("alice", Conversation::new().secret("Password: ", "hunter2"))
+//!     .start();
+//!
+//! let greeter = lightdm::Greeter::new();
+//! greeter.connect_to_daemon_sync().unwrap();
+//! greeter.authenticate(Some("alice")).unwrap();
+//! // ... answer the prompt from a `show-prompt` handler ...
+//! # drop(daemon);
+//! ```
+//!
+//! The environment is process-global, so a daemon holds a lock for as long as it lives and tests
+//! using one run one after the other.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Write,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+    sync::{Arc, Mutex, MutexGuard},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use crate::protocol::{self, GreeterMessage, MessageBuilder, Payload, ServerMessage};
+
+const PAM_AUTH_ERR: u32 = 7;
+const PAM_USER_UNKNOWN: u32 = 10;
+const PAM_CONV_ERR: u32 = 19;
+
+static ENVIRONMENT: Mutex<()> = Mutex::new(());
+
+/// One exchange of a scripted PAM conversation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Info(String),
+    Error(String),
+    /// An informational message sent along with the prompt before it.
+    PromptInfo(String),
+    Prompt {
+        text: String,
+        secret: bool,
+        expected: String,
+    },
+}
+
+/// What PAM says to one user, in order, and how it ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversation {
+    steps: Vec<Step>,
+    succeeds: bool,
+}
+
+impl Default for Conversation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Conversation {
+    /// A conversation which succeeds once every prompt got its expected answer.
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            succeeds: true,
+        }
+    }
+
+    /// Shows an informational message.
+    pub fn info(mut self, text: &str) -> Self {
+        self.steps.push(Step::Info(text.to_owned()));
+        self
+    }
+
+    /// Shows an error message.
+    pub fn error(mut self, text: &str) -> Self {
+        self.steps.push(Step::Error(text.to_owned()));
+        self
+    }
+
+    /// Shows an informational message in the same batch as the prompt before it, so that it
+    /// arrives while that prompt is still pending.
+    pub fn prompt_info(mut self, text: &str) -> Self {
+        self.steps.push(Step::PromptInfo(text.to_owned()));
+        self
+    }
+
+    /// Asks a visible question; any other answer than `expected` fails the authentication.
+    pub fn question(mut self, text: &str, expected: &str) -> Self {
+        self.steps.push(Step::Prompt {
+            text: text.to_owned(),
+            secret: false,
+            expected: expected.to_owned(),
+        });
+        self
+    }
+
+    /// Asks for a secret; any other answer than `expected` fails the authentication.
+    pub fn secret(mut self, text: &str, expected: &str) -> Self {
+        self.steps.push(Step::Prompt {
+            text: text.to_owned(),
+            secret: true,
+            expected: expected.to_owned(),
+        });
+        self
+    }
+
+    /// Fails the authentication even when every answer was right.
+    pub fn fail(mut self) -> Self {
+        self.succeeds = false;
+        self
+    }
+}
+
+/// A message received from the greeter, as recorded by [`MockDaemon::requests`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Request {
+    Connect {
+        version: String,
+    },
+    Authenticate {
+        sequence_number: u32,
+        username: Option<String>,
+    },
+    AuthenticateAsGuest {
+        sequence_number: u32,
+    },
+    AuthenticateRemote {
+        sequence_number: u32,
+        session: String,
+        username: Option<String>,
+    },
+    ContinueAuthentication {
+        responses: Vec<String>,
+    },
+    CancelAuthentication,
+    StartSession {
+        session: Option<String>,
+    },
+    SetLanguage {
+        language: String,
+    },
+    EnsureSharedDir {
+        username: String,
+    },
+    SetResettable {
+        resettable: bool,
+    },
+}
+
+/// Configures a [`MockDaemon`] before it starts.
+#[derive(Debug, Default)]
+pub struct MockDaemonBuilder {
+    hints: Vec<(String, String)>,
+    users: HashMap<String, Conversation>,
+    shared_dir: Option<String>,
+    session_fails: bool,
+}
+
+impl MockDaemonBuilder {
+    /// Sends `name=value` with the other hints on connect.
+    pub fn hint(mut self, name: &str, value: &str) -> Self {
+        self.hints.retain(|(n, _)| n != name);
+        self.hints.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Scripts the conversation for `username`. Unknown users fail authentication.
+    pub fn user(mut self, username: &str, conversation: Conversation) -> Self {
+        self.users.insert(username.to_owned(), conversation);
+        self
+    }
+
+    /// Advertises `username` for autologin after `timeout` seconds.
+    pub fn autologin_user(self, username: &str, timeout: u32) -> Self {
+        self.hint("autologin-user", username)
+            .hint("autologin-timeout", &timeout.to_string())
+    }
+
+    /// Advertises guest autologin.
+    pub fn autologin_guest(self) -> Self {
+        self.hint("autologin-guest", "true")
+    }
+
+    /// Advertises a guest account, which always authenticates.
+    pub fn guest_account(self) -> Self {
+        self.hint("has-guest-account", "true")
+    }
+
+    /// Sets the hint telling the greeter it was started to unlock a session.
+    pub fn lock_hint(self, lock: bool) -> Self {
+        self.hint("lock-screen", if lock { "true" } else { "false" })
+    }
+
+    /// Directory returned for shared data dir requests; `<dir>/<username>` is answered.
+    pub fn shared_dir(mut self, dir: &str) -> Self {
+        self.shared_dir = Some(dir.to_owned());
+        self
+    }
+
+    /// Makes every session start fail, as when the session binary is missing.
+    pub fn session_fails(mut self) -> Self {
+        self.session_fails = true;
+        self
+    }
+
+    /// Creates the pipes, exports them to the environment and starts answering.
+    pub fn start(self) -> MockDaemon {
+        let lock = ENVIRONMENT.lock().unwrap_or_else(|err| err.into_inner());
+
+        let [to_server_read, to_server_write] = pipe();
+        let [from_server_read, from_server_write] = pipe();
+        // SAFETY: `ENVIRONMENT` serializes daemons, the only writers of these variables.
+        unsafe {
+            std::env::set_var("LIGHTDM_TO_SERVER_FD", to_server_write.to_string());
+            std::env::set_var("LIGHTDM_FROM_SERVER_FD", from_server_read.to_string());
+        }
+
+        // The greeter ends are handed over through the environment; the daemon owns the rest.
+        let [shutdown_read, shutdown_write] = pipe();
+        let (to_server_read, from_server_write, shutdown_read, shutdown_write) = unsafe {
+            (
+                OwnedFd::from_raw_fd(to_server_read),
+                OwnedFd::from_raw_fd(from_server_write),
+                OwnedFd::from_raw_fd(shutdown_read),
+                OwnedFd::from_raw_fd(shutdown_write),
+            )
+        };
+
+        let shared = Arc::new(Shared::default());
+        let writer = Arc::new(Mutex::new(File::from(from_server_write)));
+        let thread = {
+            let shared = shared.clone();
+            let writer = writer.clone();
+            let reader = File::from(to_server_read);
+            std::thread::spawn(move || Server::new(self, shared, writer).run(reader, shutdown_read))
+        };
+
+        MockDaemon {
+            shared,
+            writer,
+            shutdown: Some(shutdown_write),
+            thread: Some(thread),
+            _lock: lock,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    requests: Mutex<Vec<Request>>,
+}
+
+/// A fake LightDM daemon answering a greeter over the `LIGHTDM_*_FD` pipes.
+///
+/// Dropping it stops the server thread and removes the variables from the environment. The
+/// greeter ends of the pipes belong to the greeter, as they would in a real greeter process, and
+/// are left open.
+#[derive(Debug)]
+pub struct MockDaemon {
+    shared: Arc<Shared>,
+    writer: Arc<Mutex<File>>,
+    shutdown: Option<OwnedFd>,
+    thread: Option<JoinHandle<()>>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl MockDaemon {
+    pub fn builder() -> MockDaemonBuilder {
+        MockDaemonBuilder::default()
+    }
+
+    /// Every message received from the greeter so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.shared.requests.lock().unwrap().clone()
+    }
+
+    /// Sends the `idle` signal to the greeter.
+    pub fn idle(&self) {
+        self.send(MessageBuilder::new(ServerMessage::Idle as u32).finish());
+    }
+
+    /// Sends `reset` with a new set of hints, as when a locked session is shown again.
+    pub fn reset(&self, hints: &[(&str, &str)]) {
+        let message = hints.iter().fold(
+            MessageBuilder::new(ServerMessage::Reset as u32),
+            |message, (name, value)| message.string(Some(name)).string(Some(value)),
+        );
+        self.send(message.finish());
+    }
+
+    fn send(&self, message: Vec<u8>) {
+        let _ = self.writer.lock().unwrap().write_all(&message);
+    }
+}
+
+impl Drop for MockDaemon {
+    fn drop(&mut self) {
+        // SAFETY: see `MockDaemonBuilder::start`; the lock is still held.
+        unsafe {
+            std::env::remove_var("LIGHTDM_TO_SERVER_FD");
+            std::env::remove_var("LIGHTDM_FROM_SERVER_FD");
+        }
+        drop(self.shutdown.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn pipe() -> [RawFd; 2] {
+    let mut fds = [0; 2];
+    let ret = unsafe { libc::pipe(fds.as_mut_ptr()) };
+    assert_eq!(ret, 0, "pipe() failed: {}", std::io::Error::last_os_error());
+    fds
+}
+
+struct Authentication {
+    sequence_number: u32,
+    username: String,
+    steps: std::vec::IntoIter<Step>,
+    expected: Option<String>,
+    succeeds: bool,
+    asking_username: bool,
+}
+
+struct Server {
+    config: MockDaemonBuilder,
+    shared: Arc<Shared>,
+    writer: Arc<Mutex<File>>,
+    authentication: Option<Authentication>,
+    authenticated: bool,
+}
+
+impl Server {
+    fn new(config: MockDaemonBuilder, shared: Arc<Shared>, writer: Arc<Mutex<File>>) -> Self {
+        Self {
+            config,
+            shared,
+            writer,
+            authentication: None,
+            authenticated: false,
+        }
+    }
+
+    fn run(mut self, mut reader: File, shutdown: OwnedFd) {
+        loop {
+            let mut fds = [
+                libc::pollfd {
+                    fd: reader.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+                libc::pollfd {
+                    fd: shutdown.as_raw_fd(),
+                    events: libc::POLLIN,
+                    revents: 0,
+                },
+            ];
+            let ret = unsafe { libc::poll(fds.as_mut_ptr(), 2, -1) };
+            if ret < 0 && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted
+            {
+                continue;
+            }
+            if ret < 0 || fds[1].revents != 0 {
+                return;
+            }
+            let Ok((id, data)) = protocol::read_message(&mut reader) else {
+                return;
+            };
+            let Some(request) = parse_request(id, &data) else {
+                continue;
+            };
+            self.shared.requests.lock().unwrap().push(request.clone());
+            self.handle(request);
+        }
+    }
+
+    fn send(&self, message: Vec<u8>) {
+        let _ = self.writer.lock().unwrap().write_all(&message);
+    }
+
+    fn handle(&mut self, request: Request) {
+        match request {
+            Request::Connect { .. } => {
+                let message = self.config.hints.iter().fold(
+                    MessageBuilder::new(ServerMessage::ConnectedV2 as u32)
+                        .string(Some(env!("CARGO_PKG_VERSION")))
+                        .int(protocol::API_VERSION)
+                        .int(self.config.hints.len() as u32),
+                    |message, (name, value)| message.string(Some(name)).string(Some(value)),
+                );
+                self.send(message.finish());
+            }
+            Request::Authenticate {
+                sequence_number,
+                username,
+            } => {
+                self.authenticated = false;
+                match username.filter(|username| !username.is_empty()) {
+                    Some(username) => self.begin(sequence_number, username),
+                    None => {
+                        self.authentication = Some(Authentication {
+                            sequence_number,
+                            username: String::new(),
+                            steps: Vec::new().into_iter(),
+                            expected: None,
+                            succeeds: false,
+                            asking_username: true,
+                        });
+                        self.prompt(
+                            sequence_number,
+                            "",
+                            vec![(protocol::PAM_PROMPT_ECHO_ON, "login:".to_owned())],
+                        );
+                    }
+                }
+            }
+            Request::AuthenticateAsGuest { sequence_number } => {
+                let allowed = self
+                    .config
+                    .hints
+                    .iter()
+                    .any(|(name, value)| name == "has-guest-account" && value == "true");
+                self.authentication = None;
+                self.end(sequence_number, "", allowed);
+            }
+            Request::AuthenticateRemote {
+                sequence_number, ..
+            } => {
+                self.authentication = None;
+                self.end(sequence_number, "", false);
+            }
+            Request::ContinueAuthentication { responses } => self.answer(responses),
+            Request::CancelAuthentication => {
+                if let Some(auth) = self.authentication.take() {
+                    self.send(
+                        MessageBuilder::new(ServerMessage::EndAuthentication as u32)
+                            .int(auth.sequence_number)
+                            .string(Some(&auth.username))
+                            .int(PAM_CONV_ERR)
+                            .finish(),
+                    );
+                }
+            }
+            Request::StartSession { .. } => {
+                let code = if self.authenticated && !self.config.session_fails {
+                    0
+                } else {
+                    1
+                };
+                self.send(
+                    MessageBuilder::new(ServerMessage::SessionResult as u32)
+                        .int(code)
+                        .finish(),
+                );
+            }
+            Request::EnsureSharedDir { username } => {
+                let dir = self
+                    .config
+                    .shared_dir
+                    .as_ref()
+                    .map(|dir| format!("{dir}/{username}"));
+                self.send(
+                    MessageBuilder::new(ServerMessage::SharedDirResult as u32)
+                        .string(dir.as_deref())
+                        .finish(),
+                );
+            }
+            Request::SetLanguage { .. } | Request::SetResettable { .. } => {}
+        }
+    }
+
+    fn begin(&mut self, sequence_number: u32, username: String) {
+        let Some(conversation) = self.config.users.get(&username).cloned() else {
+            self.authentication = None;
+            self.send(
+                MessageBuilder::new(ServerMessage::EndAuthentication as u32)
+                    .int(sequence_number)
+                    .string(Some(&username))
+                    .int(PAM_USER_UNKNOWN)
+                    .finish(),
+            );
+            return;
+        };
+        self.authentication = Some(Authentication {
+            sequence_number,
+            username,
+            steps: conversation.steps.into_iter(),
+            expected: None,
+            succeeds: conversation.succeeds,
+            asking_username: false,
+        });
+        self.advance();
+    }
+
+    /// Sends messages up to and including the next prompt, or ends the conversation.
+    fn advance(&mut self) {
+        let Some(auth) = self.authentication.as_mut() else {
+            return;
+        };
+        let mut messages = Vec::new();
+        for step in auth.steps.by_ref() {
+            match step {
+                Step::Info(text) | Step::PromptInfo(text) => {
+                    messages.push((protocol::PAM_TEXT_INFO, text))
+                }
+                Step::Error(text) => messages.push((protocol::PAM_ERROR_MSG, text)),
+                Step::Prompt {
+                    text,
+                    secret,
+                    expected,
+                } => {
+                    let style = if secret {
+                        protocol::PAM_PROMPT_ECHO_OFF
+                    } else {
+                        protocol::PAM_PROMPT_ECHO_ON
+                    };
+                    messages.push((style, text));
+                    while let Some(Step::PromptInfo(text)) = auth.steps.as_slice().first().cloned()
+                    {
+                        messages.push((protocol::PAM_TEXT_INFO, text));
+                        auth.steps.next();
+                    }
+                    auth.expected = Some(expected);
+                    let (sequence_number, username) = (auth.sequence_number, auth.username.clone());
+                    self.prompt(sequence_number, &username, messages);
+                    return;
+                }
+            }
+        }
+        let auth = self.authentication.take().unwrap();
+        if !messages.is_empty() {
+            self.prompt(auth.sequence_number, &auth.username, messages);
+        }
+        self.end(auth.sequence_number, &auth.username, auth.succeeds);
+    }
+
+    fn answer(&mut self, responses: Vec<String>) {
+        let Some(auth) = self.authentication.as_mut() else {
+            return;
+        };
+        if auth.asking_username {
+            let username = responses.into_iter().next().unwrap_or_default();
+            let sequence_number = auth.sequence_number;
+            self.begin(sequence_number, username);
+            return;
+        }
+        let expected = auth.expected.take();
+        if expected.is_some() && responses.first() == expected.as_ref() {
+            self.advance();
+        } else {
+            let auth = self.authentication.take().unwrap();
+            self.send(
+                MessageBuilder::new(ServerMessage::EndAuthentication as u32)
+                    .int(auth.sequence_number)
+                    .string(Some(&auth.username))
+                    .int(PAM_AUTH_ERR)
+                    .finish(),
+            );
+        }
+    }
+
+    fn prompt(&self, sequence_number: u32, username: &str, messages: Vec<(u32, String)>) {
+        let message = messages.iter().fold(
+            MessageBuilder::new(ServerMessage::PromptAuthentication as u32)
+                .int(sequence_number)
+                .string(Some(username))
+                .int(messages.len() as u32),
+            |message, (style, text)| message.int(*style).string(Some(text)),
+        );
+        self.send(message.finish());
+    }
+
+    fn end(&mut self, sequence_number: u32, username: &str, succeeded: bool) {
+        self.authenticated = succeeded;
+        self.send(
+            MessageBuilder::new(ServerMessage::EndAuthentication as u32)
+                .int(sequence_number)
+                .string(Some(username))
+                .int(if succeeded {
+                    protocol::PAM_SUCCESS
+                } else {
+                    PAM_AUTH_ERR
+                })
+                .finish(),
+        );
+    }
+}
+
+fn parse_request(id: u32, data: &[u8]) -> Option<Request> {
+    let mut payload = Payload::new(data);
+    let optional = |value: String| (!value.is_empty()).then_some(value);
+    let request = match GreeterMessage::from_id(id)? {
+        GreeterMessage::Connect => Request::Connect {
+            version: payload.string().ok()?,
+        },
+        GreeterMessage::Authenticate => Request::Authenticate {
+            sequence_number: payload.int().ok()?,
+            username: optional(payload.string().ok()?),
+        },
+        GreeterMessage::AuthenticateAsGuest => Request::AuthenticateAsGuest {
+            sequence_number: payload.int().ok()?,
+        },
+        GreeterMessage::ContinueAuthentication => {
+            let n_responses = payload.int().ok()?;
+            let responses = (0..n_responses)
+                .map(|_| payload.string().ok())
+                .collect::<Option<_>>()?;
+            Request::ContinueAuthentication { responses }
+        }
+        GreeterMessage::StartSession => Request::StartSession {
+            session: optional(payload.string().ok()?),
+        },
+        GreeterMessage::CancelAuthentication => Request::CancelAuthentication,
+        GreeterMessage::SetLanguage => Request::SetLanguage {
+            language: payload.string().ok()?,
+        },
+        GreeterMessage::AuthenticateRemote => Request::AuthenticateRemote {
+            sequence_number: payload.int().ok()?,
+            session: payload.string().ok()?,
+            username: optional(payload.string().ok()?),
+        },
+        GreeterMessage::EnsureSharedDir => Request::EnsureSharedDir {
+            username: payload.string().ok()?,
+        },
+        GreeterMessage::SetResettable => Request::SetResettable {
+            resettable: payload.int().ok()? != 0,
+        },
+    };
+    Some(request)
+}
+
+/// Iterates the thread-default main context until `condition` holds or `timeout` elapses.
+///
+/// Returns whether the condition was met. Greeter signals are only delivered while the main
+/// context runs, so tests use this to wait for prompts and authentication results.
+pub fn iterate_until(timeout: Duration, mut condition: impl FnMut() -> bool) -> bool {
+    let context = glib::MainContext::ref_thread_default();
+    let deadline = Instant::now() + timeout;
+    while !condition() {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        if !context.iteration(false) {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+    true
+}
@@ -0,0 +1,37 @@
+use glib::{object::IsA, prelude::ObjectExt};
+
+use super::{
+    Error, User,
+    accounts::{Accounts, AccountsUser},
+    avatar::{Avatar, AvatarCache, AvatarLoader},
+};
+
+const AVATAR_CACHE_KEY: &str = "lightdm-rs-avatar-cache";
+
+pub trait UserExtManual: IsA<User> + 'static {
+    /// The picture of the user, see [`AvatarLoader`] for the files tried and the checks made.
+    ///
+    /// The picture is kept on the user until its `image` property changes.
+    fn avatar(&self) -> Option<Avatar> {
+        let user = self.as_ref();
+        // SAFETY: the data is only ever set to an `AvatarCache`, and never replaced or removed.
+        let cache = unsafe {
+            user.data::<AvatarCache>(AVATAR_CACHE_KEY)
+                .map(|cache| cache.as_ref().clone())
+        };
+        let cache = cache.unwrap_or_else(|| {
+            let cache = AvatarCache::new(AvatarLoader::new());
+            // SAFETY: see above.
+            unsafe { user.set_data(AVATAR_CACHE_KEY, cache.clone()) };
+            cache
+        });
+        cache.get(user)
+    }
+
+    /// The AccountsService user of the same uid, with the details `User` does not have.
+    fn accounts_user_sync(&self, accounts: &Accounts) -> Result<AccountsUser, Error> {
+        accounts.user_sync(self)
+    }
+}
+
+impl<O: IsA<User>> UserExtManual for O {}
@@ -0,0 +1,518 @@
+//! Reader for the XKB registry (`evdev.xml`, `base.xml`), the catalog behind
+//! `functions::layouts()`.
+//!
+//! Unlike `Layout`, an [`XkbLayout`] keeps its variants nested under it, along with the ISO 639
+//! language and ISO 3166 country codes of each. [`LayoutRef::lightdm_name`] gives the
+//! `layout\tvariant` name liblightdm uses, so entries can be mapped back to the `Layout`
+//! accepted by `functions::set_layout`.
+
+#[cfg(feature = "liblightdm")]
+use glib::object::IsA;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "liblightdm")]
+use crate::{Language, Layout, functions, locale, prelude::LayoutExt};
+use crate::{
+    config::Error,
+    locale::{LocaleCode, LocaleNames},
+};
+
+/// The fields shared by layouts, variants and options.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigItem {
+    pub name: String,
+    pub short_description: Option<String>,
+    pub description: String,
+    /// ISO 3166 codes, uppercase.
+    pub countries: Vec<String>,
+    /// ISO 639-3 codes such as `deu`.
+    pub languages: Vec<String>,
+    /// Marked `popularity="exotic"`.
+    pub exotic: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XkbLayout {
+    pub item: ConfigItem,
+    pub variants: Vec<XkbVariant>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XkbVariant {
+    pub item: ConfigItem,
+}
+
+/// A group of XKB options such as `grp` (layout switching).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptionGroup {
+    pub item: ConfigItem,
+    /// Whether several options of the group may be set at once.
+    pub allow_multiple: bool,
+    pub options: Vec<ConfigItem>,
+}
+
+/// A layout, or one of its variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutRef<'a> {
+    pub layout: &'a XkbLayout,
+    pub variant: Option<&'a XkbVariant>,
+}
+
+impl<'a> LayoutRef<'a> {
+    fn item(&self) -> &'a ConfigItem {
+        self.variant
+            .map_or(&self.layout.item, |variant| &variant.item)
+    }
+
+    /// The name of the matching `Layout`: `us` or `us\tintl`.
+    pub fn lightdm_name(&self) -> String {
+        match self.variant {
+            Some(variant) => format!("{}\t{}", self.layout.item.name, variant.item.name),
+            None => self.layout.item.name.clone(),
+        }
+    }
+
+    pub fn description(&self) -> &'a str {
+        &self.item().description
+    }
+
+    pub fn short_description(&self) -> Option<&'a str> {
+        self.item()
+            .short_description
+            .as_deref()
+            .or(self.layout.item.short_description.as_deref())
+    }
+
+    /// The languages of the variant, or of the layout if the variant lists none.
+    pub fn languages(&self) -> &'a [String] {
+        match self.variant {
+            Some(variant) if !variant.item.languages.is_empty() => &variant.item.languages,
+            _ => &self.layout.item.languages,
+        }
+    }
+
+    /// The countries of the variant, or of the layout if the variant lists none.
+    pub fn countries(&self) -> &'a [String] {
+        match self.variant {
+            Some(variant) if !variant.item.countries.is_empty() => &variant.item.countries,
+            _ => &self.layout.item.countries,
+        }
+    }
+
+    /// The `Layout` of this entry among `functions::layouts()`.
+    #[cfg(feature = "liblightdm")]
+    pub fn to_layout(&self) -> Option<Layout> {
+        let name = self.lightdm_name();
+        functions::layouts()
+            .into_iter()
+            .find(|layout| layout.name().as_deref() == Some(name.as_str()))
+    }
+}
+
+/// The layouts and option groups of an XKB ruleset.
+#[derive(Debug, Clone, Default)]
+pub struct XkbRegistry {
+    layouts: Vec<XkbLayout>,
+    option_groups: Vec<OptionGroup>,
+}
+
+impl XkbRegistry {
+    /// Reads the `evdev` ruleset of `/usr/share/X11/xkb/rules`, falling back to `base`.
+    pub fn load() -> Result<Self, Error> {
+        let rules = Path::new("/usr/share/X11/xkb/rules");
+        match Self::load_from(rules, "evdev") {
+            Err(Error::Io { source, .. }) if source.kind() == std::io::ErrorKind::NotFound => {
+                Self::load_from(rules, "base")
+            }
+            result => result,
+        }
+    }
+
+    /// Reads `<ruleset>.xml` of `rules_dir`, and `<ruleset>.extras.xml` if there is one.
+    pub fn load_from(rules_dir: &Path, ruleset: &str) -> Result<Self, Error> {
+        let mut registry = Self::default();
+        let path = rules_dir.join(format!("{ruleset}.xml"));
+        registry.merge(&read(&path)?, &path)?;
+        let extras = rules_dir.join(format!("{ruleset}.extras.xml"));
+        match read(&extras) {
+            Ok(text) => registry.merge(&text, &extras)?,
+            Err(Error::Io { source, .. }) if source.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        Ok(registry)
+    }
+
+    /// Parses the XML of a registry; `path` is only used in errors.
+    pub fn parse(text: &str, path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let mut registry = Self::default();
+        registry.merge(text, &path.into())?;
+        Ok(registry)
+    }
+
+    /// Adds the entries of another registry file. Variants of a layout which is already known
+    /// are appended to it.
+    fn merge(&mut self, text: &str, path: &Path) -> Result<(), Error> {
+        let root = Element::parse(text, path)?;
+        for element in root
+            .children("layoutList")
+            .flat_map(|list| list.children("layout"))
+        {
+            let layout = XkbLayout {
+                item: ConfigItem::from_element(element),
+                variants: element
+                    .children("variantList")
+                    .flat_map(|list| list.children("variant"))
+                    .map(|variant| XkbVariant {
+                        item: ConfigItem::from_element(variant),
+                    })
+                    .collect(),
+            };
+            match self
+                .layouts
+                .iter_mut()
+                .find(|other| other.item.name == layout.item.name)
+            {
+                Some(other) => other.variants.extend(layout.variants),
+                None => self.layouts.push(layout),
+            }
+        }
+        for element in root
+            .children("optionList")
+            .flat_map(|list| list.children("group"))
+        {
+            self.option_groups.push(OptionGroup {
+                item: ConfigItem::from_element(element),
+                allow_multiple: element.attribute("allowMultipleSelection") == Some("true"),
+                options: element
+                    .children("option")
+                    .map(ConfigItem::from_element)
+                    .collect(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn layouts(&self) -> &[XkbLayout] {
+        &self.layouts
+    }
+
+    pub fn option_groups(&self) -> &[OptionGroup] {
+        &self.option_groups
+    }
+
+    /// Every layout followed by its variants.
+    pub fn entries(&self) -> impl Iterator<Item = LayoutRef<'_>> {
+        self.layouts.iter().flat_map(|layout| {
+            std::iter::once(LayoutRef {
+                layout,
+                variant: None,
+            })
+            .chain(layout.variants.iter().map(move |variant| LayoutRef {
+                layout,
+                variant: Some(variant),
+            }))
+        })
+    }
+
+    /// The entry for a liblightdm layout name such as `de\tnodeadkeys`.
+    pub fn find(&self, lightdm_name: &str) -> Option<LayoutRef<'_>> {
+        let (name, variant) = match lightdm_name.split_once('\t') {
+            Some((name, variant)) => (name, Some(variant)),
+            None => (lightdm_name, None),
+        };
+        let layout = self
+            .layouts
+            .iter()
+            .find(|layout| layout.item.name == name)?;
+        let variant = match variant {
+            Some(variant) => Some(
+                layout
+                    .variants
+                    .iter()
+                    .find(|other| other.item.name == variant)?,
+            ),
+            None => None,
+        };
+        Some(LayoutRef { layout, variant })
+    }
+
+    /// The entry for a `Layout` of `functions::layouts()`.
+    #[cfg(feature = "liblightdm")]
+    pub fn find_layout(&self, layout: &impl IsA<Layout>) -> Option<LayoutRef<'_>> {
+        self.find(&layout.as_ref().name()?)
+    }
+
+    /// The entries whose name or description contains `query`, ignoring case.
+    pub fn search(&self, query: &str) -> Vec<LayoutRef<'_>> {
+        let query = query.to_lowercase();
+        self.entries()
+            .filter(|entry| {
+                entry.lightdm_name().to_lowercase().contains(&query)
+                    || entry.description().to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    /// The entries for the language of `code`, best first: those also made for its territory,
+    /// then plain layouts before variants, then non-exotic ones.
+    pub fn for_locale(&self, code: &LocaleCode, names: &LocaleNames) -> Vec<LayoutRef<'_>> {
+        let language = names.alpha_3(&code.language);
+        let mut entries = self
+            .entries()
+            .filter(|entry| entry.languages().iter().any(|other| other == language))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| {
+            let in_territory = code
+                .territory
+                .as_ref()
+                .is_some_and(|territory| entry.countries().contains(territory));
+            (!in_territory, entry.variant.is_some(), entry.item().exotic)
+        });
+        entries
+    }
+
+    /// The entries for the language of a `Language` of `functions::languages()`.
+    #[cfg(feature = "liblightdm")]
+    pub fn for_language(
+        &self,
+        language: &impl IsA<Language>,
+        names: &LocaleNames,
+    ) -> Vec<LayoutRef<'_>> {
+        match locale::language_code(language) {
+            Some(code) => self.for_locale(&code, names),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Normalises the spellings of a layout with variant found in user settings (`de\tnodeadkeys`,
+/// `de nodeadkeys`, `de+nodeadkeys` and `de(nodeadkeys)`) to liblightdm's `de\tnodeadkeys`.
+pub fn lightdm_layout_name(name: &str) -> String {
+    let name = name.trim();
+    if let Some((layout, variant)) = name.strip_suffix(')').and_then(|name| name.split_once('(')) {
+        return format!("{}\t{}", layout.trim(), variant.trim());
+    }
+    match name.split_once(['\t', ' ', '+']) {
+        Some((layout, variant)) if !variant.trim().is_empty() => {
+            format!("{layout}\t{}", variant.trim())
+        }
+        Some((layout, _)) => layout.to_owned(),
+        None => name.to_owned(),
+    }
+}
+
+/// The layouts of a user, as returned by
+/// [`apply_user_layouts`](crate::prelude::GreeterExtManual::apply_user_layouts).
+#[cfg(feature = "liblightdm")]
+#[derive(Debug, Clone, Default)]
+pub struct UserLayouts {
+    /// In the user's order, without duplicates.
+    pub layouts: Vec<Layout>,
+    /// The names which match none of `functions::layouts()`.
+    pub unresolved: Vec<String>,
+}
+
+/// Looks `names` up among `functions::layouts()`, accepting the spellings of
+/// [`lightdm_layout_name`].
+#[cfg(feature = "liblightdm")]
+pub fn resolve_layouts(names: impl IntoIterator<Item = impl AsRef<str>>) -> UserLayouts {
+    let available = functions::layouts();
+    let mut resolved = UserLayouts::default();
+    for name in names {
+        let wanted = lightdm_layout_name(name.as_ref());
+        match available
+            .iter()
+            .find(|layout| layout.name().as_deref() == Some(wanted.as_str()))
+        {
+            Some(layout) if !resolved.layouts.contains(layout) => {
+                resolved.layouts.push(layout.clone())
+            }
+            Some(_) => {}
+            None => resolved.unresolved.push(name.as_ref().to_owned()),
+        }
+    }
+    resolved
+}
+
+impl ConfigItem {
+    fn from_element(element: &Element) -> Self {
+        let Some(item) = element.children("configItem").next() else {
+            return Self::default();
+        };
+        let codes = |list: &str, code: &str| {
+            item.children(list)
+                .flat_map(|list| list.children(code))
+                .map(|element| element.text.trim().to_owned())
+                .collect()
+        };
+        Self {
+            name: item.child_text("name").unwrap_or_default(),
+            short_description: item.child_text("shortDescription"),
+            description: item.child_text("description").unwrap_or_default(),
+            countries: codes("countryList", "iso3166Id"),
+            languages: codes("languageList", "iso639Id"),
+            exotic: item.attribute("popularity") == Some("exotic"),
+        }
+    }
+}
+
+fn read(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|err| Error::Io {
+        path: path.to_owned(),
+        source: err,
+    })
+}
+
+/// Just enough of an XML parser for the registry: elements, attributes, text and the five
+/// predefined and numeric entities. Declarations, comments and the DOCTYPE are skipped.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    fn child_text(&self, name: &str) -> Option<String> {
+        let text = self.children(name).next()?.text.trim();
+        (!text.is_empty()).then(|| text.to_owned())
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parses a document, returning its root element.
+    fn parse(text: &str, path: &Path) -> Result<Self, Error> {
+        let error = |offset: usize, message: &str| Error::Parse {
+            path: path.to_owned(),
+            line: text[..offset].matches('\n').count() + 1,
+            message: message.to_owned(),
+        };
+        let mut stack = vec![Element::default()];
+        let mut position = 0;
+        while let Some(start) = text[position..].find('<').map(|start| position + start) {
+            let content = &text[position..start];
+            let parent = stack.last_mut().unwrap();
+            parent
+                .text
+                .push_str(&unescape(content).ok_or_else(|| error(position, "Invalid entity"))?);
+            let rest = &text[start..];
+            let skip = [
+                ("<?", "?>"),
+                ("<!--", "-->"),
+                ("<![CDATA[", "]]>"),
+                ("<!", ">"),
+            ]
+            .into_iter()
+            .find(|(open, _)| rest.starts_with(open));
+            if let Some((open, close)) = skip {
+                let end = rest
+                    .find(close)
+                    .ok_or_else(|| error(start, "Unterminated markup"))?;
+                if open == "<![CDATA[" {
+                    parent.text.push_str(&rest[open.len()..end]);
+                }
+                position = start + end + close.len();
+                continue;
+            }
+            let end = rest
+                .find('>')
+                .ok_or_else(|| error(start, "Unterminated tag"))?;
+            let tag = &rest[1..end];
+            position = start + end + 1;
+            if let Some(name) = tag.strip_prefix('/') {
+                let element = stack.pop().filter(|element| element.name == name.trim());
+                let element = element.ok_or_else(|| error(start, "Mismatched closing tag"))?;
+                stack
+                    .last_mut()
+                    .ok_or_else(|| error(start, "Unexpected closing tag"))?
+                    .children
+                    .push(element);
+                continue;
+            }
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = Self::open_tag(tag).ok_or_else(|| error(start, "Invalid tag"))?;
+            if empty {
+                stack.last_mut().unwrap().children.push(element);
+            } else {
+                stack.push(element);
+            }
+        }
+        if stack.len() != 1 {
+            return Err(error(text.len(), "Unclosed element"));
+        }
+        let mut document = stack.pop().unwrap();
+        document
+            .children
+            .pop()
+            .ok_or_else(|| error(text.len(), "No root element"))
+    }
+
+    fn open_tag(tag: &str) -> Option<Self> {
+        let tag = tag.trim();
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let mut element = Element {
+            name: tag[..name_end].to_owned(),
+            ..Element::default()
+        };
+        if element.name.is_empty() {
+            return None;
+        }
+        let mut rest = tag[name_end..].trim_start();
+        while !rest.is_empty() {
+            let (key, value) = rest.split_once('=')?;
+            let value = value.trim_start();
+            let quote = value.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+            let end = value[1..].find(quote)? + 1;
+            element
+                .attributes
+                .push((key.trim().to_owned(), unescape(&value[1..end])?));
+            rest = value[end + 1..].trim_start();
+        }
+        Some(element)
+    }
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find(';')? + start;
+        let entity = &rest[start + 1..end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => entity.strip_prefix('#')?.parse().ok()?,
+                };
+                char::from_u32(code)?
+            }
+        };
+        result.push(c);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Some(result)
+}
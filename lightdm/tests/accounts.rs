@@ -0,0 +1,237 @@
+//! Runs the `lightdm::accounts` proxies against a stub AccountsService on a private
+//! `dbus-daemon`.
+
+mod common;
+
+use gio::{DBusConnection, DBusNodeInfo, prelude::*};
+use glib::{Variant, variant::ObjectPath};
+use lightdm::accounts::{
+    ACCOUNTS_BUS_NAME, ACCOUNTS_OBJECT_PATH, AccountType, Accounts, LockedReason,
+    PasswordExpirationPolicy, PasswordMode,
+};
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, atomic::AtomicUsize, atomic::Ordering},
+};
+
+use common::{PrivateBus, iterate_until, with_context};
+
+const ALICE: &str = "/org/freedesktop/Accounts/User1000";
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.freedesktop.Accounts">
+    <method name="FindUserById">
+      <arg direction="in" type="x"/>
+      <arg direction="out" type="o"/>
+    </method>
+    <method name="FindUserByName">
+      <arg direction="in" type="s"/>
+      <arg direction="out" type="o"/>
+    </method>
+  </interface>
+  <interface name="org.freedesktop.Accounts.User">
+    <property name="Uid" type="t" access="read"/>
+    <property name="UserName" type="s" access="read"/>
+    <property name="RealName" type="s" access="read"/>
+    <property name="AccountType" type="i" access="read"/>
+    <property name="LoginTime" type="x" access="read"/>
+    <property name="PasswordMode" type="i" access="read"/>
+    <property name="Email" type="s" access="read"/>
+    <property name="Locked" type="b" access="read"/>
+    <method name="GetPasswordExpirationPolicy">
+      <arg direction="out" type="x"/>
+      <arg direction="out" type="x"/>
+      <arg direction="out" type="x"/>
+      <arg direction="out" type="x"/>
+      <arg direction="out" type="x"/>
+      <arg direction="out" type="x"/>
+    </method>
+    <signal name="Changed"/>
+  </interface>
+</node>
+"#;
+
+type Properties = Arc<Mutex<HashMap<&'static str, Variant>>>;
+
+/// A stub AccountsService knowing alice (uid 1000), with her properties in `properties`.
+struct StubAccounts {
+    bus: PrivateBus,
+    properties: Properties,
+    service: Arc<Mutex<Option<DBusConnection>>>,
+}
+
+impl StubAccounts {
+    fn start() -> Option<Self> {
+        let properties = Arc::new(Mutex::new(HashMap::from([
+            ("Uid", 1000u64.to_variant()),
+            ("UserName", "alice".to_variant()),
+            ("RealName", "Alice".to_variant()),
+            ("AccountType", 1i32.to_variant()),
+            ("LoginTime", 1_700_000_000i64.to_variant()),
+            ("PasswordMode", 0i32.to_variant()),
+            ("Email", "".to_variant()),
+            ("Locked", false.to_variant()),
+        ])));
+        let service = Arc::new(Mutex::new(None));
+        let bus = PrivateBus::start({
+            let properties = properties.clone();
+            let service = service.clone();
+            move |connection| {
+                serve(connection, properties);
+                *service.lock().unwrap() = Some(connection.clone());
+            }
+        })?;
+        Some(Self {
+            bus,
+            properties,
+            service,
+        })
+    }
+
+    /// Changes a property of alice the way AccountsService does, without `PropertiesChanged`.
+    fn change(&self, name: &'static str, value: Variant) {
+        self.properties.lock().unwrap().insert(name, value);
+        let service = self.service.lock().unwrap();
+        service
+            .as_ref()
+            .unwrap()
+            .emit_signal(
+                None,
+                ALICE,
+                "org.freedesktop.Accounts.User",
+                "Changed",
+                None,
+            )
+            .unwrap();
+    }
+}
+
+fn serve(connection: &DBusConnection, properties: Properties) {
+    let node = DBusNodeInfo::for_xml(INTROSPECTION).unwrap();
+    connection
+        .register_object(
+            ACCOUNTS_OBJECT_PATH,
+            &node.lookup_interface("org.freedesktop.Accounts").unwrap(),
+        )
+        .method_call(|_, _, _, _, method, parameters, invocation| {
+            let known = match method {
+                "FindUserById" => parameters.get::<(i64,)>() == Some((1000,)),
+                _ => parameters.get::<(String,)>() == Some(("alice".to_owned(),)),
+            };
+            if known {
+                let path = ObjectPath::try_from(ALICE.to_owned()).unwrap();
+                invocation.return_value(Some(&(path,).to_variant()));
+            } else {
+                invocation
+                    .return_dbus_error("org.freedesktop.Accounts.Error.Failed", "no such user");
+            }
+        })
+        .build()
+        .unwrap();
+    connection
+        .register_object(
+            ALICE,
+            &node
+                .lookup_interface("org.freedesktop.Accounts.User")
+                .unwrap(),
+        )
+        .method_call(|_, _, _, _, _, _, invocation| {
+            // Password changed on day 19000, valid for 90 days, locked 7 days later.
+            invocation.return_value(Some(
+                &(0i64, 19000i64, 0i64, 90i64, 7i64, 7i64).to_variant(),
+            ));
+        })
+        .property(move |_, _, _, _, name| properties.lock().unwrap()[name].clone())
+        .build()
+        .unwrap();
+    common::request_name(connection, ACCOUNTS_BUS_NAME);
+}
+
+#[test]
+fn user_properties_by_uid_and_name() {
+    let Some(stub) = StubAccounts::start() else {
+        return;
+    };
+    with_context(|context| {
+        let accounts = Accounts::new_sync(&stub.bus.connection()).unwrap();
+        let alice = accounts.find_user_by_id_sync(1000).unwrap();
+        assert_eq!(alice.object_path(), ALICE);
+        assert_eq!(alice.uid(), Some(1000));
+        assert_eq!(alice.user_name().as_deref(), Some("alice"));
+        assert_eq!(alice.real_name().as_deref(), Some("Alice"));
+        assert_eq!(alice.account_type(), Some(AccountType::Administrator));
+        assert_eq!(alice.password_mode(), Some(PasswordMode::Regular));
+        assert_eq!(alice.last_login().unwrap().to_unix(), 1_700_000_000);
+        assert_eq!(alice.email(), None);
+        assert!(!alice.is_locked());
+        // The password expired in 2022.
+        assert_eq!(
+            alice.locked_reason_sync().unwrap(),
+            Some(LockedReason::PasswordExpired)
+        );
+
+        let by_name = accounts.find_user_by_name_sync("alice").unwrap();
+        assert_eq!(by_name.object_path(), ALICE);
+        assert!(accounts.find_user_by_id_sync(1001).is_err());
+
+        let future = context
+            .block_on(accounts.find_user_by_id_future(1000))
+            .unwrap();
+        assert_eq!(future.user_name().as_deref(), Some("alice"));
+    });
+}
+
+#[test]
+fn changed_signal_reloads_properties() {
+    let Some(stub) = StubAccounts::start() else {
+        return;
+    };
+    with_context(|context| {
+        let accounts = Accounts::new_sync(&stub.bus.connection()).unwrap();
+        let alice = accounts.find_user_by_id_sync(1000).unwrap();
+        let changes = Arc::new(AtomicUsize::new(0));
+        alice.connect_changed({
+            let changes = changes.clone();
+            move |_| {
+                changes.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+
+        stub.change("Email", "alice@example.org".to_variant());
+        stub.change("Locked", true.to_variant());
+        assert!(iterate_until(context, || changes.load(Ordering::SeqCst) >= 2));
+        assert_eq!(changes.load(Ordering::SeqCst), 2);
+        assert_eq!(alice.email().as_deref(), Some("alice@example.org"));
+        assert!(alice.is_locked());
+        assert_eq!(
+            alice.locked_reason_sync().unwrap(),
+            Some(LockedReason::Locked)
+        );
+    });
+}
+
+#[test]
+fn expiration_policy() {
+    let policy = |expiration_time, last_change_time, max, inactive| PasswordExpirationPolicy {
+        expiration_time,
+        last_change_time,
+        min_days_between_changes: 0,
+        max_days_between_changes: max,
+        days_to_warn: 7,
+        days_after_expiration_until_lock: inactive,
+    };
+    assert_eq!(policy(-1, 100, -1, -1).locked_reason(1000), None);
+    assert_eq!(
+        policy(500, 100, -1, -1).locked_reason(1000),
+        Some(LockedReason::AccountExpired)
+    );
+    assert_eq!(policy(-1, 100, 90, 7).locked_reason(196), None);
+    assert_eq!(
+        policy(-1, 100, 90, 7).locked_reason(197),
+        Some(LockedReason::PasswordExpired)
+    );
+    // Expired passwords without a grace period only have to be changed.
+    assert_eq!(policy(-1, 100, 90, -1).locked_reason(1000), None);
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "testing")]
+
+use lightdm::{
+    Greeter, MessageType, PromptType, Secret,
+    auth::{AuthState, Authenticator, Error},
+    prelude::*,
+    testing::{Conversation, MockDaemon, Request, iterate_until},
+};
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn authenticator() -> Authenticator {
+    let greeter = Greeter::new();
+    greeter.connect_to_daemon_sync().unwrap();
+    Authenticator::new(greeter)
+}
+
+fn awaiting_response(auth: &Authenticator) -> bool {
+    matches!(auth.state(), AuthState::AwaitingResponse(..))
+}
+
+#[test]
+fn calls_out_of_turn_are_refused() {
+    let daemon = MockDaemon::builder()
+        .user("alice", Conversation::new().secret("Password: ", "hunter2"))
+        .start();
+    let auth = authenticator();
+
+    assert!(matches!(auth.respond("alice"), Err(Error::NoPendingPrompt)));
+    assert!(matches!(auth.cancel(), Err(Error::NotInProgress)));
+    assert!(matches!(
+        auth.start_session(None),
+        Err(Error::NotAuthenticated)
+    ));
+
+    auth.authenticate(Some("alice")).unwrap();
+    assert!(iterate_until(TIMEOUT, || awaiting_response(&auth)));
+    assert!(matches!(
+        auth.respond("hunter2"),
+        Err(Error::SecretRequired)
+    ));
+    assert_eq!(
+        auth.state(),
+        AuthState::AwaitingResponse("Password: ".to_owned(), PromptType::Secret)
+    );
+    assert!(
+        !daemon
+            .requests()
+            .iter()
+            .any(|request| matches!(request, Request::ContinueAuthentication { .. }))
+    );
+
+    auth.cancel().unwrap();
+    assert_eq!(auth.state(), AuthState::Cancelled);
+    assert!(matches!(auth.cancel(), Err(Error::NotInProgress)));
+}
+
+#[test]
+fn messages_are_kept_until_a_prompt_is_pending() {
+    let _daemon = MockDaemon::builder()
+        .user(
+            "alice",
+            Conversation::new()
+                .info("Welcome")
+                .question("Token: ", "123456")
+                .prompt_info("Caps Lock is on")
+                .error("Password expires soon"),
+        )
+        .start();
+    let auth = authenticator();
+    let states = Rc::new(RefCell::new(Vec::new()));
+    auth.connect_state_changed({
+        let states = states.clone();
+        move |state| states.borrow_mut().push(state.clone())
+    });
+
+    auth.authenticate(Some("alice")).unwrap();
+    assert!(iterate_until(TIMEOUT, || awaiting_response(&auth)));
+    auth.respond("123456").unwrap();
+    assert!(iterate_until(TIMEOUT, || !auth.state().is_in_progress()));
+
+    assert_eq!(
+        *states.borrow(),
+        [
+            AuthState::Messages(Vec::new()),
+            AuthState::Messages(vec![("Welcome".to_owned(), MessageType::Info)]),
+            AuthState::AwaitingResponse("Token: ".to_owned(), PromptType::Question),
+            AuthState::Messages(Vec::new()),
+            AuthState::Messages(vec![(
+                "Password expires soon".to_owned(),
+                MessageType::Error
+            )]),
+            AuthState::Succeeded(Some("alice".to_owned())),
+        ]
+    );
+}
+
+#[test]
+fn session_starts_on_success() {
+    let daemon = MockDaemon::builder()
+        .user("alice", Conversation::new().secret("Password: ", "hunter2"))
+        .start();
+    let auth = authenticator();
+    let result = Rc::new(RefCell::new(None));
+    auth.start_session_on_success(Some("plasma"), {
+        let result = result.clone();
+        move |res| *result.borrow_mut() = Some(res)
+    });
+
+    auth.authenticate(Some("alice")).unwrap();
+    assert!(iterate_until(TIMEOUT, || awaiting_response(&auth)));
+    auth.respond_secret(&Secret::from("hunter2")).unwrap();
+    assert!(iterate_until(TIMEOUT, || result.borrow().is_some()));
+    assert!(matches!(*result.borrow(), Some(Ok(()))));
+    assert_eq!(auth.state(), AuthState::Succeeded(Some("alice".to_owned())));
+    assert!(daemon.requests().contains(&Request::StartSession {
+        session: Some("plasma".to_owned())
+    }));
+}
@@ -0,0 +1,137 @@
+use gio::prelude::*;
+use lightdm::avatar::{AvatarError, AvatarLoader, ImageFormat};
+
+use std::{
+    fs,
+    os::unix::fs::{MetadataExt, chown, symlink},
+    path::Path,
+};
+
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+fn write(path: &Path, data: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, data).unwrap();
+}
+
+#[test]
+fn formats_are_sniffed() {
+    assert_eq!(ImageFormat::sniff(PNG), Some(ImageFormat::Png));
+    assert_eq!(
+        ImageFormat::sniff(b"\xff\xd8\xff\xe0JFIF"),
+        Some(ImageFormat::Jpeg)
+    );
+    assert_eq!(ImageFormat::sniff(b"GIF89a"), Some(ImageFormat::Gif));
+    assert_eq!(
+        ImageFormat::sniff(b"RIFF\0\0\0\0WEBPVP8 "),
+        Some(ImageFormat::Webp)
+    );
+    assert_eq!(
+        ImageFormat::sniff(b"\xef\xbb\xbf <?xml version=\"1.0\"?>\n<svg/>"),
+        Some(ImageFormat::Svg)
+    );
+    assert_eq!(ImageFormat::sniff(b"#!/bin/sh\n"), None);
+    assert_eq!(ImageFormat::Svg.mime_type(), "image/svg+xml");
+}
+
+#[test]
+fn fallback_chain() {
+    let root = tempfile::tempdir().unwrap();
+    let icons = root.path().join("icons");
+    let home = root.path().join("home/alice");
+    let default = root.path().join("default.png");
+    write(&default, PNG);
+    let uid = fs::metadata(&default).unwrap().uid();
+    let loader = AvatarLoader::new()
+        .accounts_service_icons(&icons)
+        .default_image(&default);
+
+    assert_eq!(
+        loader.candidates("alice", Some(&home), None),
+        [
+            icons.join("alice"),
+            home.join(".face"),
+            home.join(".face.icon"),
+            default.clone(),
+        ]
+    );
+    let avatar = loader.load("alice", uid, Some(&home), None).unwrap();
+    assert_eq!(avatar.path(), fs::canonicalize(&default).unwrap());
+
+    write(&home.join(".face.icon"), b"GIF87a");
+    let avatar = loader.load("alice", uid, Some(&home), None).unwrap();
+    assert_eq!(avatar.format(), ImageFormat::Gif);
+
+    // Not an image, so skipped.
+    write(&home.join(".face"), b"hello");
+    write(&icons.join("alice"), PNG);
+    let avatar = loader.load("alice", uid, Some(&home), None).unwrap();
+    assert_eq!(
+        avatar.path(),
+        fs::canonicalize(icons.join("alice")).unwrap()
+    );
+    assert_eq!(&avatar.bytes()[..], PNG);
+    assert_eq!(
+        avatar.file().path().unwrap(),
+        fs::canonicalize(icons.join("alice")).unwrap()
+    );
+}
+
+#[test]
+fn unsafe_files_are_refused() {
+    let root = tempfile::tempdir().unwrap();
+    let icons = root.path().join("icons");
+    let loader = AvatarLoader::new()
+        .accounts_service_icons(&icons)
+        .max_size(64);
+    let home = root.path().join("alice");
+
+    let large = home.join("large.png");
+    write(&large, &[PNG, &[0; 64]].concat());
+    let uid = fs::metadata(&large).unwrap().uid();
+    assert!(matches!(
+        loader.check(&large, "alice", uid, Some(&home), None),
+        Err(AvatarError::TooLarge { size: 80, .. })
+    ));
+    assert!(matches!(
+        loader.check(&home, "alice", uid, Some(&home), None),
+        Err(AvatarError::NotAFile(_))
+    ));
+
+    // A picture in the home of alice which belongs to someone else. As root, hand the file to
+    // someone else; otherwise ask for the picture of someone else.
+    let icon = home.join(".face.icon");
+    write(&icon, PNG);
+    let alice = if uid == 0 {
+        chown(&icon, Some(4242), None).unwrap();
+        1000
+    } else {
+        uid + 1
+    };
+    assert!(matches!(
+        loader.check(&icon, "alice", alice, Some(&home), None),
+        Err(AvatarError::ForeignOwner { .. })
+    ));
+
+    // A `~/.face` pointing at the AccountsService icon of bob, which root owns, or at a file
+    // outside the home of alice.
+    let face = home.join(".face");
+    write(&icons.join("bob"), PNG);
+    symlink(icons.join("bob"), &face).unwrap();
+    assert!(matches!(
+        loader.check(&face, "alice", alice, Some(&home), Some(&face)),
+        Err(AvatarError::Outside(_))
+    ));
+    fs::remove_file(&face).unwrap();
+    symlink("/etc/passwd", &face).unwrap();
+    assert!(matches!(
+        loader.check(&face, "alice", alice, Some(&home), None),
+        Err(AvatarError::Outside(_))
+    ));
+    assert!(loader.load("alice", alice, Some(&home), None).is_none());
+
+    // The AccountsService icon of alice herself is fine.
+    write(&icons.join("alice"), PNG);
+    let own = loader.load("alice", uid, Some(&home), None).unwrap();
+    assert_eq!(own.path(), fs::canonicalize(icons.join("alice")).unwrap());
+}
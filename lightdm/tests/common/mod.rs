@@ -0,0 +1,137 @@
+//! A private `dbus-daemon` for running proxies against stub services, and the main context
+//! helpers the proxies need.
+
+use gio::{DBusConnection, DBusConnectionFlags, prelude::*};
+use glib::{MainContext, MainLoop};
+
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::mpsc,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A bus with services exported from a thread of their own, so that the test thread can make
+/// blocking calls.
+pub struct PrivateBus {
+    bus: Child,
+    address: String,
+    main_loop: MainLoop,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PrivateBus {
+    /// Starts the bus and runs `serve` on the service connection, or returns `None` when
+    /// `dbus-daemon` is not in `PATH`.
+    pub fn start(serve: impl FnOnce(&DBusConnection) + Send + 'static) -> Option<Self> {
+        let mut bus = match Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(bus) => bus,
+            Err(err) => {
+                eprintln!("skipping, cannot run dbus-daemon: {err}");
+                return None;
+            }
+        };
+        let mut address = String::new();
+        BufReader::new(bus.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        let address = address.trim().to_owned();
+
+        let (sender, receiver) = mpsc::channel();
+        let thread = std::thread::spawn({
+            let address = address.clone();
+            move || {
+                let context = MainContext::new();
+                context
+                    .with_thread_default(|| {
+                        let connection = connect(&address);
+                        serve(&connection);
+                        let main_loop = MainLoop::new(Some(&context), false);
+                        sender.send(main_loop.clone()).unwrap();
+                        main_loop.run();
+                    })
+                    .unwrap();
+            }
+        });
+        let main_loop = receiver.recv().unwrap();
+
+        Some(Self {
+            bus,
+            address,
+            main_loop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn connection(&self) -> DBusConnection {
+        connect(&self.address)
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        self.main_loop.quit();
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+        let _ = self.bus.kill();
+        let _ = self.bus.wait();
+    }
+}
+
+fn connect(address: &str) -> DBusConnection {
+    DBusConnection::for_address_sync(
+        address,
+        DBusConnectionFlags::AUTHENTICATION_CLIENT | DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+        None,
+        gio::Cancellable::NONE,
+    )
+    .unwrap()
+}
+
+/// Takes `name` on the bus of `connection`.
+pub fn request_name(connection: &DBusConnection, name: &str) {
+    connection
+        .call_sync(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "RequestName",
+            Some(&(name, 4u32).to_variant()),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+        )
+        .unwrap();
+}
+
+/// Runs `f` with a fresh thread-default context, so that proxy signals of parallel tests do
+/// not share one.
+#[allow(dead_code)]
+pub fn with_context(f: impl FnOnce(&MainContext)) {
+    let context = MainContext::new();
+    context.with_thread_default(|| f(&context)).unwrap();
+}
+
+/// Iterates `context` until `condition` holds, or returns `false` after a few seconds.
+#[allow(dead_code)]
+pub fn iterate_until(context: &MainContext, condition: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + TIMEOUT;
+    while !condition() {
+        if Instant::now() > deadline {
+            return false;
+        }
+        context.iteration(false);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    true
+}
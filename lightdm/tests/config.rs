@@ -0,0 +1,332 @@
+use glib::MainContext;
+use lightdm::config::{
+    Anchor, Config, ConfigPaths, Dimension, Error, GreeterConfigLoader, Position, UsersConfig,
+};
+
+use std::{
+    cell::RefCell,
+    fs,
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+fn write(path: &Path, text: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, text).unwrap();
+}
+
+#[test]
+fn files_override_in_lightdm_order() {
+    let root = tempfile::tempdir().unwrap();
+    let data = root.path().join("usr/share");
+    let xdg = root.path().join("etc/xdg");
+    let etc = root.path().join("etc/lightdm");
+    write(
+        &data.join("lightdm/lightdm.conf.d/50-distro.conf"),
+        "[Seat:*]\ngreeter-session=distro-greeter\nuser-session=distro\nallow-guest=false\n",
+    );
+    write(
+        &xdg.join("lightdm/lightdm.conf.d/10-xdg.conf"),
+        "[Seat:*]\nuser-session=xdg\n",
+    );
+    write(
+        &etc.join("lightdm.conf.d/20-b.conf"),
+        "[Seat:*]\nautologin-user=bob\n",
+    );
+    write(
+        &etc.join("lightdm.conf.d/10-a.conf"),
+        "[Seat:*]\nautologin-user=alice\nautologin-user-timeout=5\n",
+    );
+    write(&etc.join("lightdm.conf"), "[Seat:*]\nuser-session=plasma\n");
+    write(&etc.join("lightdm.conf.d/README"), "not a config file");
+
+    let paths = ConfigPaths {
+        config_dir: etc.clone(),
+        data_dirs: vec![data.clone()],
+        config_dirs: vec![xdg.clone()],
+    };
+    let config = Config::load_from(&paths).unwrap();
+    assert_eq!(
+        config.sources(),
+        [
+            data.join("lightdm/lightdm.conf.d/50-distro.conf"),
+            xdg.join("lightdm/lightdm.conf.d/10-xdg.conf"),
+            etc.join("lightdm.conf.d/10-a.conf"),
+            etc.join("lightdm.conf.d/20-b.conf"),
+            etc.join("lightdm.conf"),
+        ]
+    );
+
+    let seat = config.seat("seat0");
+    assert_eq!(seat.greeter_session().as_deref(), Some("distro-greeter"));
+    assert_eq!(seat.user_session().as_deref(), Some("plasma"));
+    assert_eq!(seat.autologin_user().as_deref(), Some("bob"));
+    assert_eq!(seat.autologin_user_timeout().unwrap(), 5);
+    assert!(!seat.allow_guest().unwrap());
+    assert_eq!(
+        seat.section().location("autologin-user"),
+        Some((etc.join("lightdm.conf.d/20-b.conf").as_path(), 2))
+    );
+}
+
+#[test]
+fn seat_sections_match_globs() {
+    let config = Config::parse(
+        "[SeatDefaults]\n\
+         autologin-user=alice\n\
+         greeter-hide-users=true\n\
+         [Seat:seat?]\n\
+         greeter-session=slick-greeter\n\
+         [Seat:seat1]\n\
+         autologin-user=bob\n",
+        "lightdm.conf",
+    )
+    .unwrap();
+
+    assert_eq!(
+        config.seat_patterns().collect::<Vec<_>>(),
+        ["*", "seat?", "seat1"]
+    );
+
+    let seat0 = config.seat("seat0");
+    assert_eq!(seat0.autologin_user().as_deref(), Some("alice"));
+    assert_eq!(seat0.greeter_session().as_deref(), Some("slick-greeter"));
+    assert!(seat0.greeter_hide_users().unwrap());
+
+    let seat1 = config.seat("seat1");
+    assert_eq!(seat1.autologin_user().as_deref(), Some("bob"));
+
+    let remote = config.seat("remote-seat");
+    assert_eq!(remote.autologin_user().as_deref(), Some("alice"));
+    assert_eq!(
+        remote.greeter_session().as_deref(),
+        Some("example-gtk-gnome")
+    );
+}
+
+#[test]
+fn builtin_defaults_apply() {
+    let config = Config::parse("", "lightdm.conf").unwrap();
+    let seat = config.seat("seat0");
+    assert_eq!(seat.seat_type().as_deref(), Some("local"));
+    assert!(seat.allow_guest().unwrap());
+    assert!(seat.allow_user_switching().unwrap());
+    assert!(!seat.autologin_guest().unwrap());
+    assert_eq!(seat.autologin_user(), None);
+    assert_eq!(seat.greeter_session().as_deref(), Some("example-gtk-gnome"));
+    assert_eq!(seat.user_session().as_deref(), Some("default"));
+    assert_eq!(seat.section().location("type"), None);
+    assert_eq!(config.minimum_vt().unwrap(), 7);
+    assert_eq!(config.greeter_user().as_deref(), Some("lightdm"));
+    assert_eq!(
+        config.sessions_directories(),
+        [
+            Path::new("/usr/share/lightdm/sessions"),
+            Path::new("/usr/share/xsessions"),
+            Path::new("/usr/share/wayland-sessions"),
+        ]
+    );
+    assert_eq!(*config.users(), UsersConfig::default());
+}
+
+#[test]
+fn errors_carry_line_numbers() {
+    let err = Config::parse("# comment\n[LightDM]\nminimum-vt\n", "bad.conf").unwrap_err();
+    assert!(matches!(err, Error::Parse { line: 3, .. }), "{err:?}");
+    assert_eq!(
+        err.to_string(),
+        "bad.conf:3: Expected key=value, found \"minimum-vt\""
+    );
+
+    let err = Config::parse("autologin-user=alice\n", "bad.conf").unwrap_err();
+    assert!(matches!(err, Error::Parse { line: 1, .. }), "{err:?}");
+
+    let err = Config::parse("[Seat:*\n", "bad.conf").unwrap_err();
+    assert!(matches!(err, Error::Parse { line: 1, .. }), "{err:?}");
+
+    let config = Config::parse("[Seat:*]\n\nallow-guest=maybe\n", "lightdm.conf").unwrap();
+    let err = config.seat("seat0").allow_guest().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "lightdm.conf:3: [Seat:seat0] allow-guest: expected a boolean, found \"maybe\""
+    );
+}
+
+#[test]
+fn users_and_keys_conf() {
+    let etc = tempfile::tempdir().unwrap();
+    write(
+        &etc.path().join("users.conf"),
+        "[UserList]\nminimum-uid=1000\nhidden-users=nobody  guest\n",
+    );
+    write(&etc.path().join("keys.conf"), "[keys]\nhost1=secret\\s1\n");
+
+    let config = Config::load_from(&ConfigPaths::with_config_dir(etc.path())).unwrap();
+    assert!(config.sources().is_empty());
+    assert_eq!(
+        *config.users(),
+        UsersConfig {
+            minimum_uid: 1000,
+            hidden_users: vec!["nobody".into(), "guest".into()],
+            ..UsersConfig::default()
+        }
+    );
+    assert_eq!(
+        config.keys().get("host1").map(String::as_str),
+        Some("secret 1")
+    );
+
+    write(
+        &etc.path().join("users.conf"),
+        "[UserList]\nminimum-uid=-1\n",
+    );
+    let err = Config::load_from(&ConfigPaths::with_config_dir(etc.path())).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::InvalidValue {
+                location: Some((_, 2)),
+                ..
+            }
+        ),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn greeter_config_merges_conf_d() {
+    let root = tempfile::tempdir().unwrap();
+    let data = root.path().join("usr/share");
+    let etc = root.path().join("etc/lightdm");
+    write(
+        &data.join("lightdm/slick-greeter.conf.d/50-distro.conf"),
+        "[greeter]\ntheme-name=Yaru\nbackground=/usr/share/backgrounds/warty.png\n",
+    );
+    write(
+        &etc.join("slick-greeter.conf.d/90-site.conf"),
+        "[greeter]\nindicators=~host;~spacer;~clock\nposition=-20%,end 50%\n",
+    );
+    write(
+        &etc.join("slick-greeter.conf"),
+        "[greeter]\ntheme-name=Adwaita\nuser-background=false\n",
+    );
+
+    let paths = ConfigPaths {
+        config_dir: etc.clone(),
+        data_dirs: vec![data.clone()],
+        config_dirs: Vec::new(),
+    };
+    let config = GreeterConfigLoader::new("slick-greeter")
+        .paths(paths)
+        .default("greeter", "font-name", "Ubuntu 11")
+        .default("greeter", "theme-name", "HighContrast")
+        .load()
+        .unwrap();
+    assert_eq!(
+        config.sources(),
+        [
+            data.join("lightdm/slick-greeter.conf.d/50-distro.conf"),
+            etc.join("slick-greeter.conf.d/90-site.conf"),
+            etc.join("slick-greeter.conf"),
+        ]
+    );
+    assert_eq!(config.theme_name().as_deref(), Some("Adwaita"));
+    assert_eq!(config.font_name().as_deref(), Some("Ubuntu 11"));
+    assert_eq!(
+        config.background().as_deref(),
+        Some("/usr/share/backgrounds/warty.png")
+    );
+    assert_eq!(config.user_background().unwrap(), Some(false));
+    assert_eq!(config.hide_user_image().unwrap(), None);
+    assert_eq!(config.indicators(), ["~host", "~spacer", "~clock"]);
+
+    let position = config.position().unwrap().unwrap();
+    assert_eq!(
+        position,
+        Position {
+            x: Dimension {
+                value: -20,
+                percentage: true,
+                anchor: Anchor::End,
+            },
+            y: Dimension {
+                value: 50,
+                percentage: true,
+                anchor: Anchor::Center,
+            },
+        }
+    );
+    assert_eq!(position.x.resolve(1000, 300), 500);
+    assert_eq!(position.y.resolve(800, 200), 300);
+}
+
+#[test]
+fn greeter_config_rejects_bad_position() {
+    let etc = tempfile::tempdir().unwrap();
+    write(
+        &etc.path().join("gtk-greeter.conf"),
+        "[greeter]\nposition=left\n",
+    );
+    let config = GreeterConfigLoader::new("gtk-greeter")
+        .paths(ConfigPaths::with_config_dir(etc.path()))
+        .load()
+        .unwrap();
+    let err = config.position().unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::InvalidValue {
+                location: Some((_, 2)),
+                ..
+            }
+        ),
+        "{err:?}"
+    );
+    assert_eq!("10".parse::<Position>().unwrap().y.value, 10);
+    assert!("1 2 3".parse::<Position>().is_err());
+}
+
+#[test]
+fn greeter_config_watcher_reloads() {
+    let etc = tempfile::tempdir().unwrap();
+    let main = etc.path().join("gtk-greeter.conf");
+    write(&main, "[greeter]\ntheme-name=Adwaita\n");
+    fs::create_dir(etc.path().join("gtk-greeter.conf.d")).unwrap();
+
+    let context = MainContext::new();
+    context
+        .with_thread_default(|| {
+            let loader = GreeterConfigLoader::new("gtk-greeter")
+                .paths(ConfigPaths::with_config_dir(etc.path()));
+            let themes = Rc::new(RefCell::new(Vec::new()));
+            let _watcher = loader
+                .watch({
+                    let themes = themes.clone();
+                    move |config| themes.borrow_mut().push(config.unwrap().theme_name())
+                })
+                .unwrap();
+
+            write(
+                &etc.path().join("gtk-greeter.conf.d/10-dark.conf"),
+                "[greeter]\nicon-theme-name=Papirus\n",
+            );
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while themes.borrow().is_empty() && Instant::now() < deadline {
+                context.iteration(false);
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            assert_eq!(themes.borrow().last(), Some(&Some("Adwaita".to_owned())));
+
+            write(&main, "[greeter]\ntheme-name=Yaru\n");
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while themes.borrow().last() != Some(&Some("Yaru".to_owned()))
+                && Instant::now() < deadline
+            {
+                context.iteration(false);
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            assert_eq!(themes.borrow().last(), Some(&Some("Yaru".to_owned())));
+        })
+        .unwrap();
+}
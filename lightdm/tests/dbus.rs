@@ -0,0 +1,284 @@
+//! Runs the `lightdm::dbus` proxies against a stub service on a private `dbus-daemon`.
+//!
+//! The tests are skipped when `dbus-daemon` is not in `PATH`.
+
+mod common;
+
+use gio::{DBusConnection, DBusNodeInfo, prelude::*};
+use glib::{Variant, variant::ObjectPath};
+use lightdm::dbus::{BUS_NAME, DisplayManager, OBJECT_PATH, Seat, Session};
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use common::{PrivateBus, iterate_until, with_context};
+
+const SEAT0: &str = "/org/freedesktop/DisplayManager/Seat0";
+const SESSION0: &str = "/org/freedesktop/DisplayManager/Session0";
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.freedesktop.DisplayManager">
+    <property name="Seats" type="ao" access="read"/>
+    <property name="Sessions" type="ao" access="read"/>
+    <method name="AddSeat">
+      <arg name="type" direction="in" type="s"/>
+      <arg name="properties" direction="in" type="a(ss)"/>
+      <arg name="seat" direction="out" type="o"/>
+    </method>
+    <method name="AddLocalXSeat">
+      <arg name="display_number" direction="in" type="i"/>
+      <arg name="seat" direction="out" type="o"/>
+    </method>
+    <signal name="SeatAdded"><arg name="seat" type="o"/></signal>
+    <signal name="SeatRemoved"><arg name="seat" type="o"/></signal>
+  </interface>
+  <interface name="org.freedesktop.DisplayManager.Seat">
+    <property name="CanSwitch" type="b" access="read"/>
+    <property name="HasGuestAccount" type="b" access="read"/>
+    <property name="Sessions" type="ao" access="read"/>
+    <method name="SwitchToGreeter"/>
+    <method name="SwitchToUser">
+      <arg name="username" direction="in" type="s"/>
+      <arg name="session_name" direction="in" type="s"/>
+    </method>
+    <method name="SwitchToGuest">
+      <arg name="session_name" direction="in" type="s"/>
+    </method>
+    <method name="Lock"/>
+  </interface>
+  <interface name="org.freedesktop.DisplayManager.Session">
+    <property name="Seat" type="o" access="read"/>
+    <property name="UserName" type="s" access="read"/>
+    <method name="Lock"/>
+  </interface>
+</node>
+"#;
+
+fn object_path(path: &str) -> ObjectPath {
+    ObjectPath::try_from(path.to_owned()).unwrap()
+}
+
+fn paths(paths: &[String]) -> Variant {
+    paths
+        .iter()
+        .map(|path| object_path(path))
+        .collect::<Vec<_>>()
+        .to_variant()
+}
+
+/// A private bus with a stub LightDM daemon owning [`BUS_NAME`] on it.
+struct StubDaemon {
+    bus: PrivateBus,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl StubDaemon {
+    fn start() -> Option<Self> {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let bus = PrivateBus::start({
+            let calls = calls.clone();
+            move |connection| serve(connection, calls)
+        })?;
+        Some(Self { bus, calls })
+    }
+
+    fn connection(&self) -> DBusConnection {
+        self.bus.connection()
+    }
+
+    fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+/// Exports the display manager, `Seat0` and `Session0` and takes the bus name.
+fn serve(connection: &DBusConnection, calls: Arc<Mutex<Vec<String>>>) {
+    let node = DBusNodeInfo::for_xml(INTROSPECTION).unwrap();
+    let seats = Rc::new(RefCell::new(vec![SEAT0.to_owned()]));
+
+    let record = move |interface: &str, method: &str, parameters: &Variant| {
+        let interface = interface.rsplit('.').next().unwrap();
+        calls
+            .lock()
+            .unwrap()
+            .push(format!("{interface}.{method}{}", parameters.print(false)));
+    };
+
+    connection
+        .register_object(
+            OBJECT_PATH,
+            &node
+                .lookup_interface("org.freedesktop.DisplayManager")
+                .unwrap(),
+        )
+        .method_call({
+            let seats = seats.clone();
+            let record = record.clone();
+            move |connection, _, _, interface, method, parameters, invocation| {
+                record(interface.unwrap(), method, &parameters);
+                let path = format!("{OBJECT_PATH}/Seat{}", seats.borrow().len());
+                seats.borrow_mut().push(path.clone());
+                connection
+                    .emit_signal(
+                        None,
+                        OBJECT_PATH,
+                        "org.freedesktop.DisplayManager",
+                        "SeatAdded",
+                        Some(&(object_path(&path),).to_variant()),
+                    )
+                    .unwrap();
+                let changed = HashMap::from([("Seats", paths(&seats.borrow()))]);
+                connection
+                    .emit_signal(
+                        None,
+                        OBJECT_PATH,
+                        "org.freedesktop.DBus.Properties",
+                        "PropertiesChanged",
+                        Some(
+                            &(
+                                "org.freedesktop.DisplayManager",
+                                changed,
+                                Vec::<String>::new(),
+                            )
+                                .to_variant(),
+                        ),
+                    )
+                    .unwrap();
+                invocation.return_value(Some(&(object_path(&path),).to_variant()));
+            }
+        })
+        .property(move |_, _, _, _, name| match name {
+            "Seats" => paths(&seats.borrow()),
+            _ => paths(&[SESSION0.to_owned()]),
+        })
+        .build()
+        .unwrap();
+
+    connection
+        .register_object(
+            SEAT0,
+            &node
+                .lookup_interface("org.freedesktop.DisplayManager.Seat")
+                .unwrap(),
+        )
+        .method_call({
+            let record = record.clone();
+            move |_, _, _, interface, method, parameters, invocation| {
+                record(interface.unwrap(), method, &parameters);
+                invocation.return_value(None);
+            }
+        })
+        .property(|_, _, _, _, name| match name {
+            "CanSwitch" => true.to_variant(),
+            "HasGuestAccount" => false.to_variant(),
+            _ => paths(&[SESSION0.to_owned()]),
+        })
+        .build()
+        .unwrap();
+
+    connection
+        .register_object(
+            SESSION0,
+            &node
+                .lookup_interface("org.freedesktop.DisplayManager.Session")
+                .unwrap(),
+        )
+        .method_call(move |_, _, _, interface, method, parameters, invocation| {
+            record(interface.unwrap(), method, &parameters);
+            invocation.return_value(None);
+        })
+        .property(|_, _, _, _, name| match name {
+            "Seat" => object_path(SEAT0).to_variant(),
+            _ => "alice".to_variant(),
+        })
+        .build()
+        .unwrap();
+
+    common::request_name(connection, BUS_NAME);
+}
+
+#[test]
+fn seat_methods_reach_the_daemon() {
+    let Some(daemon) = StubDaemon::start() else {
+        return;
+    };
+    with_context(|_| {
+        let seat = Seat::new_sync(&daemon.connection(), SEAT0).unwrap();
+        assert!(seat.can_switch());
+        assert!(!seat.has_guest_account());
+        assert_eq!(seat.sessions(), [SESSION0]);
+
+        seat.switch_to_greeter_sync().unwrap();
+        seat.switch_to_user_sync("alice", None).unwrap();
+        seat.switch_to_guest_sync(Some("xfce")).unwrap();
+        seat.lock_sync().unwrap();
+    });
+    assert_eq!(
+        daemon.calls(),
+        [
+            "Seat.SwitchToGreeter()",
+            "Seat.SwitchToUser('alice', '')",
+            "Seat.SwitchToGuest('xfce',)",
+            "Seat.Lock()",
+        ]
+    );
+}
+
+#[test]
+fn session_properties_and_lock() {
+    let Some(daemon) = StubDaemon::start() else {
+        return;
+    };
+    with_context(|context| {
+        let session = context
+            .block_on(Session::new_future(&daemon.connection(), SESSION0))
+            .unwrap();
+        assert_eq!(session.user_name().as_deref(), Some("alice"));
+        assert_eq!(session.seat().as_deref(), Some(SEAT0));
+        context.block_on(session.lock_future()).unwrap();
+    });
+    assert_eq!(daemon.calls(), ["Session.Lock()"]);
+}
+
+#[test]
+fn add_local_x_seat_emits_signals() {
+    let Some(daemon) = StubDaemon::start() else {
+        return;
+    };
+    with_context(|context| {
+        let display_manager = DisplayManager::new_sync(&daemon.connection()).unwrap();
+        assert_eq!(display_manager.seats(), [SEAT0]);
+        assert_eq!(display_manager.sessions(), [SESSION0]);
+
+        let added = Arc::new(Mutex::new(Vec::new()));
+        display_manager.connect_seat_added({
+            let added = added.clone();
+            move |_, path| added.lock().unwrap().push(path.to_owned())
+        });
+        let changed = Arc::new(Mutex::new(Vec::new()));
+        display_manager.connect_properties_changed({
+            let changed = changed.clone();
+            move |_, names| {
+                changed
+                    .lock()
+                    .unwrap()
+                    .extend(names.iter().map(|name| name.to_string()))
+            }
+        });
+
+        let seat = display_manager.add_local_x_seat_sync(1).unwrap();
+        assert_eq!(seat, "/org/freedesktop/DisplayManager/Seat1");
+        assert!(iterate_until(context, || {
+            !added.lock().unwrap().is_empty() && !changed.lock().unwrap().is_empty()
+        }));
+        assert_eq!(*added.lock().unwrap(), [seat.as_str()]);
+        assert_eq!(*changed.lock().unwrap(), ["Seats"]);
+        assert_eq!(display_manager.seats(), [SEAT0, &seat]);
+    });
+    assert_eq!(daemon.calls(), ["DisplayManager.AddLocalXSeat(1,)"]);
+}
@@ -0,0 +1,234 @@
+use lightdm::locale::{self, InvalidLocale, LocaleCode, LocaleNames};
+
+use std::{fs, path::Path};
+
+fn code(s: &str) -> LocaleCode {
+    s.parse().unwrap()
+}
+
+#[test]
+fn posix_codes_parse_and_normalise() {
+    let serbian = code("sr_RS.utf8@Latin");
+    assert_eq!(
+        serbian,
+        LocaleCode {
+            language: "sr".into(),
+            territory: Some("RS".into()),
+            codeset: Some("UTF-8".into()),
+            modifier: Some("latin".into()),
+        }
+    );
+    assert_eq!(serbian.to_string(), "sr_RS.UTF-8@latin");
+    assert_eq!(serbian.to_bcp47(), "sr-Latn-RS");
+
+    assert_eq!(
+        code("de_DE.iso885915@euro").to_string(),
+        "de_DE.ISO-8859-15@euro"
+    );
+    assert_eq!(code("en_US.UTF-8"), code("en_US.utf8"));
+    assert_eq!(code("es_419").to_bcp47(), "es-419");
+    assert!(code("POSIX").is_c());
+    assert_eq!(code("C.UTF-8").to_bcp47(), "und");
+
+    for invalid in ["", "english", "en_USA", "en_US.", "en@", "e1_US"] {
+        assert_eq!(
+            invalid.parse::<LocaleCode>(),
+            Err(InvalidLocale(invalid.to_owned())),
+            "{invalid:?}"
+        );
+    }
+}
+
+#[test]
+fn bcp47_tags_parse() {
+    assert_eq!(code("pt-BR"), code("pt_BR"));
+    assert_eq!(code("sr-Cyrl-RS"), code("sr_RS@cyrillic"));
+    assert_eq!(code("zh-Hant-TW"), code("zh_TW"));
+    assert_eq!(
+        LocaleCode::from_bcp47("en-GB-oxendict").unwrap(),
+        code("en_GB")
+    );
+}
+
+#[test]
+fn preference_lists_parse() {
+    assert_eq!(
+        locale::parse_language_list("de_CH:de::bogus-tag-1:en"),
+        [code("de_CH"), code("de"), code("en")]
+    );
+    assert_eq!(
+        locale::parse_accept_language("fr;q=0.5, de-CH, *;q=0.1, en;q=0.8, it;q=0"),
+        [code("de_CH"), code("en"), code("fr")]
+    );
+}
+
+#[test]
+fn negotiation_picks_the_best_installed_language() {
+    let available = [
+        code("de_AT.UTF-8"),
+        code("de_DE.UTF-8"),
+        code("en_GB.UTF-8"),
+        code("sr_RS.UTF-8"),
+        code("sr_RS.UTF-8@latin"),
+    ];
+
+    let best = |preferences: &str| {
+        locale::negotiate(&locale::parse_language_list(preferences), &available)
+            .map(|index| available[index].to_string())
+    };
+    assert_eq!(best("de_AT").as_deref(), Some("de_AT.UTF-8"));
+    assert_eq!(best("de").as_deref(), Some("de_DE.UTF-8"));
+    assert_eq!(best("de_CH:en").as_deref(), Some("de_DE.UTF-8"));
+    assert_eq!(best("en_US").as_deref(), Some("en_GB.UTF-8"));
+    assert_eq!(best("sr_RS@latin").as_deref(), Some("sr_RS.UTF-8@latin"));
+    assert_eq!(best("fr:it"), None);
+}
+
+#[test]
+fn negotiation_falls_back_to_the_language() {
+    let available = [
+        code("de_DE.UTF-8"),
+        code("en_US.UTF-8"),
+        code("fr_FR.UTF-8"),
+    ];
+    let best = |header: &str| {
+        locale::negotiate(&locale::parse_accept_language(header), &available)
+            .map(|index| available[index].to_string())
+    };
+    assert_eq!(best("de-CH").as_deref(), Some("de_DE.UTF-8"));
+    assert_eq!(best("en-GB, fr;q=0.5").as_deref(), Some("en_US.UTF-8"));
+    assert_eq!(best("fr-CA").as_deref(), Some("fr_FR.UTF-8"));
+
+    assert!(code("de_DE").score(&code("de_CH")) > 0);
+    assert!(code("de_CH").score(&code("de_CH")) > code("de").score(&code("de_CH")));
+    assert!(code("de").score(&code("de_CH")) > code("de_DE").score(&code("de_CH")));
+    assert_eq!(code("sr_RS@latin").score(&code("sr_RS@cyrillic")), 0);
+}
+
+fn write(path: &Path, data: impl AsRef<[u8]>) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, data).unwrap();
+}
+
+/// A little-endian gettext catalog without hash table.
+fn mo(messages: &[(&str, &str)]) -> Vec<u8> {
+    let count = messages.len() as u32;
+    let originals = 28;
+    let translations = originals + 8 * count;
+    let mut strings_offset = translations + 8 * count;
+    let mut tables = Vec::new();
+    let mut strings = Vec::new();
+    for column in [0, 1] {
+        for message in messages {
+            let text = if column == 0 { message.0 } else { message.1 };
+            tables.extend((text.len() as u32).to_le_bytes());
+            tables.extend(strings_offset.to_le_bytes());
+            strings.extend(text.as_bytes());
+            strings.push(0);
+            strings_offset += text.len() as u32 + 1;
+        }
+    }
+    let mut data = Vec::new();
+    for word in [0x950412de, 0, count, originals, translations, 0, 0] {
+        data.extend(u32::to_le_bytes(word));
+    }
+    data.extend(tables);
+    data.extend(strings);
+    data
+}
+
+#[test]
+fn names_come_from_iso_codes_and_glibc() {
+    let share = tempfile::tempdir().unwrap();
+    let root = share.path();
+    write(
+        &root.join("iso-codes/json/iso_639-3.json"),
+        r#"{"639-3": [
+            {"alpha_2": "de", "alpha_3": "deu", "name": "German", "scope": "I"},
+            {"alpha_2": "fr", "alpha_3": "fra", "name": "French", "scope": "I"},
+            {"alpha_3": "gsw", "name": "Swiss German \u00e9", "scope": "I"},
+            {"alpha_3": "xnt", "name": "Tab\tCR\rFF\fBS\b \ud801\udc37", "scope": "I"}
+        ]}"#,
+    );
+    write(
+        &root.join("iso-codes/json/iso_3166-1.json"),
+        r#"{"3166-1": [
+            {"alpha_2": "AT", "alpha_3": "AUT", "name": "Austria", "numeric": "040"},
+            {"alpha_2": "FR", "alpha_3": "FRA", "name": "France", "numeric": "250"}
+        ]}"#,
+    );
+    write(
+        &root.join("locale/fr/LC_MESSAGES/iso_639-3.mo"),
+        mo(&[
+            ("", "Content-Type: text/plain; charset=UTF-8\n"),
+            ("French", "français"),
+            ("German", "allemand"),
+        ]),
+    );
+    write(
+        &root.join("locale/de/LC_MESSAGES/iso_3166-1.mo"),
+        mo(&[("Austria", "Österreich"), ("France", "Frankreich")]),
+    );
+    write(
+        &root.join("i18n/locales/de_DE"),
+        "LC_IDENTIFICATION\nlanguage \"German\"\nEND LC_IDENTIFICATION\n\
+         LC_ADDRESS\ncountry_name \"Deutschland\"\nlang_name \"<U0044>eutsch\"\nEND LC_ADDRESS\n",
+    );
+    write(
+        &root.join("i18n/locales/de_AT"),
+        "LC_ADDRESS\ncountry_name \"<U00D6>sterreich\"\ncopy \"de_DE\"\nEND LC_ADDRESS\n",
+    );
+
+    let names = LocaleNames::load_from(root);
+    assert_eq!(names.language_name("deu"), Some("German"));
+    assert_eq!(names.language_name("gsw"), Some("Swiss German é"));
+    assert_eq!(
+        names.language_name("xnt"),
+        Some("Tab\tCR\rFF\u{c}BS\u{8} \u{10437}")
+    );
+    assert_eq!(names.territory_name("040"), Some("Austria"));
+
+    let french = code("fr_FR.UTF-8");
+    assert_eq!(
+        names.language_name_in("de", &french).as_deref(),
+        Some("allemand")
+    );
+    assert_eq!(
+        names.territory_name_in("AT", &french).as_deref(),
+        Some("Austria")
+    );
+    assert_eq!(names.language_name_in("xx", &french), None);
+
+    let austrian = code("de_AT.UTF-8");
+    assert_eq!(
+        names.native_language_name(&austrian).as_deref(),
+        Some("Deutsch")
+    );
+    assert_eq!(
+        names.native_territory_name(&austrian).as_deref(),
+        Some("Österreich")
+    );
+    assert_eq!(
+        names.native_language_name(&french).as_deref(),
+        Some("français")
+    );
+    assert_eq!(
+        names.native_territory_name(&code("de_FR")).as_deref(),
+        Some("Frankreich")
+    );
+}
+
+#[test]
+fn corrupt_iso_codes_leave_names_unresolved() {
+    let share = tempfile::tempdir().unwrap();
+    let root = share.path();
+    write(
+        &root.join("iso-codes/json/iso_639-3.json"),
+        r#"{"639-3": [{"alpha_3": "deu", "name": "German"}}]"#,
+    );
+    write(&root.join("iso-codes/json/iso_3166-1.json"), "[}");
+
+    let names = LocaleNames::load_from(root);
+    assert_eq!(names.language_name("deu"), None);
+    assert_eq!(names.territory_name("AT"), None);
+}
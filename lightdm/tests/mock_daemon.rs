@@ -0,0 +1,225 @@
+#![cfg(feature = "testing")]
+
+use futures_core::Stream;
+use lightdm::{
+    Error, Greeter, GreeterEvent, PromptType, Secret,
+    prelude::*,
+    testing::{Conversation, MockDaemon, Request, iterate_until},
+};
+
+use std::{
+    cell::RefCell,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn record_prompts(greeter: &Greeter) -> Rc<RefCell<Vec<(String, PromptType)>>> {
+    let prompts = Rc::new(RefCell::new(Vec::new()));
+    greeter.connect_show_prompt({
+        let prompts = prompts.clone();
+        move |_, text, prompt_type| prompts.borrow_mut().push((text.to_owned(), prompt_type))
+    });
+    prompts
+}
+
+fn record_completion(greeter: &Greeter) -> Rc<RefCell<bool>> {
+    let complete = Rc::new(RefCell::new(false));
+    greeter.connect_authentication_complete({
+        let complete = complete.clone();
+        move |_| *complete.borrow_mut() = true
+    });
+    complete
+}
+
+#[test]
+fn connect_reports_hints() {
+    let _daemon = MockDaemon::builder()
+        .autologin_user("alice", 10)
+        .lock_hint(true)
+        .hint("default-session", "plasma")
+        .start();
+
+    let greeter = Greeter::new();
+    greeter.connect_to_daemon_sync().unwrap();
+
+    assert!(greeter.is_lock_hint());
+    assert_eq!(greeter.autologin_user_hint().as_deref(), Some("alice"));
+    assert_eq!(greeter.autologin_timeout_hint(), 10);
+    assert_eq!(greeter.default_session_hint().as_deref(), Some("plasma"));
+    assert!(!greeter.has_guest_account_hint());
+}
+
+#[test]
+fn password_conversation_starts_session() {
+    let daemon = MockDaemon::builder()
+        .user(
+            "alice",
+            Conversation::new()
+                .info("Welcome")
+                .secret("Password: ", "hunter2"),
+        )
+        .start();
+
+    let greeter = Greeter::new();
+    let prompts = record_prompts(&greeter);
+    let complete = record_completion(&greeter);
+    greeter.connect_to_daemon_sync().unwrap();
+
+    greeter.authenticate(Some("alice")).unwrap();
+    assert!(iterate_until(TIMEOUT, || !prompts.borrow().is_empty()));
+    assert_eq!(
+        prompts.borrow()[0],
+        ("Password: ".to_owned(), PromptType::Secret)
+    );
+
+    assert!(daemon.requests().contains(&Request::Authenticate {
+        sequence_number: 1,
+        username: Some("alice".to_owned())
+    }));
+
+    greeter.respond_secret(&Secret::from("hunter2")).unwrap();
+    assert!(iterate_until(TIMEOUT, || *complete.borrow()));
+    assert!(greeter.is_authenticated());
+    assert_eq!(greeter.authentication_user().as_deref(), Some("alice"));
+
+    greeter.start_session_sync(Some("plasma")).unwrap();
+    assert!(daemon.requests().contains(&Request::StartSession {
+        session: Some("plasma".to_owned())
+    }));
+}
+
+#[test]
+fn wrong_password_fails() {
+    let _daemon = MockDaemon::builder()
+        .user("alice", Conversation::new().secret("Password: ", "hunter2"))
+        .start();
+
+    let greeter = Greeter::new();
+    let prompts = record_prompts(&greeter);
+    let complete = record_completion(&greeter);
+    greeter.connect_to_daemon_sync().unwrap();
+
+    greeter.authenticate(Some("alice")).unwrap();
+    assert!(iterate_until(TIMEOUT, || !prompts.borrow().is_empty()));
+    assert!(greeter.respond_secret(&Secret::from("hunter2\0")).is_err());
+    greeter.respond_secret(&Secret::from("letmein")).unwrap();
+    assert!(iterate_until(TIMEOUT, || *complete.borrow()));
+    assert!(!greeter.is_authenticated());
+
+    assert!(matches!(
+        greeter.start_session_sync(None),
+        Err(Error::SessionFailed(_))
+    ));
+}
+
+#[test]
+fn plain_responses_only_answer_questions() {
+    let _daemon = MockDaemon::builder()
+        .user(
+            "alice",
+            Conversation::new()
+                .question("Token: ", "123456")
+                .secret("Password: ", "hunter2"),
+        )
+        .start();
+
+    let greeter = Greeter::new();
+    let prompts = record_prompts(&greeter);
+    let complete = record_completion(&greeter);
+    greeter.connect_to_daemon_sync().unwrap();
+
+    greeter.authenticate(Some("alice")).unwrap();
+    assert!(iterate_until(TIMEOUT, || prompts.borrow().len() == 1));
+    greeter.respond("123456").unwrap();
+    assert!(iterate_until(TIMEOUT, || prompts.borrow().len() == 2));
+    assert!(matches!(
+        greeter.respond("hunter2"),
+        Err(Error::SecretPrompt)
+    ));
+    greeter.respond_secret(&Secret::from("hunter2")).unwrap();
+    assert!(iterate_until(TIMEOUT, || *complete.borrow()));
+    assert!(greeter.is_authenticated());
+}
+
+#[test]
+fn cancel_ends_authentication() {
+    let daemon = MockDaemon::builder()
+        .user("alice", Conversation::new().secret("Password: ", "hunter2"))
+        .start();
+
+    let greeter = Greeter::new();
+    let prompts = record_prompts(&greeter);
+    let complete = record_completion(&greeter);
+    greeter.connect_to_daemon_sync().unwrap();
+
+    greeter.authenticate(Some("alice")).unwrap();
+    assert!(iterate_until(TIMEOUT, || !prompts.borrow().is_empty()));
+    greeter.cancel_authentication().unwrap();
+    assert!(iterate_until(TIMEOUT, || *complete.borrow()));
+    assert!(!greeter.is_authenticated());
+    assert!(daemon.requests().contains(&Request::CancelAuthentication));
+}
+
+#[test]
+fn connect_to_daemon_calls_back() {
+    let _daemon = MockDaemon::builder().lock_hint(true).start();
+
+    let greeter = Greeter::new();
+    let result = Rc::new(RefCell::new(None));
+    greeter.connect_to_daemon(gio::Cancellable::NONE, {
+        let result = result.clone();
+        move |res| *result.borrow_mut() = Some(res)
+    });
+    assert!(iterate_until(TIMEOUT, || result.borrow().is_some()));
+    assert!(matches!(*result.borrow(), Some(Ok(()))));
+    assert!(greeter.is_lock_hint());
+}
+
+#[test]
+fn reset_notifies_changed_hints() {
+    let daemon = MockDaemon::builder()
+        .lock_hint(true)
+        .hint("default-session", "plasma")
+        .start();
+
+    let greeter = Greeter::new();
+    greeter.connect_to_daemon_sync().unwrap();
+    let notified = Rc::new(RefCell::new(Vec::new()));
+    greeter.connect_lock_hint_notify({
+        let notified = notified.clone();
+        move |_| notified.borrow_mut().push("lock-hint")
+    });
+    greeter.connect_default_session_hint_notify({
+        let notified = notified.clone();
+        move |_| notified.borrow_mut().push("default-session-hint")
+    });
+    greeter.connect_select_user_hint_notify({
+        let notified = notified.clone();
+        move |_| notified.borrow_mut().push("select-user-hint")
+    });
+    let mut events = greeter.events();
+
+    daemon.reset(&[("default-session", "plasma"), ("select-user", "bob")]);
+    assert!(iterate_until(TIMEOUT, || notified.borrow().len() == 2));
+    assert_eq!(*notified.borrow(), ["lock-hint", "select-user-hint"]);
+    assert!(!greeter.is_lock_hint());
+    assert_eq!(greeter.select_user_hint().as_deref(), Some("bob"));
+
+    let mut received = Vec::new();
+    let mut cx = Context::from_waker(Waker::noop());
+    while let Poll::Ready(Some(event)) = Pin::new(&mut events).poll_next(&mut cx) {
+        received.push(event);
+    }
+    assert_eq!(
+        received,
+        [
+            GreeterEvent::HintChanged("lock-hint"),
+            GreeterEvent::HintChanged("select-user-hint"),
+            GreeterEvent::Reset,
+        ]
+    );
+}
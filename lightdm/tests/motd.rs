@@ -0,0 +1,94 @@
+use lightdm::motd::{Color, Motd, MotdLoader, Span, Style};
+
+use std::{fs, os::unix::fs::symlink};
+
+const UBUNTU: &str = "Welcome to \x1b[1mUbuntu 24.04\x1b[0m (GNU/Linux)\r\n\
+    \x1b]0;title\x07\x1b[?25l\n \
+    * Updates: \x1b[31;1m3\x1b[22m urgent\x1b[39m & \x1b[38;5;21mmore\x1b[m <here>\n\
+    \x1b[48;2;1;2;3mrgb\x1b[0m\x08\n";
+
+#[test]
+fn parses_sgr_and_drops_other_escapes() {
+    let motd = Motd::parse(UBUNTU);
+    assert_eq!(
+        motd.to_plain(),
+        "Welcome to Ubuntu 24.04 (GNU/Linux)\n\n * Updates: 3 urgent & more <here>\nrgb\n"
+    );
+    let bold = Style {
+        bold: true,
+        ..Style::default()
+    };
+    assert_eq!(
+        motd.spans()[1],
+        Span {
+            text: "Ubuntu 24.04".to_owned(),
+            style: bold,
+        }
+    );
+    let red = motd.spans().iter().find(|span| span.text == "3").unwrap();
+    assert_eq!(red.style.foreground, Some(Color::Indexed(1)));
+    assert!(red.style.bold);
+    let urgent = motd
+        .spans()
+        .iter()
+        .find(|span| span.text == " urgent")
+        .unwrap();
+    assert!(!urgent.style.bold);
+    assert_eq!(Color::Indexed(21).to_hex(), "#0000ff");
+    assert_eq!(Color::Indexed(244).to_hex(), "#808080");
+    assert!(!motd.is_empty());
+    assert!(Motd::parse(" \x1b[1m\n\t").is_empty());
+}
+
+#[test]
+fn renders_pango_markup() {
+    let motd = Motd::parse(UBUNTU);
+    assert_eq!(
+        motd.to_markup(),
+        "Welcome to <span weight=\"bold\">Ubuntu 24.04</span> (GNU/Linux)\n\n * Updates: \
+         <span weight=\"bold\" foreground=\"#cd0000\">3</span>\
+         <span foreground=\"#cd0000\"> urgent</span> &amp; \
+         <span foreground=\"#0000ff\">more</span> &lt;here&gt;\n\
+         <span background=\"#010203\">rgb</span>\n"
+    );
+}
+
+#[test]
+fn loader_merges_dynamic_parts_and_fragments() {
+    let root = tempfile::tempdir().unwrap();
+    let path = |path: &str| root.path().join(path);
+    for dir in ["etc/motd.d", "run/motd.d", "usr/lib/motd.d"] {
+        fs::create_dir_all(path(dir)).unwrap();
+    }
+    fs::write(path("run/motd.dynamic"), "dynamic").unwrap();
+    // Ubuntu links /etc/motd to the dynamic part.
+    symlink(path("run/motd.dynamic"), path("etc/motd")).unwrap();
+    fs::write(path("usr/lib/motd.d/10-vendor"), "vendor\n").unwrap();
+    fs::write(path("usr/lib/motd.d/20-hidden"), "hidden\n").unwrap();
+    fs::write(path("etc/motd.d/20-hidden"), "admin\n").unwrap();
+    fs::write(path("run/motd.d/30-run"), "run\n").unwrap();
+
+    let loader = MotdLoader::new().root(root.path());
+    assert_eq!(loader.load().unwrap().to_plain(), "dynamic\n");
+
+    let dynamic = loader.dynamic(true);
+    assert_eq!(
+        dynamic.files().unwrap(),
+        [
+            path("run/motd.dynamic"),
+            path("usr/lib/motd.d/10-vendor"),
+            path("etc/motd.d/20-hidden"),
+            path("run/motd.d/30-run"),
+        ]
+    );
+    assert_eq!(
+        dynamic.load().unwrap().to_plain(),
+        "dynamic\nvendor\nadmin\nrun\n"
+    );
+}
+
+#[test]
+fn loader_reads_absolute_paths_by_default() {
+    let files = MotdLoader::new().dynamic(true).files().unwrap();
+    assert!(files.iter().all(|path| path.is_absolute()), "{files:?}");
+}
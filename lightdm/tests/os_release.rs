@@ -0,0 +1,88 @@
+use lightdm::{config::Error, os_release::OsRelease};
+
+use std::fs;
+
+const FEDORA: &str = r#"NAME="Fedora Linux"
+VERSION="40 (Workstation Edition)"
+ID=fedora
+ID_LIKE="rhel centos"
+VERSION_ID=40
+PRETTY_NAME="Fedora Linux 40 (Workstation Edition)"
+ANSI_COLOR="0;38;2;60;110;180"
+LOGO=fedora-logo-icon
+# a comment
+HOME_URL="https://fedoraproject.org/"
+SUPPORT_URL="https://ask.fedoraproject.org/"
+VARIANT="Workstation Edition"
+BUILD_ID='2024-04-23 "nightly"'
+IMAGE_VERSION="1.0 \"beta\" \$HOME \\ \`x\`"
+not an assignment
+"#;
+
+#[test]
+fn parses_every_key_with_quoting() {
+    let os = OsRelease::parse(FEDORA);
+    assert_eq!(os.id(), Some("fedora"));
+    assert_eq!(os.name(), Some("Fedora Linux"));
+    assert_eq!(os.version(), Some("40 (Workstation Edition)"));
+    assert_eq!(os.version_id(), Some("40"));
+    assert_eq!(
+        os.pretty_name(),
+        Some("Fedora Linux 40 (Workstation Edition)")
+    );
+    assert_eq!(os.id_like(), ["rhel", "centos"]);
+    assert_eq!(os.ansi_color(), Some("0;38;2;60;110;180"));
+    assert_eq!(os.logo(), Some("fedora-logo-icon"));
+    assert_eq!(os.home_url(), Some("https://fedoraproject.org/"));
+    assert_eq!(os.support_url(), Some("https://ask.fedoraproject.org/"));
+    assert_eq!(os.variant(), Some("Workstation Edition"));
+    assert_eq!(os.build_id(), Some(r#"2024-04-23 "nightly""#));
+    assert_eq!(os.image_version(), Some(r#"1.0 "beta" $HOME \ `x`"#));
+    assert_eq!(os.version_codename(), None);
+    assert_eq!(os.iter().count(), 13);
+}
+
+#[test]
+fn loads_the_first_existing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let usr_lib = dir.path().join("os-release");
+    fs::write(&usr_lib, "ID=arch\n").unwrap();
+    let etc = dir.path().join("missing");
+
+    let os = OsRelease::load_first([&etc, &usr_lib]).unwrap();
+    assert_eq!(os.id(), Some("arch"));
+
+    let err = OsRelease::load_first([&etc]).unwrap_err();
+    assert!(matches!(err, Error::Io { path, .. } if path == etc));
+}
+
+/// liblightdm only reads `/etc/os-release`, so this compares on the file of the test machine,
+/// when its values are plain double-quoted strings both parsers read the same way.
+#[cfg(feature = "liblightdm")]
+#[test]
+fn agrees_with_liblightdm_on_double_quoted_values() {
+    use lightdm::functions;
+
+    let Ok(text) = fs::read_to_string("/etc/os-release") else {
+        return;
+    };
+    if text.contains(['\'', '\\', '$', '`']) {
+        return;
+    }
+    let os = OsRelease::parse(&text);
+    let ours = |value: Option<&str>| value.map(str::to_owned);
+    assert_eq!(functions::os_id().map(String::from), ours(os.id()));
+    assert_eq!(functions::os_name().map(String::from), ours(os.name()));
+    assert_eq!(
+        functions::os_pretty_name().map(String::from),
+        ours(os.pretty_name())
+    );
+    assert_eq!(
+        functions::os_version().map(String::from),
+        ours(os.version())
+    );
+    assert_eq!(
+        functions::os_version_id().map(String::from),
+        ours(os.version_id())
+    );
+}
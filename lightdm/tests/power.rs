@@ -0,0 +1,185 @@
+mod common;
+
+use gio::{DBusNodeInfo, prelude::*};
+use lightdm::power::{
+    DryRun, InhibitMode, Inhibitor, LOGIND_BUS_NAME, Logind, PowerAction, PowerCapability,
+    PowerError, PowerManager,
+};
+
+use std::sync::{Arc, Mutex};
+
+use common::PrivateBus;
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.freedesktop.login1.Manager">
+    <method name="CanPowerOff"><arg direction="out" type="s"/></method>
+    <method name="CanReboot"><arg direction="out" type="s"/></method>
+    <method name="CanSuspend"><arg direction="out" type="s"/></method>
+    <method name="CanHibernate"><arg direction="out" type="s"/></method>
+    <method name="PowerOff"><arg direction="in" type="b"/></method>
+    <method name="Reboot"><arg direction="in" type="b"/></method>
+    <method name="Suspend"><arg direction="in" type="b"/></method>
+    <method name="Hibernate"><arg direction="in" type="b"/></method>
+    <method name="ListInhibitors"><arg direction="out" type="a(ssssuu)"/></method>
+  </interface>
+</node>
+"#;
+
+/// What a power menu would do: offer the allowed actions and run the chosen one.
+fn menu(manager: &dyn PowerManager) -> Vec<PowerAction> {
+    PowerAction::ALL
+        .into_iter()
+        .filter(|action| manager.can(*action))
+        .collect()
+}
+
+#[test]
+fn dry_run_records_actions() {
+    let manager = DryRun::new();
+    assert_eq!(menu(&manager), PowerAction::ALL);
+    manager.suspend().unwrap();
+    manager.shutdown().unwrap();
+    assert_eq!(
+        manager.actions(),
+        [PowerAction::Suspend, PowerAction::Shutdown]
+    );
+}
+
+#[test]
+fn dry_run_refuses_disallowed_actions() {
+    let manager = DryRun::allowing(&[PowerAction::Restart]);
+    assert_eq!(menu(&manager), [PowerAction::Restart]);
+    assert!(!manager.can_hibernate());
+    assert!(manager.hibernate().is_err());
+    manager.restart().unwrap();
+    assert_eq!(manager.actions(), [PowerAction::Restart]);
+}
+
+/// Starts a stub logind which allows powering off, needs authentication for rebooting, answers
+/// `can_suspend` for suspending, which an inhibitor blocks, and cannot hibernate.
+fn stub_logind(can_suspend: &'static str) -> Option<(PrivateBus, Arc<Mutex<Vec<String>>>)> {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let bus = PrivateBus::start({
+        let calls = calls.clone();
+        move |connection| {
+            let node = DBusNodeInfo::for_xml(INTROSPECTION).unwrap();
+            connection
+                .register_object(
+                    "/org/freedesktop/login1",
+                    &node
+                        .lookup_interface("org.freedesktop.login1.Manager")
+                        .unwrap(),
+                )
+                .method_call(move |_, _, _, _, method, parameters, invocation| {
+                    let reply = match method {
+                        "CanPowerOff" => Some(("yes",).to_variant()),
+                        "CanSuspend" => Some((can_suspend,).to_variant()),
+                        "CanReboot" => Some(("challenge",).to_variant()),
+                        "CanHibernate" => Some(("na",).to_variant()),
+                        "ListInhibitors" => Some(
+                            (vec![
+                                (
+                                    "sleep:idle",
+                                    "GNOME Software",
+                                    "Installing updates",
+                                    "block",
+                                    1000u32,
+                                    42u32,
+                                ),
+                                ("shutdown", "NetworkManager", "Teardown", "delay", 0, 7),
+                            ],)
+                                .to_variant(),
+                        ),
+                        _ => {
+                            calls
+                                .lock()
+                                .unwrap()
+                                .push(format!("{method}{}", parameters.print(false)));
+                            None
+                        }
+                    };
+                    invocation.return_value(reply.as_ref());
+                })
+                .build()
+                .unwrap();
+            common::request_name(connection, LOGIND_BUS_NAME);
+        }
+    })?;
+    Some((bus, calls))
+}
+
+#[test]
+fn logind_capabilities_and_inhibitors() {
+    let Some((bus, calls)) = stub_logind("yes") else {
+        return;
+    };
+    let logind = Logind::with_connection(&bus.connection());
+
+    let capabilities = PowerAction::ALL.map(|action| logind.capability(action));
+    assert_eq!(
+        capabilities,
+        [
+            PowerCapability::Yes,
+            PowerCapability::Challenge,
+            PowerCapability::Inhibited,
+            PowerCapability::NotAvailable,
+        ]
+    );
+    assert_eq!(menu(&logind), [PowerAction::Shutdown, PowerAction::Suspend]);
+
+    let inhibitors = logind.inhibitors().unwrap();
+    assert_eq!(
+        inhibitors[0],
+        Inhibitor {
+            what: vec!["sleep".to_owned(), "idle".to_owned()],
+            who: "GNOME Software".to_owned(),
+            why: "Installing updates".to_owned(),
+            mode: InhibitMode::Block,
+            uid: 1000,
+            pid: 42,
+        }
+    );
+    assert_eq!(inhibitors[1].mode, InhibitMode::Delay);
+    assert!(!inhibitors[1].blocks(PowerAction::Shutdown));
+    assert!(calls.lock().unwrap().is_empty());
+}
+
+#[test]
+fn logind_refused_action_is_not_inhibited() {
+    let Some((bus, _)) = stub_logind("no") else {
+        return;
+    };
+    let logind = Logind::with_connection(&bus.connection());
+
+    assert!(logind.inhibitors().unwrap()[0].blocks(PowerAction::Suspend));
+    assert_eq!(logind.capability(PowerAction::Suspend), PowerCapability::No);
+    assert!(!logind.capability(PowerAction::Suspend).is_offered());
+}
+
+#[test]
+fn logind_runs_actions_unless_inhibited() {
+    let Some((bus, calls)) = stub_logind("yes") else {
+        return;
+    };
+    let logind = Logind::with_connection(&bus.connection());
+
+    let err = logind.run_with(PowerAction::Suspend, false).unwrap_err();
+    let PowerError::Inhibited { action, inhibitors } = &err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(*action, PowerAction::Suspend);
+    assert_eq!(inhibitors.len(), 1);
+    assert_eq!(
+        err.to_string(),
+        "suspend is inhibited by GNOME Software (Installing updates)"
+    );
+
+    logind.run_with(PowerAction::Suspend, true).unwrap();
+    logind.run_with(PowerAction::Shutdown, false).unwrap();
+    logind.interactive(true).restart().unwrap();
+    assert_eq!(
+        *calls.lock().unwrap(),
+        ["Suspend(false,)", "PowerOff(false,)", "Reboot(true,)"]
+    );
+}
@@ -0,0 +1,154 @@
+use lightdm::{
+    config::{Config, Error},
+    sessions::{self, SessionCatalog, SessionSource, SessionType},
+};
+
+use std::{fs, path::Path};
+
+fn write(path: &Path, text: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, text).unwrap();
+}
+
+#[test]
+fn catalog_reads_configured_directories() {
+    let root = tempfile::tempdir().unwrap();
+    let xsessions = root.path().join("xsessions");
+    let wayland = root.path().join("wayland-sessions");
+    let remote = root.path().join("remote-sessions");
+    write(
+        &wayland.join("plasma.desktop"),
+        "[Desktop Entry]\n\
+         Name=Plasma\n\
+         Name[de]=Plasma (Wayland)\n\
+         Name[zh_Hant]=Plasma (Hant)\n\
+         Exec=/bin/sh -c startplasma-wayland\n\
+         DesktopNames=KDE;\n",
+    );
+    write(
+        &xsessions.join("plasma.desktop"),
+        "[Desktop Entry]\nName=Plasma (X11)\nExec=startplasma-x11\n",
+    );
+    write(
+        &xsessions.join("gnome.desktop"),
+        "[Desktop Entry]\n\
+         Name=GNOME\n\
+         TryExec=/nonexistent/gnome-session\n\
+         Exec=gnome-session\n\
+         DesktopNames=GNOME;ubuntu\n\
+         X-LightDM-Session-Type=mir\n",
+    );
+    write(
+        &xsessions.join("hidden.desktop"),
+        "[Desktop Entry]\nName=Hidden\nExec=/bin/sh\nHidden=true\n",
+    );
+    write(&xsessions.join("broken.desktop"), "Name=Broken\n");
+    write(
+        &remote.join("rdp.desktop"),
+        "[Desktop Entry]\nName=RDP\nComment=Remote desktop\n",
+    );
+
+    let config = Config::parse(
+        &format!(
+            "[LightDM]\nsessions-directory={}:{}\nremote-sessions-directory={}\n",
+            wayland.display(),
+            xsessions.display(),
+            remote.display()
+        ),
+        "lightdm.conf",
+    )
+    .unwrap();
+    let catalog = SessionCatalog::load(&config).unwrap();
+
+    let keys = catalog
+        .sessions()
+        .iter()
+        .map(|entry| entry.key.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, ["gnome", "hidden", "plasma"]);
+
+    let plasma = catalog.session("plasma").unwrap();
+    assert_eq!(plasma.path, wayland.join("plasma.desktop"));
+    assert_eq!(plasma.session_type, SessionType::Wayland);
+    assert_eq!(plasma.desktop_names, ["KDE"]);
+    assert_eq!(plasma.program().as_deref(), Some("/bin/sh"));
+    assert_eq!(plasma.name_for("de_DE.UTF-8"), "Plasma (Wayland)");
+    assert_eq!(plasma.name_for("fr_FR"), "Plasma");
+    assert_eq!(plasma.name_for("zh_Hant.UTF-8"), "Plasma (Hant)");
+    assert!(plasma.is_available());
+
+    let gnome = catalog.session("gnome").unwrap();
+    assert_eq!(gnome.session_type, SessionType::Mir);
+    assert_eq!(gnome.desktop_names, ["GNOME", "ubuntu"]);
+    assert!(!gnome.program_exists());
+    assert!(!catalog.session("hidden").unwrap().is_available());
+
+    assert_eq!(
+        catalog
+            .available()
+            .map(|entry| entry.key.as_str())
+            .collect::<Vec<_>>(),
+        ["plasma"]
+    );
+
+    let rdp = catalog.remote_session("rdp").unwrap();
+    assert!(rdp.remote);
+    assert_eq!(rdp.comment.as_deref(), Some("Remote desktop"));
+    assert!(rdp.is_available());
+
+    assert!(
+        matches!(catalog.errors(), [Error::Parse { line: 1, .. }]),
+        "{:?}",
+        catalog.errors()
+    );
+}
+
+#[test]
+fn missing_directories_are_empty() {
+    let catalog = SessionCatalog::load_dirs(&["/nonexistent/xsessions".into()], &[]).unwrap();
+    assert!(catalog.sessions().is_empty());
+    assert!(catalog.remote_sessions().is_empty());
+    assert!(catalog.errors().is_empty());
+}
+
+#[test]
+fn session_types_and_desktops() {
+    assert_eq!("x".parse::<SessionType>().unwrap(), SessionType::X);
+    assert_eq!(
+        SessionType::from("xremote"),
+        SessionType::Unknown("xremote".into())
+    );
+    assert_eq!(String::from(SessionType::Wayland), "wayland");
+    assert_eq!(
+        SessionType::Unknown("xremote".into()).to_string(),
+        "xremote"
+    );
+
+    assert_eq!(sessions::desktop_of("gnome-xorg"), "gnome");
+    assert_eq!(sessions::desktop_of("plasmawayland"), "plasma");
+    assert_eq!(sessions::desktop_of("ubuntu-wayland"), "ubuntu");
+    assert_eq!(sessions::desktop_of("Xfce"), "xfce");
+    assert_eq!(sessions::desktop_of("wayland"), "wayland");
+}
+
+#[test]
+fn resolve_key_skips_stale_preferences() {
+    let keys = ["gnome", "plasma", "xfce"];
+    assert_eq!(
+        sessions::resolve_key(&keys, Some("xfce"), Some("plasma"), Some("gnome")),
+        Some(("xfce", SessionSource::User))
+    );
+    assert_eq!(
+        sessions::resolve_key(&keys, Some("unity"), Some("plasma"), Some("gnome")),
+        Some(("plasma", SessionSource::Autologin))
+    );
+    assert_eq!(
+        sessions::resolve_key(&keys, None, None, Some("xfce")),
+        Some(("xfce", SessionSource::Default))
+    );
+    assert_eq!(
+        sessions::resolve_key(&keys, Some("unity"), None, Some("ubuntu")),
+        Some(("gnome", SessionSource::Fallback))
+    );
+    assert_eq!(sessions::resolve_key(&[], Some("gnome"), None, None), None);
+}
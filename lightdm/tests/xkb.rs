@@ -0,0 +1,162 @@
+use lightdm::{
+    config::Error,
+    locale::{LocaleCode, LocaleNames},
+    xkb::{self, XkbRegistry},
+};
+
+use std::fs;
+
+const EVDEV: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE xkbConfigRegistry SYSTEM "xkb.dtd">
+<xkbConfigRegistry version="1.1">
+  <modelList/>
+  <layoutList>
+    <layout>
+      <configItem>
+        <name>de</name>
+        <shortDescription>de</shortDescription>
+        <description>German</description>
+        <countryList><iso3166Id>DE</iso3166Id></countryList>
+        <languageList><iso639Id>deu</iso639Id></languageList>
+      </configItem>
+      <variantList>
+        <variant>
+          <configItem>
+            <name>nodeadkeys</name>
+            <description>German (no dead keys)</description>
+          </configItem>
+        </variant>
+        <!-- a comment -->
+        <variant>
+          <configItem popularity="exotic">
+            <name>dsb</name>
+            <description>Lower Sorbian</description>
+            <languageList><iso639Id>dsb</iso639Id></languageList>
+          </configItem>
+        </variant>
+      </variantList>
+    </layout>
+    <layout>
+      <configItem>
+        <name>at</name>
+        <shortDescription>de</shortDescription>
+        <description>German (Austria)</description>
+        <countryList><iso3166Id>AT</iso3166Id></countryList>
+        <languageList><iso639Id>deu</iso639Id></languageList>
+      </configItem>
+    </layout>
+    <layout>
+      <configItem>
+        <name>cz</name>
+        <description>Czech (with &lt;\|&gt; key)</description>
+      </configItem>
+    </layout>
+  </layoutList>
+  <optionList>
+    <group allowMultipleSelection="true">
+      <configItem>
+        <name>grp</name>
+        <description>Switching to another layout</description>
+      </configItem>
+      <option><configItem><name>grp:alt_shift_toggle</name><description>Alt+Shift</description></configItem></option>
+    </group>
+  </optionList>
+</xkbConfigRegistry>
+"#;
+
+const EXTRAS: &str = r#"<xkbConfigRegistry version="1.1">
+  <layoutList>
+    <layout>
+      <configItem><name>de</name></configItem>
+      <variantList>
+        <variant><configItem><name>hu</name><description>German (with Hungarian letters)</description></configItem></variant>
+      </variantList>
+    </layout>
+  </layoutList>
+</xkbConfigRegistry>
+"#;
+
+#[test]
+fn registry_nests_variants_and_merges_extras() {
+    let rules = tempfile::tempdir().unwrap();
+    fs::write(rules.path().join("evdev.xml"), EVDEV).unwrap();
+    fs::write(rules.path().join("evdev.extras.xml"), EXTRAS).unwrap();
+    let registry = XkbRegistry::load_from(rules.path(), "evdev").unwrap();
+
+    let names = registry
+        .entries()
+        .map(|entry| entry.lightdm_name())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["de", "de\tnodeadkeys", "de\tdsb", "de\thu", "at", "cz"]
+    );
+
+    let nodeadkeys = registry.find("de\tnodeadkeys").unwrap();
+    assert_eq!(nodeadkeys.description(), "German (no dead keys)");
+    assert_eq!(nodeadkeys.short_description(), Some("de"));
+    assert_eq!(nodeadkeys.languages(), ["deu"]);
+    assert_eq!(nodeadkeys.countries(), ["DE"]);
+    assert_eq!(registry.find("de\tdsb").unwrap().languages(), ["dsb"]);
+    assert!(registry.find("de\tneo").is_none());
+
+    assert_eq!(
+        registry.find("cz").unwrap().description(),
+        r"Czech (with <\|> key)"
+    );
+
+    let [group] = registry.option_groups() else {
+        panic!("{:?}", registry.option_groups());
+    };
+    assert_eq!(group.item.name, "grp");
+    assert!(group.allow_multiple);
+    assert_eq!(group.options[0].name, "grp:alt_shift_toggle");
+
+    let found = registry
+        .search("HUNGARIAN")
+        .iter()
+        .map(|entry| entry.lightdm_name())
+        .collect::<Vec<_>>();
+    assert_eq!(found, ["de\thu"]);
+}
+
+#[test]
+fn layouts_for_a_locale() {
+    let registry = XkbRegistry::parse(EVDEV, "evdev.xml").unwrap();
+    let share = tempfile::tempdir().unwrap();
+    fs::create_dir_all(share.path().join("iso-codes/json")).unwrap();
+    fs::write(
+        share.path().join("iso-codes/json/iso_639-3.json"),
+        r#"{"639-3": [{"alpha_2": "de", "alpha_3": "deu", "name": "German"}]}"#,
+    )
+    .unwrap();
+    let names = LocaleNames::load_from(share.path());
+
+    let austrian = "de_AT.UTF-8".parse::<LocaleCode>().unwrap();
+    let suggested = registry
+        .for_locale(&austrian, &names)
+        .iter()
+        .map(|entry| entry.lightdm_name())
+        .collect::<Vec<_>>();
+    assert_eq!(suggested, ["at", "de", "de\tnodeadkeys"]);
+}
+
+#[test]
+fn malformed_registry_reports_the_line() {
+    let err = XkbRegistry::parse("<a>\n<b>\n</a>\n", "bad.xml").unwrap_err();
+    assert!(matches!(err, Error::Parse { line: 3, .. }), "{err:?}");
+}
+
+#[test]
+fn user_layout_spellings() {
+    for name in [
+        "de\tnodeadkeys",
+        "de nodeadkeys",
+        "de+nodeadkeys",
+        "de(nodeadkeys)",
+    ] {
+        assert_eq!(xkb::lightdm_layout_name(name), "de\tnodeadkeys", "{name:?}");
+    }
+    assert_eq!(xkb::lightdm_layout_name(" us "), "us");
+    assert_eq!(xkb::lightdm_layout_name("us\t"), "us");
+}